@@ -0,0 +1,41 @@
+use std::{fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::CliError;
+
+/// Defaults shared by every subcommand, stored as JSON.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CliConfig {
+    /// Deployed escrow program id.
+    pub program_id: Option<String>,
+    /// Keypair of the caller / relayer, also the fee payer.
+    pub keypair_path: Option<String>,
+}
+
+impl CliConfig {
+    pub fn default_path() -> PathBuf {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home)
+            .join(".config")
+            .join("solana-escrow")
+            .join("cli.json")
+    }
+
+    /// Loads the config, a missing file is an empty config.
+    pub fn load(path: &PathBuf) -> Result<Self, CliError> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &PathBuf) -> Result<(), CliError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
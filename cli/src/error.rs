@@ -0,0 +1,19 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid config: {0}")]
+    Config(#[from] serde_json::Error),
+    #[error("Invalid base64: {0}")]
+    Base64(#[from] base64::DecodeError),
+    #[error("Could not serialize transaction: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("Could not sign transaction: {0}")]
+    Signer(#[from] solana_sdk::signer::SignerError),
+    #[error("Could not decode account: {0}")]
+    Program(#[from] solana_sdk::program_error::ProgramError),
+    #[error("{0}")]
+    Usage(String),
+}
@@ -0,0 +1,60 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use clap::ValueEnum;
+use solana_escrow::{
+    decoder::{self, ProgramAccount},
+    states::{
+        default_escrow::EscrowState, deposit_record::DepositRecordState,
+        escrow_registry::EscrowRegistryState, layout::AccountLayout,
+        program_config::ProgramConfigState, registry_mint::RegistryMintState,
+        sol_escrow::SolEscrowState,
+    },
+};
+use crate::error::CliError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AccountKind {
+    Escrow,
+    SolEscrow,
+    Registry,
+    DepositRecord,
+    RegistryMint,
+    ProgramConfig,
+}
+
+/// Accepts raw base64 or the JSON printed by `solana account --output json`.
+pub fn decode_dump(dump: &str) -> Result<Vec<u8>, CliError> {
+    let dump = dump.trim();
+    let encoded = match serde_json::from_str::<serde_json::Value>(dump) {
+        Ok(json) => json
+            .pointer("/account/data/0")
+            .and_then(|data| data.as_str())
+            .ok_or_else(|| CliError::Usage("JSON dump has no account.data field".to_string()))?
+            .to_string(),
+        Err(_) => dump.to_string(),
+    };
+    Ok(STANDARD.decode(encoded)?)
+}
+
+/// Decodes `data` as `kind`, or lets the program decoder tell the account
+/// type apart.
+pub fn decode(kind: Option<AccountKind>, data: &[u8]) -> Result<ProgramAccount, CliError> {
+    Ok(match kind {
+        Some(AccountKind::Escrow) => ProgramAccount::Escrow(EscrowState::unpack_versioned(data)?),
+        Some(AccountKind::SolEscrow) => {
+            ProgramAccount::SolEscrow(SolEscrowState::unpack_versioned(data)?)
+        }
+        Some(AccountKind::Registry) => {
+            ProgramAccount::Registry(EscrowRegistryState::unpack_versioned(data)?)
+        }
+        Some(AccountKind::DepositRecord) => {
+            ProgramAccount::DepositRecord(DepositRecordState::unpack_versioned(data)?)
+        }
+        Some(AccountKind::RegistryMint) => {
+            ProgramAccount::RegistryMint(RegistryMintState::unpack_versioned(data)?)
+        }
+        Some(AccountKind::ProgramConfig) => {
+            ProgramAccount::ProgramConfig(ProgramConfigState::unpack_versioned(data)?)
+        }
+        None => decoder::decode_account(data)?,
+    })
+}
@@ -0,0 +1,877 @@
+//! Offline command line client for the escrow program.
+//!
+//! Transactions are built against a blockhash fetched elsewhere and signed
+//! with the keypairs available locally, so they can be prepared on an
+//! airgapped machine and broadcast from an online one.
+
+mod config;
+mod error;
+mod inspect;
+mod offline;
+mod voucher;
+
+use std::{fs, path::PathBuf, process::exit, str::FromStr};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use solana_escrow::{
+    decoder, instruction,
+    states::{escrow_registry::EscrowRegistryState, sol_escrow::SolEscrowState},
+    utils::{
+        constants::REFERENCE_LEN, memo::EscrowMemo, time_basis::TimeBasis, voucher::Voucher,
+    },
+};
+use solana_sdk::{
+    hash::Hash, instruction::Instruction, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+    system_instruction,
+};
+
+use crate::{
+    config::CliConfig,
+    error::CliError,
+    inspect::AccountKind,
+    offline::SignerSource,
+    voucher::SignedVoucher,
+};
+
+#[derive(Parser)]
+#[command(name = "escrow", version, about = "Build, sign and inspect escrow transactions offline")]
+struct Cli {
+    /// Config file [default: ~/.config/solana-escrow/cli.json]
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create the escrow state account and initialize a token or native SOL escrow
+    Create(CreateArgs),
+    /// Release a matured escrow to its receiver
+    Release(ReleaseArgs),
+    /// Sign a voucher releasing a token escrow, as JSON for `release --voucher`
+    Voucher(VoucherArgs),
+    /// Add tokens to a token escrow as its initializer
+    Increase(IncreaseArgs),
+    /// Lower the amount, extend the expire date or change the receiver of a token escrow
+    Amend(AmendArgs),
+    /// Hand the receiver's claim on an escrow over to a new receiver
+    Assign(AssignArgs),
+    /// Refund a native SOL escrow before it matures, or a token escrow whose release window has closed
+    Refund(RefundArgs),
+    /// Create the registry state account and register a wallet escrow
+    Register(RegisterArgs),
+    /// Accept a mint in a wallet escrow registry, with its own vault
+    AddMint(MintArgs),
+    /// Stop accepting a mint without outstanding deposits and close its vault
+    RemoveMint(MintArgs),
+    /// Deposit tokens into a wallet escrow registry
+    Deposit(DepositArgs),
+    /// Withdraw a deposit from a wallet escrow registry before it matures
+    Withdraw(DepositArgs),
+    /// Collect a wallet escrow deposit as the registry owner
+    Collect(CollectArgs),
+    /// Rewrite an escrow or registry account in the current layout (upgrade authority only)
+    Migrate(MigrateArgs),
+    /// Create or change the program config (upgrade authority only)
+    SetConfig(SetConfigArgs),
+    /// Decode an escrow or registry account from a base64 dump, as JSON
+    Inspect(InspectArgs),
+    /// Print the account layout schema (configs/schema.json)
+    Schema,
+    /// Show or change the CLI config
+    #[command(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Args)]
+struct TxArgs {
+    /// Recent blockhash, fetched on an online machine
+    #[arg(long)]
+    blockhash: Hash,
+    /// Caller / relayer paying the fees: keypair file or pubkey [default: config keypair]
+    #[arg(long)]
+    fee_payer: Option<String>,
+    /// Escrow program id [default: config program id]
+    #[arg(long)]
+    program_id: Option<Pubkey>,
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum TokenProgram {
+    #[default]
+    Spl,
+    Token2022,
+}
+
+impl TokenProgram {
+    fn id(self) -> Pubkey {
+        match self {
+            Self::Spl => spl_token::id(),
+            Self::Token2022 => spl_token_2022::id(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum TimeBasisArg {
+    #[default]
+    UnixTimestamp,
+    Slot,
+    Epoch,
+}
+
+impl From<TimeBasisArg> for TimeBasis {
+    fn from(arg: TimeBasisArg) -> Self {
+        match arg {
+            TimeBasisArg::UnixTimestamp => TimeBasis::UnixTimestamp,
+            TimeBasisArg::Slot => TimeBasis::Slot,
+            TimeBasisArg::Epoch => TimeBasis::Epoch,
+        }
+    }
+}
+
+#[derive(Args)]
+struct CreateArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Initializer: keypair file, or pubkey if it signs elsewhere
+    #[arg(long)]
+    initializer: String,
+    #[arg(long)]
+    receiver: Pubkey,
+    /// New native SOL escrow state account: keypair file, or pubkey if it signs elsewhere
+    #[arg(long, required_if_eq("native", "true"))]
+    escrow: Option<String>,
+    /// Index of the new token escrow, the initializer profile's `escrow_count` + 1, or 1 for
+    /// its first escrow. The escrow lives at the address derived from it
+    #[arg(long, required_unless_present = "native", conflicts_with_all = ["native", "escrow"])]
+    escrow_index: Option<u64>,
+    #[arg(long)]
+    amount: u64,
+    /// Escrow native SOL from the initializer instead of tokens
+    #[arg(long, conflicts_with_all = ["temp_token_account", "mint"])]
+    native: bool,
+    /// Token account holding the deposit, owned by the caller
+    #[arg(long, required_unless_present = "native")]
+    temp_token_account: Option<Pubkey>,
+    #[arg(long, required_unless_present = "native")]
+    mint: Option<Pubkey>,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+    /// Clock the token escrow matures by: unix seconds, or slots and epochs,
+    /// which validators can't skew
+    #[arg(long, value_enum, default_value = "unix-timestamp", conflicts_with = "native")]
+    time_basis: TimeBasisArg,
+    /// Order ID or hash the token escrow is linked to, as 64 hex digits
+    #[arg(long, value_parser = parse_reference, conflicts_with = "native")]
+    reference: Option<[u8; REFERENCE_LEN]>,
+    /// Text stored in the token escrow, up to 64 bytes
+    #[arg(long, default_value = "", conflicts_with = "native")]
+    memo: String,
+    /// Also log the reference and memo through SPL Memo on init and release
+    #[arg(long, conflicts_with = "native")]
+    log_memo: bool,
+}
+
+#[derive(Args)]
+struct ReleaseArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    #[arg(long)]
+    escrow: Pubkey,
+    /// Release a native SOL escrow to this receiver
+    #[arg(long, conflicts_with_all = ["receiver_token_account", "temp_token_account", "initializer", "initializer_token_account", "mint"])]
+    native_receiver: Option<Pubkey>,
+    #[arg(long, required_unless_present = "native_receiver")]
+    receiver_token_account: Option<Pubkey>,
+    #[arg(long, required_unless_present = "native_receiver")]
+    temp_token_account: Option<Pubkey>,
+    #[arg(long, required_unless_present = "native_receiver")]
+    initializer: Option<Pubkey>,
+    /// Gets back the tokens held above the escrowed amount when the escrow closes
+    #[arg(long, required_unless_present = "native_receiver")]
+    initializer_token_account: Option<Pubkey>,
+    #[arg(long, required_unless_present = "native_receiver")]
+    mint: Option<Pubkey>,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+    /// Release with a voucher file printed by `voucher`
+    #[arg(long, conflicts_with = "native_receiver")]
+    voucher: Option<PathBuf>,
+    /// Pass the SPL Memo program, required when the escrow was created with `--log-memo`
+    #[arg(long, conflicts_with = "native_receiver")]
+    log_memo: bool,
+}
+
+#[derive(Args)]
+struct VoucherArgs {
+    /// Initializer, to release before maturity, or receiver: keypair file
+    #[arg(long)]
+    signer: String,
+    #[arg(long)]
+    escrow: Pubkey,
+    #[arg(long)]
+    amount: u64,
+    /// Owner of the token account receiving the tokens
+    #[arg(long)]
+    recipient: Pubkey,
+    /// Above the nonce of the last voucher used on the escrow
+    #[arg(long)]
+    nonce: u64,
+    /// Unix timestamp the voucher expires at
+    #[arg(long)]
+    expiry: i64,
+}
+
+#[derive(Args)]
+struct IncreaseArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Initializer: keypair file or pubkey
+    #[arg(long)]
+    initializer: String,
+    /// Initializer's token account the tokens move from
+    #[arg(long)]
+    token_account: Pubkey,
+    #[arg(long)]
+    escrow: Pubkey,
+    #[arg(long)]
+    temp_token_account: Pubkey,
+    #[arg(long)]
+    mint: Pubkey,
+    #[arg(long)]
+    amount: u64,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+}
+
+// The new terms are given in full, the current value of a term keeps it.
+#[derive(Args)]
+struct AmendArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Initializer: keypair file or pubkey
+    #[arg(long)]
+    initializer: String,
+    /// Current receiver: keypair file or pubkey
+    #[arg(long)]
+    receiver: String,
+    /// Initializer's token account, it gets the tokens taken off the escrow
+    #[arg(long)]
+    token_account: Pubkey,
+    #[arg(long)]
+    escrow: Pubkey,
+    #[arg(long)]
+    temp_token_account: Pubkey,
+    #[arg(long)]
+    mint: Pubkey,
+    /// New amount, at most the current one
+    #[arg(long)]
+    amount: u64,
+    /// New expire date in the escrow's time basis (unix seconds, slot or epoch), at least the current one
+    #[arg(long)]
+    expire_date: i64,
+    #[arg(long)]
+    new_receiver: Pubkey,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+}
+
+#[derive(Args)]
+struct AssignArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Current receiver: keypair file or pubkey [default: fee payer]
+    #[arg(long)]
+    receiver: Option<String>,
+    #[arg(long)]
+    escrow: Pubkey,
+    #[arg(long)]
+    new_receiver: Pubkey,
+}
+
+#[derive(Args)]
+struct RefundArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    #[arg(long)]
+    escrow: Pubkey,
+    #[arg(long)]
+    initializer: Pubkey,
+    /// Refund a token escrow to this token account of its initializer, anyone can send it
+    #[arg(long, requires_all = ["temp_token_account", "mint"])]
+    initializer_token_account: Option<Pubkey>,
+    #[arg(long)]
+    temp_token_account: Option<Pubkey>,
+    #[arg(long)]
+    mint: Option<Pubkey>,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+    /// Caller recorded on the escrow, it gets the rent back. A native SOL escrow refunded
+    /// before maturity needs it to be the fee payer [default: fee payer]
+    #[arg(long)]
+    caller: Option<Pubkey>,
+}
+
+#[derive(Args)]
+struct RegisterArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Owner of the token account: keypair file or pubkey
+    #[arg(long)]
+    initializer: String,
+    #[arg(long)]
+    token_account: Pubkey,
+    /// New registry state account: keypair file, or pubkey if it signs elsewhere
+    #[arg(long)]
+    registry: String,
+    #[arg(long)]
+    mint: Pubkey,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+}
+
+#[derive(Args)]
+struct MintArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Registry owner: keypair file or pubkey [default: fee payer]
+    #[arg(long)]
+    owner: Option<String>,
+    #[arg(long)]
+    registry: Pubkey,
+    #[arg(long)]
+    mint: Pubkey,
+    /// Vault of the mint, pass the registered token account to adopt it
+    /// [default: the vault PDA]
+    #[arg(long)]
+    vault: Option<Pubkey>,
+    /// Token account receiving tokens left in the vault (remove-mint)
+    #[arg(long)]
+    receiver_token_account: Option<Pubkey>,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+}
+
+#[derive(Args)]
+struct DepositArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Depositor: keypair file or pubkey
+    #[arg(long)]
+    depositor: String,
+    /// Depositor's token account the tokens move from or to
+    #[arg(long)]
+    token_account: Pubkey,
+    #[arg(long)]
+    registry: Pubkey,
+    /// Vault of the mint [default: the vault PDA created by add-mint]
+    #[arg(long)]
+    vault: Option<Pubkey>,
+    #[arg(long)]
+    mint: Pubkey,
+    #[arg(long)]
+    amount: u64,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+}
+
+#[derive(Args)]
+struct CollectArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Registry owner: keypair file or pubkey [default: fee payer]
+    #[arg(long)]
+    owner: Option<String>,
+    #[arg(long)]
+    receiver_token_account: Pubkey,
+    #[arg(long)]
+    registry: Pubkey,
+    /// Vault of the mint [default: the vault PDA created by add-mint]
+    #[arg(long)]
+    vault: Option<Pubkey>,
+    /// Depositor whose record is collected: pubkey, or keypair file to release it early
+    #[arg(long)]
+    depositor: String,
+    #[arg(long)]
+    mint: Pubkey,
+    #[arg(long, value_enum, default_value = "spl")]
+    token_program: TokenProgram,
+}
+
+#[derive(Args)]
+struct MigrateArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Escrow or registry account to migrate
+    #[arg(long)]
+    account: Pubkey,
+    /// Program upgrade authority: keypair file or pubkey [default: fee payer]
+    #[arg(long)]
+    authority: Option<String>,
+}
+
+#[derive(Args)]
+struct SetConfigArgs {
+    #[command(flatten)]
+    tx: TxArgs,
+    /// Token escrows an initializer can have open at once
+    #[arg(long)]
+    max_open_escrows_per_user: u64,
+    /// Program upgrade authority: keypair file or pubkey [default: fee payer]
+    #[arg(long)]
+    authority: Option<String>,
+}
+
+#[derive(Args)]
+struct InspectArgs {
+    /// Base64 account data, or a file holding it or `solana account --output json`
+    dump: String,
+    /// Account type, detected from the data when omitted
+    #[arg(long, value_enum)]
+    kind: Option<AccountKind>,
+    /// Clock sysvar dump, to estimate the wall-clock time of slot and epoch expire dates
+    #[arg(long)]
+    clock: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Print the current config
+    Get,
+    /// Update config values
+    Set {
+        #[arg(long)]
+        program_id: Option<Pubkey>,
+        #[arg(long)]
+        keypair: Option<String>,
+    },
+}
+
+/// Resolves the config defaults every transaction needs.
+struct TxContext {
+    program_id: Pubkey,
+    fee_payer: SignerSource,
+    blockhash: Hash,
+}
+
+impl TxContext {
+    fn new(args: &TxArgs, config: &CliConfig) -> Result<Self, CliError> {
+        let program_id = match (args.program_id, &config.program_id) {
+            (Some(program_id), _) => program_id,
+            (None, Some(program_id)) => Pubkey::from_str(program_id)
+                .map_err(|_| CliError::Usage(format!("Invalid program id in config: {program_id}")))?,
+            (None, None) => return Err(CliError::Usage("No --program-id given or configured".to_string())),
+        };
+        let fee_payer = match (&args.fee_payer, &config.keypair_path) {
+            (Some(fee_payer), _) | (None, Some(fee_payer)) => SignerSource::parse(fee_payer)?,
+            (None, None) => return Err(CliError::Usage("No --fee-payer given or configured".to_string())),
+        };
+
+        Ok(Self {
+            program_id,
+            fee_payer,
+            blockhash: args.blockhash,
+        })
+    }
+
+    fn finish(&self, instructions: &[Instruction], signers: &[&SignerSource]) -> Result<(), CliError> {
+        let mut all_signers = vec![&self.fee_payer];
+        all_signers.extend_from_slice(signers);
+        let transaction =
+            offline::build_transaction(instructions, &self.fee_payer.pubkey(), &all_signers, self.blockhash)?;
+        offline::print_transaction(&transaction)
+    }
+}
+
+fn create_state_account(payer: &Pubkey, account: &Pubkey, space: usize, program_id: &Pubkey) -> Instruction {
+    system_instruction::create_account(
+        payer,
+        account,
+        Rent::default().minimum_balance(space),
+        space as u64,
+        program_id,
+    )
+}
+
+fn owner_or_fee_payer(owner: &Option<String>, ctx: &TxContext) -> Result<SignerSource, CliError> {
+    match owner {
+        Some(owner) => SignerSource::parse(owner),
+        None => Ok(SignerSource::Pubkey(ctx.fee_payer.pubkey())),
+    }
+}
+
+fn vault_or_default(program_id: &Pubkey, registry: &Pubkey, mint: &Pubkey, vault: Option<Pubkey>) -> Pubkey {
+    vault.unwrap_or_else(|| instruction::find_registry_vault(program_id, registry, mint).0)
+}
+
+fn parse_reference(value: &str) -> Result<[u8; REFERENCE_LEN], String> {
+    let digits = value.as_bytes();
+    if digits.len() != REFERENCE_LEN * 2 {
+        return Err(format!("expected {} hex digits", REFERENCE_LEN * 2));
+    }
+    let mut reference = [0; REFERENCE_LEN];
+    for (byte, pair) in reference.iter_mut().zip(digits.chunks(2)) {
+        let pair = std::str::from_utf8(pair).map_err(|error| error.to_string())?;
+        *byte = u8::from_str_radix(pair, 16).map_err(|error| error.to_string())?;
+    }
+    Ok(reference)
+}
+
+/// An account dump given inline or as the file holding it.
+fn read_dump(dump: String) -> String {
+    fs::read_to_string(&dump).unwrap_or(dump)
+}
+
+fn run(cli: Cli) -> Result<(), CliError> {
+    let config_path = cli.config.unwrap_or_else(CliConfig::default_path);
+    let config = CliConfig::load(&config_path)?;
+
+    match cli.command {
+        Command::Create(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let caller = ctx.fee_payer.pubkey();
+            let initializer = SignerSource::parse(&args.initializer)?;
+
+            match (&args.escrow, args.escrow_index, args.temp_token_account, args.mint) {
+                (Some(escrow), None, _, _) => {
+                    let escrow = SignerSource::parse(escrow)?;
+                    let instructions = [
+                        create_state_account(&caller, &escrow.pubkey(), SolEscrowState::LEN, &ctx.program_id),
+                        instruction::init_sol_escrow(
+                            &ctx.program_id,
+                            &initializer.pubkey(),
+                            &args.receiver,
+                            &escrow.pubkey(),
+                            &caller,
+                            args.amount,
+                        ),
+                    ];
+                    ctx.finish(&instructions, &[&initializer, &escrow])
+                }
+                (None, Some(escrow_index), Some(temp_token_account), Some(mint)) => {
+                    let (escrow, _) =
+                        instruction::find_escrow_account(&ctx.program_id, &initializer.pubkey(), escrow_index);
+                    let instruction = instruction::init_escrow(
+                        &ctx.program_id,
+                        &initializer.pubkey(),
+                        &args.receiver,
+                        &temp_token_account,
+                        &escrow,
+                        &args.token_program.id(),
+                        &caller,
+                        &mint,
+                        args.amount,
+                        args.time_basis.into(),
+                        &EscrowMemo {
+                            reference: args.reference,
+                            memo: args.memo,
+                            log_memo: args.log_memo,
+                        },
+                    );
+                    println!("Escrow: {escrow}");
+                    ctx.finish(&[instruction], &[&initializer])
+                }
+                _ => unreachable!("clap requires the escrow, or its index and token accounts without --native"),
+            }
+        }
+        Command::Release(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let caller = ctx.fee_payer.pubkey();
+            let mut instructions = match (args.native_receiver, &args.voucher) {
+                (Some(receiver), _) => {
+                    vec![instruction::release_sol_escrow(&ctx.program_id, &receiver, &args.escrow, &caller)]
+                }
+                (None, None) => vec![instruction::release_escrow(
+                    &ctx.program_id,
+                    &args.receiver_token_account.expect("required by clap"),
+                    &args.temp_token_account.expect("required by clap"),
+                    &args.initializer.expect("required by clap"),
+                    &args.initializer_token_account.expect("required by clap"),
+                    &args.escrow,
+                    &args.token_program.id(),
+                    &caller,
+                    &args.mint.expect("required by clap"),
+                )],
+                (None, Some(path)) => {
+                    let (voucher, signer, signature) = SignedVoucher::load(path)?.parts()?;
+                    if voucher.escrow != args.escrow {
+                        return Err(CliError::Usage("The voucher is for another escrow".to_string()));
+                    }
+                    vec![
+                        instruction::ed25519_verify(&signer, &signature, &voucher),
+                        instruction::release_with_voucher(
+                            &ctx.program_id,
+                            &args.receiver_token_account.expect("required by clap"),
+                            &args.temp_token_account.expect("required by clap"),
+                            &args.initializer.expect("required by clap"),
+                            &args.initializer_token_account.expect("required by clap"),
+                            &args.escrow,
+                            &args.token_program.id(),
+                            &caller,
+                            &args.mint.expect("required by clap"),
+                            voucher,
+                        ),
+                    ]
+                }
+            };
+            if args.log_memo {
+                let release = instructions.last_mut().expect("a release instruction");
+                instruction::append_memo_program(release);
+            }
+            ctx.finish(&instructions, &[])
+        }
+        Command::Voucher(args) => {
+            let signer = SignerSource::parse(&args.signer)?;
+            let voucher = Voucher {
+                escrow: args.escrow,
+                amount: args.amount,
+                recipient: args.recipient,
+                nonce: args.nonce,
+                expiry: args.expiry,
+            };
+            let signed = SignedVoucher::sign(&voucher, &signer)?;
+            println!("{}", serde_json::to_string_pretty(&signed)?);
+            Ok(())
+        }
+        Command::Increase(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let initializer = SignerSource::parse(&args.initializer)?;
+            let instruction = instruction::increase_deposit(
+                &ctx.program_id,
+                &initializer.pubkey(),
+                &args.token_account,
+                &args.temp_token_account,
+                &args.escrow,
+                &args.token_program.id(),
+                &args.mint,
+                args.amount,
+            );
+            ctx.finish(&[instruction], &[&initializer])
+        }
+        Command::Amend(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let initializer = SignerSource::parse(&args.initializer)?;
+            let receiver = SignerSource::parse(&args.receiver)?;
+            let instruction = instruction::amend_escrow(
+                &ctx.program_id,
+                &initializer.pubkey(),
+                &receiver.pubkey(),
+                &args.token_account,
+                &args.temp_token_account,
+                &args.escrow,
+                &args.token_program.id(),
+                &args.mint,
+                args.amount,
+                args.expire_date,
+                &args.new_receiver,
+            );
+            ctx.finish(&[instruction], &[&initializer, &receiver])
+        }
+        Command::Assign(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let receiver = owner_or_fee_payer(&args.receiver, &ctx)?;
+            let instruction = instruction::assign_receiver(
+                &ctx.program_id,
+                &receiver.pubkey(),
+                &args.escrow,
+                &args.new_receiver,
+            );
+            ctx.finish(&[instruction], &[&receiver])
+        }
+        Command::Refund(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let caller = args.caller.unwrap_or_else(|| ctx.fee_payer.pubkey());
+            let instruction = match args.initializer_token_account {
+                None => instruction::refund_sol_escrow(
+                    &ctx.program_id,
+                    &args.initializer,
+                    &args.escrow,
+                    &caller,
+                    caller == ctx.fee_payer.pubkey(),
+                ),
+                Some(initializer_token_account) => instruction::refund_escrow(
+                    &ctx.program_id,
+                    &args.initializer,
+                    &initializer_token_account,
+                    &args.temp_token_account.expect("required by clap"),
+                    &args.escrow,
+                    &args.token_program.id(),
+                    &caller,
+                    &args.mint.expect("required by clap"),
+                ),
+            };
+            ctx.finish(&[instruction], &[])
+        }
+        Command::Register(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let initializer = SignerSource::parse(&args.initializer)?;
+            let registry = SignerSource::parse(&args.registry)?;
+            let instructions = vec![
+                create_state_account(
+                    &ctx.fee_payer.pubkey(),
+                    &registry.pubkey(),
+                    EscrowRegistryState::LEN,
+                    &ctx.program_id,
+                ),
+                instruction::register_escrow(
+                    &ctx.program_id,
+                    &initializer.pubkey(),
+                    &args.token_account,
+                    &registry.pubkey(),
+                    &args.token_program.id(),
+                    &args.mint,
+                ),
+            ];
+            ctx.finish(&instructions, &[&initializer, &registry])
+        }
+        Command::AddMint(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let owner = owner_or_fee_payer(&args.owner, &ctx)?;
+            let instruction = instruction::add_mint(
+                &ctx.program_id,
+                &owner.pubkey(),
+                &args.registry,
+                &vault_or_default(&ctx.program_id, &args.registry, &args.mint, args.vault),
+                &args.token_program.id(),
+                &args.mint,
+            );
+            ctx.finish(&[instruction], &[&owner])
+        }
+        Command::RemoveMint(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let owner = owner_or_fee_payer(&args.owner, &ctx)?;
+            let receiver_token_account = args
+                .receiver_token_account
+                .ok_or_else(|| CliError::Usage("remove-mint needs --receiver-token-account".to_string()))?;
+            let instruction = instruction::remove_mint(
+                &ctx.program_id,
+                &owner.pubkey(),
+                &receiver_token_account,
+                &args.registry,
+                &vault_or_default(&ctx.program_id, &args.registry, &args.mint, args.vault),
+                &args.token_program.id(),
+                &args.mint,
+            );
+            ctx.finish(&[instruction], &[&owner])
+        }
+        Command::Deposit(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let depositor = SignerSource::parse(&args.depositor)?;
+            let instruction = instruction::deposit(
+                &ctx.program_id,
+                &depositor.pubkey(),
+                &args.token_account,
+                &vault_or_default(&ctx.program_id, &args.registry, &args.mint, args.vault),
+                &args.registry,
+                &args.token_program.id(),
+                &args.mint,
+                args.amount,
+            );
+            ctx.finish(&[instruction], &[&depositor])
+        }
+        Command::Withdraw(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let depositor = SignerSource::parse(&args.depositor)?;
+            let instruction = instruction::withdraw(
+                &ctx.program_id,
+                &depositor.pubkey(),
+                &args.token_account,
+                &vault_or_default(&ctx.program_id, &args.registry, &args.mint, args.vault),
+                &args.registry,
+                &args.token_program.id(),
+                &args.mint,
+                args.amount,
+            );
+            ctx.finish(&[instruction], &[&depositor])
+        }
+        Command::Collect(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let owner = owner_or_fee_payer(&args.owner, &ctx)?;
+            let depositor = SignerSource::parse(&args.depositor)?;
+            let instruction = instruction::collect_deposit(
+                &ctx.program_id,
+                &owner.pubkey(),
+                &args.receiver_token_account,
+                &vault_or_default(&ctx.program_id, &args.registry, &args.mint, args.vault),
+                &args.registry,
+                &depositor.pubkey(),
+                matches!(depositor, SignerSource::Keypair(_)),
+                &args.token_program.id(),
+                &args.mint,
+            );
+            ctx.finish(&[instruction], &[&owner, &depositor])
+        }
+        Command::Migrate(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let authority = match &args.authority {
+                Some(authority) => SignerSource::parse(authority)?,
+                None => SignerSource::Pubkey(ctx.fee_payer.pubkey()),
+            };
+            let instruction = instruction::migrate_account(
+                &ctx.program_id,
+                &authority.pubkey(),
+                &ctx.fee_payer.pubkey(),
+                &args.account,
+            );
+            ctx.finish(&[instruction], &[&authority])
+        }
+        Command::SetConfig(args) => {
+            let ctx = TxContext::new(&args.tx, &config)?;
+            let authority = match &args.authority {
+                Some(authority) => SignerSource::parse(authority)?,
+                None => SignerSource::Pubkey(ctx.fee_payer.pubkey()),
+            };
+            let instruction = instruction::set_config(
+                &ctx.program_id,
+                &authority.pubkey(),
+                &ctx.fee_payer.pubkey(),
+                args.max_open_escrows_per_user,
+            );
+            ctx.finish(&[instruction], &[&authority])
+        }
+        Command::Inspect(args) => {
+            let data = inspect::decode_dump(&read_dump(args.dump))?;
+            let account = inspect::decode(args.kind, &data)?;
+            let json = match args.clock {
+                Some(clock) => {
+                    let data = inspect::decode_dump(&read_dump(clock))?;
+                    serde_json::to_string_pretty(&account.at(&bincode::deserialize(&data)?))?
+                }
+                None => serde_json::to_string_pretty(&account)?,
+            };
+            println!("{json}");
+            Ok(())
+        }
+        Command::Schema => {
+            println!("{}", serde_json::to_string_pretty(&decoder::program_schema())?);
+            Ok(())
+        }
+        Command::Config(ConfigCommand::Get) => {
+            println!("Config File: {}", config_path.display());
+            println!("{}", serde_json::to_string_pretty(&config)?);
+            Ok(())
+        }
+        Command::Config(ConfigCommand::Set { program_id, keypair }) => {
+            let mut config = config;
+            if let Some(program_id) = program_id {
+                config.program_id = Some(program_id.to_string());
+            }
+            if let Some(keypair) = keypair {
+                config.keypair_path = Some(keypair);
+            }
+            config.save(&config_path)?;
+            println!("Config File: {}", config_path.display());
+            println!("{}", serde_json::to_string_pretty(&config)?);
+            Ok(())
+        }
+    }
+}
+
+fn main() {
+    if let Err(err) = run(Cli::parse()) {
+        eprintln!("error: {err}");
+        exit(1);
+    }
+}
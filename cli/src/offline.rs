@@ -0,0 +1,93 @@
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair},
+    signer::Signer,
+    transaction::Transaction,
+};
+
+use crate::error::CliError;
+
+/// An account given either as a keypair file, which signs here, or as a bare
+/// pubkey whose signature gets added on another machine.
+pub enum SignerSource {
+    Keypair(Keypair),
+    Pubkey(Pubkey),
+}
+
+impl SignerSource {
+    pub fn parse(value: &str) -> Result<Self, CliError> {
+        if let Ok(pubkey) = Pubkey::from_str(value) {
+            return Ok(Self::Pubkey(pubkey));
+        }
+        read_keypair_file(value)
+            .map(Self::Keypair)
+            .map_err(|err| CliError::Usage(format!("Could not read keypair {value}: {err}")))
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        match self {
+            Self::Keypair(keypair) => keypair.pubkey(),
+            Self::Pubkey(pubkey) => *pubkey,
+        }
+    }
+}
+
+/// Builds the transaction against an externally fetched blockhash and signs
+/// it with whichever of `signers` it needs. Missing signatures stay empty.
+pub fn build_transaction(
+    instructions: &[Instruction],
+    fee_payer: &Pubkey,
+    signers: &[&SignerSource],
+    blockhash: Hash,
+) -> Result<Transaction, CliError> {
+    let message = Message::new(instructions, Some(fee_payer));
+    let mut transaction = Transaction::new_unsigned(message);
+    transaction.message.recent_blockhash = blockhash;
+
+    let required = &transaction.message.account_keys
+        [..transaction.message.header.num_required_signatures as usize];
+    let keypairs: Vec<&Keypair> = signers
+        .iter()
+        .filter_map(|signer| match signer {
+            SignerSource::Keypair(keypair) if required.contains(&keypair.pubkey()) => {
+                Some(keypair)
+            }
+            _ => None,
+        })
+        .collect();
+    transaction.try_partial_sign(&keypairs, blockhash)?;
+
+    Ok(transaction)
+}
+
+/// Prints the signatures collected so far, the absent signers and the
+/// base64 wire transaction, ready to carry to an online machine.
+pub fn print_transaction(transaction: &Transaction) -> Result<(), CliError> {
+    let num_signers = transaction.message.header.num_required_signatures as usize;
+    let signers = transaction.message.account_keys[..num_signers]
+        .iter()
+        .zip(&transaction.signatures);
+
+    println!("Blockhash: {}", transaction.message.recent_blockhash);
+    println!("Signers (Pubkey=Signature):");
+    for (pubkey, signature) in signers.clone() {
+        if *signature != Default::default() {
+            println!("  {pubkey}={signature}");
+        }
+    }
+    println!("Absent Signers:");
+    for (pubkey, signature) in signers {
+        if *signature == Default::default() {
+            println!("  {pubkey}");
+        }
+    }
+    println!("Transaction: {}", STANDARD.encode(bincode::serialize(transaction)?));
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use std::{fs, path::Path, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use solana_escrow::utils::voucher::Voucher;
+use solana_sdk::{pubkey::Pubkey, signature::Signature, signer::Signer};
+
+use crate::{error::CliError, offline::SignerSource};
+
+/// A voucher with its signature, as printed by `voucher` and read back by
+/// `release --voucher`.
+#[derive(Serialize, Deserialize)]
+pub struct SignedVoucher {
+    pub escrow: String,
+    pub amount: u64,
+    pub recipient: String,
+    pub nonce: u64,
+    pub expiry: i64,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl SignedVoucher {
+    pub fn sign(voucher: &Voucher, signer: &SignerSource) -> Result<Self, CliError> {
+        let SignerSource::Keypair(keypair) = signer else {
+            return Err(CliError::Usage("Vouchers are signed with a keypair file".to_string()));
+        };
+        let signature = keypair.sign_message(&voucher.message());
+        Ok(Self {
+            escrow: voucher.escrow.to_string(),
+            amount: voucher.amount,
+            recipient: voucher.recipient.to_string(),
+            nonce: voucher.nonce,
+            expiry: voucher.expiry,
+            signer: signer.pubkey().to_string(),
+            signature: signature.to_string(),
+        })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, CliError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Returns the voucher, its signer and its signature.
+    pub fn parts(&self) -> Result<(Voucher, Pubkey, [u8; 64]), CliError> {
+        let pubkey = |value: &str| {
+            Pubkey::from_str(value)
+                .map_err(|_| CliError::Usage(format!("Invalid pubkey in voucher: {value}")))
+        };
+        let signature = Signature::from_str(&self.signature)
+            .map_err(|_| CliError::Usage("Invalid voucher signature".to_string()))?;
+        let voucher = Voucher {
+            escrow: pubkey(&self.escrow)?,
+            amount: self.amount,
+            recipient: pubkey(&self.recipient)?,
+            nonce: self.nonce,
+            expiry: self.expiry,
+        };
+        Ok((voucher, pubkey(&self.signer)?, signature.into()))
+    }
+}
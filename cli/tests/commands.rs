@@ -0,0 +1,1021 @@
+//! Runs the `escrow` binary: argument parsing, the transaction each
+//! subcommand builds, which compares to the program's instruction builders,
+//! and the JSON printed by `inspect`.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Command, Output},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use solana_escrow::{
+    instruction,
+    states::{
+        default_escrow::EscrowState, escrow_registry::EscrowRegistryState,
+        sol_escrow::SolEscrowState,
+    },
+    utils::{memo::EscrowMemo, time_basis::TimeBasis, voucher::Voucher},
+};
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{write_keypair_file, Keypair, Signature},
+    signer::Signer,
+    system_instruction,
+    transaction::Transaction,
+};
+
+/// A directory of its own for each test, holding its config and keypairs.
+fn scratch_dir() -> PathBuf {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let dir = env::temp_dir().join(format!(
+        "escrow-cli-{}-{}",
+        process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn keypair_file(dir: &Path, name: &str) -> (Keypair, String) {
+    let keypair = Keypair::new();
+    let path = dir.join(format!("{name}.json"));
+    write_keypair_file(&keypair, &path).unwrap();
+    (keypair, path.to_str().unwrap().to_string())
+}
+
+/// Runs `escrow` with the config file of `dir`, empty unless set by a test.
+fn escrow(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_escrow"))
+        .arg("--config")
+        .arg(dir.join("cli.json"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(
+        output.status.success(),
+        "escrow failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+/// The value printed after `label: `.
+fn printed<'a>(stdout: &'a str, label: &str) -> &'a str {
+    stdout
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{label}: ")))
+        .unwrap_or_else(|| panic!("no {label} in {stdout}"))
+}
+
+/// Lines listed under the `Absent Signers:` heading.
+fn absent_signers(stdout: &str) -> Vec<Pubkey> {
+    stdout
+        .lines()
+        .skip_while(|line| *line != "Absent Signers:")
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .map(|line| line.trim().parse().unwrap())
+        .collect()
+}
+
+fn transaction(stdout: &str) -> Transaction {
+    let wire = STANDARD.decode(printed(stdout, "Transaction")).unwrap();
+    bincode::deserialize(&wire).unwrap()
+}
+
+fn signature_of(transaction: &Transaction, signer: &Pubkey) -> Signature {
+    let index = transaction
+        .message
+        .account_keys
+        .iter()
+        .position(|key| key == signer)
+        .unwrap();
+    transaction.signatures[index]
+}
+
+/// The program id, fee payer and blockhash every transaction is built with.
+struct Tx {
+    program_id: Pubkey,
+    fee_payer: Pubkey,
+    blockhash: Hash,
+}
+
+impl Tx {
+    fn new() -> Self {
+        Self {
+            program_id: Pubkey::new_unique(),
+            fee_payer: Pubkey::new_unique(),
+            blockhash: Hash::new_unique(),
+        }
+    }
+
+    /// Runs `subcommand`, which must succeed, with the transaction
+    /// arguments and `args`.
+    fn run(&self, dir: &Path, subcommand: &str, args: &[&str]) -> String {
+        stdout(&self.output(dir, subcommand, args))
+    }
+
+    fn output(&self, dir: &Path, subcommand: &str, args: &[&str]) -> Output {
+        let (program_id, fee_payer, blockhash) = (
+            self.program_id.to_string(),
+            self.fee_payer.to_string(),
+            self.blockhash.to_string(),
+        );
+        let mut all_args = vec![
+            subcommand,
+            "--program-id",
+            &program_id,
+            "--fee-payer",
+            &fee_payer,
+            "--blockhash",
+            &blockhash,
+        ];
+        all_args.extend_from_slice(args);
+        escrow(dir, &all_args)
+    }
+
+    /// Checks that `stdout` carries `instructions`, paid by the fee payer.
+    fn assert_builds(&self, stdout: &str, instructions: &[Instruction]) {
+        let expected = Message::new_with_blockhash(instructions, Some(&self.fee_payer), &self.blockhash);
+        assert_eq!(transaction(stdout).message, expected);
+        assert_eq!(printed(stdout, "Blockhash"), self.blockhash.to_string());
+    }
+}
+
+#[test]
+fn missing_arguments_are_reported_by_name() {
+    let dir = scratch_dir();
+    let output = escrow(&dir, &["release", "--blockhash", &Hash::new_unique().to_string()]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("--escrow"));
+}
+
+#[test]
+fn native_escrows_take_no_token_accounts() {
+    let dir = scratch_dir();
+    let (receiver, mint) = (Pubkey::new_unique().to_string(), Pubkey::new_unique().to_string());
+    let output = escrow(
+        &dir,
+        &[
+            "create",
+            "--blockhash",
+            &Hash::new_unique().to_string(),
+            "--initializer",
+            &receiver,
+            "--receiver",
+            &receiver,
+            "--amount",
+            "1",
+            "--native",
+            "--escrow",
+            &receiver,
+            "--mint",
+            &mint,
+        ],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("cannot be used with"));
+}
+
+#[test]
+fn reference_needs_64_hex_digits() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let key = Pubkey::new_unique().to_string();
+    let output = escrow(
+        &dir,
+        &[
+            "create",
+            "--blockhash",
+            &tx.blockhash.to_string(),
+            "--initializer",
+            &key,
+            "--receiver",
+            &key,
+            "--escrow-index",
+            "1",
+            "--amount",
+            "1",
+            "--temp-token-account",
+            &key,
+            "--mint",
+            &key,
+            "--reference",
+            "abcd",
+        ],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("expected 64 hex digits"));
+}
+
+#[test]
+fn transactions_need_a_program_id() {
+    let dir = scratch_dir();
+    let key = Pubkey::new_unique().to_string();
+    let output = escrow(
+        &dir,
+        &[
+            "assign",
+            "--blockhash",
+            &Hash::new_unique().to_string(),
+            "--fee-payer",
+            &key,
+            "--escrow",
+            &key,
+            "--new-receiver",
+            &key,
+        ],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("No --program-id given or configured"));
+}
+
+#[test]
+fn config_supplies_the_program_id_and_fee_payer() {
+    let dir = scratch_dir();
+    let (fee_payer, fee_payer_path) = keypair_file(&dir, "fee_payer");
+    let program_id = Pubkey::new_unique();
+    stdout(&escrow(
+        &dir,
+        &["config", "set", "--program-id", &program_id.to_string(), "--keypair", &fee_payer_path],
+    ));
+    let config: Value = serde_json::from_str(
+        stdout(&escrow(&dir, &["config", "get"]))
+            .split_once('\n')
+            .unwrap()
+            .1,
+    )
+    .unwrap();
+    assert_eq!(config["program_id"], program_id.to_string());
+    assert_eq!(config["keypair_path"], fee_payer_path);
+
+    let (escrow_account, new_receiver) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let blockhash = Hash::new_unique();
+    let out = stdout(&escrow(
+        &dir,
+        &[
+            "assign",
+            "--blockhash",
+            &blockhash.to_string(),
+            "--escrow",
+            &escrow_account.to_string(),
+            "--new-receiver",
+            &new_receiver.to_string(),
+        ],
+    ));
+
+    // The fee payer is the receiver by default, and signs here.
+    let assign = instruction::assign_receiver(&program_id, &fee_payer.pubkey(), &escrow_account, &new_receiver);
+    let transaction = transaction(&out);
+    assert_eq!(
+        transaction.message,
+        Message::new_with_blockhash(&[assign], Some(&fee_payer.pubkey()), &blockhash)
+    );
+    transaction.verify().unwrap();
+    assert!(absent_signers(&out).is_empty());
+}
+
+#[test]
+fn create_token_escrow() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (initializer, receiver, temp_token_account, mint) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let out = tx.run(
+        &dir,
+        "create",
+        &[
+            "--initializer",
+            &initializer.to_string(),
+            "--receiver",
+            &receiver.to_string(),
+            "--escrow-index",
+            "3",
+            "--amount",
+            "100",
+            "--temp-token-account",
+            &temp_token_account.to_string(),
+            "--mint",
+            &mint.to_string(),
+            "--token-program",
+            "token2022",
+            "--time-basis",
+            "slot",
+            "--reference",
+            &"ab".repeat(32),
+            "--memo",
+            "order 7",
+            "--log-memo",
+        ],
+    );
+
+    let (escrow_account, _) = instruction::find_escrow_account(&tx.program_id, &initializer, 3);
+    assert_eq!(printed(&out, "Escrow"), escrow_account.to_string());
+    tx.assert_builds(
+        &out,
+        &[instruction::init_escrow(
+            &tx.program_id,
+            &initializer,
+            &receiver,
+            &temp_token_account,
+            &escrow_account,
+            &spl_token_2022::id(),
+            &tx.fee_payer,
+            &mint,
+            100,
+            TimeBasis::Slot,
+            &EscrowMemo {
+                reference: Some([0xab; 32]),
+                memo: "order 7".to_string(),
+                log_memo: true,
+            },
+        )],
+    );
+    assert_eq!(absent_signers(&out), [tx.fee_payer, initializer]);
+}
+
+#[test]
+fn create_native_escrow_signs_with_keypair_files() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (initializer, initializer_path) = keypair_file(&dir, "initializer");
+    let (escrow_account, escrow_path) = keypair_file(&dir, "escrow");
+    let receiver = Pubkey::new_unique();
+    let out = tx.run(
+        &dir,
+        "create",
+        &[
+            "--initializer",
+            &initializer_path,
+            "--receiver",
+            &receiver.to_string(),
+            "--native",
+            "--escrow",
+            &escrow_path,
+            "--amount",
+            "5000",
+        ],
+    );
+
+    tx.assert_builds(
+        &out,
+        &[
+            system_instruction::create_account(
+                &tx.fee_payer,
+                &escrow_account.pubkey(),
+                Rent::default().minimum_balance(SolEscrowState::LEN),
+                SolEscrowState::LEN as u64,
+                &tx.program_id,
+            ),
+            instruction::init_sol_escrow(
+                &tx.program_id,
+                &initializer.pubkey(),
+                &receiver,
+                &escrow_account.pubkey(),
+                &tx.fee_payer,
+                5000,
+            ),
+        ],
+    );
+    // Only the fee payer, given as a pubkey, is left to sign elsewhere.
+    assert_eq!(absent_signers(&out), [tx.fee_payer]);
+    let transaction = transaction(&out);
+    let message = transaction.message_data();
+    for signer in [&initializer, &escrow_account] {
+        assert!(signature_of(&transaction, &signer.pubkey()).verify(signer.pubkey().as_ref(), &message));
+    }
+}
+
+#[test]
+fn release_token_escrow_with_the_memo_program() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+    let [escrow_account, receiver_token_account, temp_token_account, initializer, initializer_token_account, mint] =
+        keys[..]
+    else {
+        unreachable!()
+    };
+    let out = tx.run(
+        &dir,
+        "release",
+        &[
+            "--escrow",
+            &escrow_account.to_string(),
+            "--receiver-token-account",
+            &receiver_token_account.to_string(),
+            "--temp-token-account",
+            &temp_token_account.to_string(),
+            "--initializer",
+            &initializer.to_string(),
+            "--initializer-token-account",
+            &initializer_token_account.to_string(),
+            "--mint",
+            &mint.to_string(),
+            "--log-memo",
+        ],
+    );
+
+    let mut release = instruction::release_escrow(
+        &tx.program_id,
+        &receiver_token_account,
+        &temp_token_account,
+        &initializer,
+        &initializer_token_account,
+        &escrow_account,
+        &spl_token::id(),
+        &tx.fee_payer,
+        &mint,
+    );
+    instruction::append_memo_program(&mut release);
+    tx.assert_builds(&out, &[release]);
+}
+
+#[test]
+fn release_native_escrow() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (escrow_account, receiver) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let out = tx.run(
+        &dir,
+        "release",
+        &["--escrow", &escrow_account.to_string(), "--native-receiver", &receiver.to_string()],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::release_sol_escrow(&tx.program_id, &receiver, &escrow_account, &tx.fee_payer)],
+    );
+}
+
+#[test]
+fn release_with_a_signed_voucher() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (signer, signer_path) = keypair_file(&dir, "receiver");
+    let keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+    let [escrow_account, receiver_token_account, temp_token_account, initializer, initializer_token_account, mint] =
+        keys[..]
+    else {
+        unreachable!()
+    };
+    let voucher = Voucher {
+        escrow: escrow_account,
+        amount: 40,
+        recipient: Pubkey::new_unique(),
+        nonce: 2,
+        expiry: 1_900_000_000,
+    };
+    let signed = stdout(&escrow(
+        &dir,
+        &[
+            "voucher",
+            "--signer",
+            &signer_path,
+            "--escrow",
+            &escrow_account.to_string(),
+            "--amount",
+            "40",
+            "--recipient",
+            &voucher.recipient.to_string(),
+            "--nonce",
+            "2",
+            "--expiry",
+            "1900000000",
+        ],
+    ));
+    let json: Value = serde_json::from_str(&signed).unwrap();
+    assert_eq!(json["signer"], signer.pubkey().to_string());
+    let voucher_path = dir.join("voucher.json");
+    fs::write(&voucher_path, &signed).unwrap();
+
+    let release_args = |escrow_account: &Pubkey| {
+        [
+            "--escrow".to_string(),
+            escrow_account.to_string(),
+            "--receiver-token-account".to_string(),
+            receiver_token_account.to_string(),
+            "--temp-token-account".to_string(),
+            temp_token_account.to_string(),
+            "--initializer".to_string(),
+            initializer.to_string(),
+            "--initializer-token-account".to_string(),
+            initializer_token_account.to_string(),
+            "--mint".to_string(),
+            mint.to_string(),
+            "--voucher".to_string(),
+            voucher_path.to_str().unwrap().to_string(),
+        ]
+    };
+    let args = release_args(&escrow_account);
+    let out = tx.run(&dir, "release", &args.iter().map(String::as_str).collect::<Vec<_>>());
+
+    let signature: [u8; 64] = signer.sign_message(&voucher.message()).into();
+    tx.assert_builds(
+        &out,
+        &[
+            instruction::ed25519_verify(&signer.pubkey(), &signature, &voucher),
+            instruction::release_with_voucher(
+                &tx.program_id,
+                &receiver_token_account,
+                &temp_token_account,
+                &initializer,
+                &initializer_token_account,
+                &escrow_account,
+                &spl_token::id(),
+                &tx.fee_payer,
+                &mint,
+                voucher,
+            ),
+        ],
+    );
+
+    // A voucher only releases the escrow it names.
+    let args = release_args(&Pubkey::new_unique());
+    let output = tx.output(&dir, "release", &args.iter().map(String::as_str).collect::<Vec<_>>());
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("The voucher is for another escrow"));
+}
+
+#[test]
+fn increase_amend_and_assign() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let keys: Vec<Pubkey> = (0..7).map(|_| Pubkey::new_unique()).collect();
+    let [initializer, receiver, token_account, escrow_account, temp_token_account, mint, new_receiver] = keys[..]
+    else {
+        unreachable!()
+    };
+
+    let out = tx.run(
+        &dir,
+        "increase",
+        &[
+            "--initializer",
+            &initializer.to_string(),
+            "--token-account",
+            &token_account.to_string(),
+            "--escrow",
+            &escrow_account.to_string(),
+            "--temp-token-account",
+            &temp_token_account.to_string(),
+            "--mint",
+            &mint.to_string(),
+            "--amount",
+            "25",
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::increase_deposit(
+            &tx.program_id,
+            &initializer,
+            &token_account,
+            &temp_token_account,
+            &escrow_account,
+            &spl_token::id(),
+            &mint,
+            25,
+        )],
+    );
+
+    let out = tx.run(
+        &dir,
+        "amend",
+        &[
+            "--initializer",
+            &initializer.to_string(),
+            "--receiver",
+            &receiver.to_string(),
+            "--token-account",
+            &token_account.to_string(),
+            "--escrow",
+            &escrow_account.to_string(),
+            "--temp-token-account",
+            &temp_token_account.to_string(),
+            "--mint",
+            &mint.to_string(),
+            "--amount",
+            "60",
+            "--expire-date",
+            "1800000000",
+            "--new-receiver",
+            &new_receiver.to_string(),
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::amend_escrow(
+            &tx.program_id,
+            &initializer,
+            &receiver,
+            &token_account,
+            &temp_token_account,
+            &escrow_account,
+            &spl_token::id(),
+            &mint,
+            60,
+            1_800_000_000,
+            &new_receiver,
+        )],
+    );
+    assert_eq!(absent_signers(&out), [tx.fee_payer, initializer, receiver]);
+
+    let out = tx.run(
+        &dir,
+        "assign",
+        &[
+            "--receiver",
+            &receiver.to_string(),
+            "--escrow",
+            &escrow_account.to_string(),
+            "--new-receiver",
+            &new_receiver.to_string(),
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::assign_receiver(&tx.program_id, &receiver, &escrow_account, &new_receiver)],
+    );
+}
+
+#[test]
+fn refund_token_and_native_escrows() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+    let [escrow_account, initializer, initializer_token_account, temp_token_account, mint, caller] = keys[..] else {
+        unreachable!()
+    };
+
+    let out = tx.run(
+        &dir,
+        "refund",
+        &[
+            "--escrow",
+            &escrow_account.to_string(),
+            "--initializer",
+            &initializer.to_string(),
+            "--initializer-token-account",
+            &initializer_token_account.to_string(),
+            "--temp-token-account",
+            &temp_token_account.to_string(),
+            "--mint",
+            &mint.to_string(),
+            "--caller",
+            &caller.to_string(),
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::refund_escrow(
+            &tx.program_id,
+            &initializer,
+            &initializer_token_account,
+            &temp_token_account,
+            &escrow_account,
+            &spl_token::id(),
+            &caller,
+            &mint,
+        )],
+    );
+
+    let out = tx.run(
+        &dir,
+        "refund",
+        &["--escrow", &escrow_account.to_string(), "--initializer", &initializer.to_string()],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::refund_sol_escrow(&tx.program_id, &initializer, &escrow_account, &tx.fee_payer, true)],
+    );
+
+    // A crank refunding after the release window, the caller doesn't sign.
+    let out = tx.run(
+        &dir,
+        "refund",
+        &[
+            "--escrow",
+            &escrow_account.to_string(),
+            "--initializer",
+            &initializer.to_string(),
+            "--caller",
+            &caller.to_string(),
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::refund_sol_escrow(&tx.program_id, &initializer, &escrow_account, &caller, false)],
+    );
+}
+
+#[test]
+fn register_and_manage_mints() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (registry, registry_path) = keypair_file(&dir, "registry");
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let [owner, token_account, mint, receiver_token_account] = keys[..] else {
+        unreachable!()
+    };
+
+    let out = tx.run(
+        &dir,
+        "register",
+        &[
+            "--initializer",
+            &owner.to_string(),
+            "--token-account",
+            &token_account.to_string(),
+            "--registry",
+            &registry_path,
+            "--mint",
+            &mint.to_string(),
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[
+            system_instruction::create_account(
+                &tx.fee_payer,
+                &registry.pubkey(),
+                Rent::default().minimum_balance(EscrowRegistryState::LEN),
+                EscrowRegistryState::LEN as u64,
+                &tx.program_id,
+            ),
+            instruction::register_escrow(
+                &tx.program_id,
+                &owner,
+                &token_account,
+                &registry.pubkey(),
+                &spl_token::id(),
+                &mint,
+            ),
+        ],
+    );
+
+    // The vault defaults to the PDA, the owner to the fee payer.
+    let registry = registry.pubkey();
+    let vault = instruction::find_registry_vault(&tx.program_id, &registry, &mint).0;
+    let out = tx.run(
+        &dir,
+        "add-mint",
+        &["--registry", &registry.to_string(), "--mint", &mint.to_string(), "--token-program", "token2022"],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::add_mint(&tx.program_id, &tx.fee_payer, &registry, &vault, &spl_token_2022::id(), &mint)],
+    );
+
+    let out = tx.run(
+        &dir,
+        "remove-mint",
+        &[
+            "--owner",
+            &owner.to_string(),
+            "--registry",
+            &registry.to_string(),
+            "--mint",
+            &mint.to_string(),
+            "--vault",
+            &token_account.to_string(),
+            "--receiver-token-account",
+            &receiver_token_account.to_string(),
+        ],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::remove_mint(
+            &tx.program_id,
+            &owner,
+            &receiver_token_account,
+            &registry,
+            &token_account,
+            &spl_token::id(),
+            &mint,
+        )],
+    );
+
+    let output = tx.output(
+        &dir,
+        "remove-mint",
+        &["--registry", &registry.to_string(), "--mint", &mint.to_string()],
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("remove-mint needs --receiver-token-account"));
+}
+
+#[test]
+fn deposit_withdraw_and_collect() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (depositor, depositor_path) = keypair_file(&dir, "depositor");
+    let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+    let [token_account, registry, mint, receiver_token_account] = keys[..] else {
+        unreachable!()
+    };
+    let vault = instruction::find_registry_vault(&tx.program_id, &registry, &mint).0;
+    let args = [
+        "--depositor",
+        &depositor_path,
+        "--token-account",
+        &token_account.to_string(),
+        "--registry",
+        &registry.to_string(),
+        "--mint",
+        &mint.to_string(),
+        "--amount",
+        "70",
+    ]
+    .map(str::to_string);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+
+    let out = tx.run(&dir, "deposit", &args);
+    tx.assert_builds(
+        &out,
+        &[instruction::deposit(
+            &tx.program_id,
+            &depositor.pubkey(),
+            &token_account,
+            &vault,
+            &registry,
+            &spl_token::id(),
+            &mint,
+            70,
+        )],
+    );
+    let out = tx.run(&dir, "withdraw", &args);
+    tx.assert_builds(
+        &out,
+        &[instruction::withdraw(
+            &tx.program_id,
+            &depositor.pubkey(),
+            &token_account,
+            &vault,
+            &registry,
+            &spl_token::id(),
+            &mint,
+            70,
+        )],
+    );
+
+    // A depositor given as a pubkey doesn't sign, a keypair file co-signs.
+    for (depositor_arg, depositor_signs) in [(depositor.pubkey().to_string(), false), (depositor_path.clone(), true)] {
+        let out = tx.run(
+            &dir,
+            "collect",
+            &[
+                "--receiver-token-account",
+                &receiver_token_account.to_string(),
+                "--registry",
+                &registry.to_string(),
+                "--depositor",
+                &depositor_arg,
+                "--mint",
+                &mint.to_string(),
+            ],
+        );
+        tx.assert_builds(
+            &out,
+            &[instruction::collect_deposit(
+                &tx.program_id,
+                &tx.fee_payer,
+                &receiver_token_account,
+                &vault,
+                &registry,
+                &depositor.pubkey(),
+                depositor_signs,
+                &spl_token::id(),
+                &mint,
+            )],
+        );
+    }
+}
+
+#[test]
+fn migrate_signed_by_the_upgrade_authority() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let (authority, account) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let out = tx.run(
+        &dir,
+        "migrate",
+        &["--account", &account.to_string(), "--authority", &authority.to_string()],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::migrate_account(&tx.program_id, &authority, &tx.fee_payer, &account)],
+    );
+    assert_eq!(absent_signers(&out), [tx.fee_payer, authority]);
+}
+
+#[test]
+fn set_config_signed_by_the_upgrade_authority() {
+    let dir = scratch_dir();
+    let tx = Tx::new();
+    let authority = Pubkey::new_unique();
+    let out = tx.run(
+        &dir,
+        "set-config",
+        &["--max-open-escrows-per-user", "8", "--authority", &authority.to_string()],
+    );
+    tx.assert_builds(
+        &out,
+        &[instruction::set_config(&tx.program_id, &authority, &tx.fee_payer, 8)],
+    );
+    assert_eq!(absent_signers(&out), [tx.fee_payer, authority]);
+}
+
+fn escrow_state() -> EscrowState {
+    EscrowState {
+        is_initialized: true,
+        caller_pubkey: Pubkey::new_unique(),
+        initializer_pubkey: Pubkey::new_unique(),
+        receiver_pubkey: Pubkey::new_unique(),
+        temp_token_account_pubkey: Pubkey::new_unique(),
+        escrow_amount: 100,
+        expire_date: 1_700_000_000,
+        voucher_nonce: 3,
+        time_basis: TimeBasis::UnixTimestamp,
+        refund_after: 1_700_604_800,
+        reference: Some([0xab; 32]),
+        log_memo: false,
+        memo: "order 7".to_string(),
+        escrow_index: 2,
+    }
+}
+
+#[test]
+fn inspect_decodes_an_escrow_dump() {
+    let dir = scratch_dir();
+    let state = escrow_state();
+    let mut data = vec![0; EscrowState::LEN];
+    EscrowState::pack(state.clone(), &mut data).unwrap();
+
+    let json: Value = serde_json::from_str(&stdout(&escrow(
+        &dir,
+        &["inspect", &STANDARD.encode(&data), "--kind", "escrow"],
+    )))
+    .unwrap();
+    assert_eq!(json["type"], "escrow");
+    assert_eq!(json["initializer_pubkey"], state.initializer_pubkey.to_string());
+    assert_eq!(json["receiver_pubkey"], state.receiver_pubkey.to_string());
+    assert_eq!(json["escrow_amount"], 100);
+    assert_eq!(json["expire_date"], "2023-11-14T22:13:20Z");
+    assert_eq!(json["voucher_nonce"], 3);
+    assert_eq!(json["reference"], "ab".repeat(32));
+    assert_eq!(json["memo"], "order 7");
+    assert_eq!(json["escrow_index"], 2);
+}
+
+#[test]
+fn inspect_detects_the_kind_from_a_json_dump() {
+    let dir = scratch_dir();
+    let address = Pubkey::new_unique();
+    let mut data = vec![0; EscrowState::LEN];
+    EscrowState::pack(escrow_state(), &mut data).unwrap();
+    let dump = serde_json::json!({
+        "pubkey": address.to_string(),
+        "account": { "data": [STANDARD.encode(&data), "base64"] },
+    });
+    let dump_path = dir.join("account.json");
+    fs::write(&dump_path, dump.to_string()).unwrap();
+
+    let out = stdout(&escrow(
+        &dir,
+        &["inspect", dump_path.to_str().unwrap()],
+    ));
+    let json: Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(json["type"], "escrow");
+    assert_eq!(json["escrow_amount"], 100);
+
+    // Data that is no program account is rejected.
+    let output = escrow(&dir, &["inspect", &STANDARD.encode([9; 40])]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn schema_matches_the_committed_copy() {
+    let dir = scratch_dir();
+    let committed = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../configs/schema.json")).unwrap();
+    let printed: Value = serde_json::from_str(&stdout(&escrow(&dir, &["schema"]))).unwrap();
+    assert_eq!(printed, serde_json::from_str::<Value>(&committed).unwrap());
+}
@@ -0,0 +1,76 @@
+//! `getProgramAccounts` filters derived from the state layouts.
+
+use solana_escrow::states::layout::{AccountLayout, HEADER_LEN};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::pubkey::Pubkey;
+
+/// A party recorded on an escrow, to look its escrows up by.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Party {
+    /// The caller / relayer that created the escrow.
+    Caller(Pubkey),
+    Initializer(Pubkey),
+    /// The current holder of the claim.
+    Receiver(Pubkey),
+}
+
+impl Party {
+    /// Name of the state field holding the party.
+    pub const fn field(self) -> &'static str {
+        match self {
+            Party::Caller(_) => "caller_pubkey",
+            Party::Initializer(_) => "initializer_pubkey",
+            Party::Receiver(_) => "receiver_pubkey",
+        }
+    }
+
+    pub const fn pubkey(self) -> Pubkey {
+        match self {
+            Party::Caller(pubkey) | Party::Initializer(pubkey) | Party::Receiver(pubkey) => pubkey,
+        }
+    }
+}
+
+/// Offset of the field of `party` in the current layout of `T`: 3, 35 and
+/// 67 for the caller, initializer and receiver of escrows.
+///
+/// # Panics
+///
+/// If `T` has no such field.
+pub fn offset_of<T: AccountLayout>(party: Party) -> usize {
+    T::offset_of(party.field())
+        .unwrap_or_else(|| panic!("{} has no {} field", T::NAME, party.field()))
+}
+
+/// Matches accounts of `T` written with a header, at any version: fields
+/// are only ever appended, so the offset holds across versions.
+pub fn filters<T: AccountLayout>(party: Party) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, &[T::DISCRIMINATOR])),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            offset_of::<T>(party),
+            party.pubkey().as_ref(),
+        )),
+    ]
+}
+
+/// Matches headerless legacy accounts of `T`, where every field sits
+/// `HEADER_LEN` bytes earlier: 1, 33 and 65 for escrows. `None` when `T`
+/// has no legacy layout.
+pub fn legacy_filters<T: AccountLayout>(party: Party) -> Option<Vec<RpcFilterType>> {
+    let legacy_len = T::LEGACY_LEN?;
+    Some(vec![
+        RpcFilterType::DataSize(legacy_len as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+            offset_of::<T>(party) - HEADER_LEN,
+            party.pubkey().as_ref(),
+        )),
+    ])
+}
+
+/// Every query needed to find the accounts of `T` holding `party`.
+pub fn queries<T: AccountLayout>(party: Party) -> Vec<Vec<RpcFilterType>> {
+    let mut queries = vec![filters::<T>(party)];
+    queries.extend(legacy_filters::<T>(party));
+    queries
+}
@@ -0,0 +1,79 @@
+//! Rust client for the escrow program.
+//!
+//! Lists the escrows a party takes part in through `getProgramAccounts`,
+//! with memcmp filters placed at the offsets of the state layouts, and
+//! decodes them with the program's own decoder. [`MemoryRpc`] stands in for
+//! a cluster so queries can be tested offline.
+
+// Errors are `RpcClient`'s own, passed through as they are.
+#![allow(clippy::result_large_err)]
+
+pub mod filters;
+pub mod rpc;
+
+use std::collections::BTreeMap;
+
+use solana_escrow::{
+    decoder::{self, ProgramAccount},
+    states::{default_escrow::EscrowState, layout::AccountLayout, sol_escrow::SolEscrowState},
+};
+use solana_rpc_client_api::client_error::Result as ClientResult;
+use solana_sdk::pubkey::Pubkey;
+
+pub use filters::Party;
+pub use rpc::{MemoryRpc, ProgramAccounts};
+
+/// Token escrows `party` takes part in, in every layout version, ordered by
+/// address.
+pub fn find_escrows(
+    rpc: &impl ProgramAccounts,
+    program_id: &Pubkey,
+    party: Party,
+) -> ClientResult<Vec<(Pubkey, EscrowState)>> {
+    find(rpc, program_id, party, |account| match account {
+        ProgramAccount::Escrow(state) => Some(state),
+        _ => None,
+    })
+}
+
+/// Native SOL escrows `party` takes part in, in every layout version,
+/// ordered by address.
+pub fn find_sol_escrows(
+    rpc: &impl ProgramAccounts,
+    program_id: &Pubkey,
+    party: Party,
+) -> ClientResult<Vec<(Pubkey, SolEscrowState)>> {
+    find(rpc, program_id, party, |account| match account {
+        ProgramAccount::SolEscrow(state) => Some(state),
+        _ => None,
+    })
+}
+
+/// Runs the queries of `T` and keeps the accounts that decode as `T` with
+/// `party` in place. Memcmp filters only compare bytes and a headerless
+/// account can start with a discriminator, so every match is decoded and
+/// checked again.
+/// Accounts that don't decode, like uninitialized ones, are left out.
+fn find<T: AccountLayout>(
+    rpc: &impl ProgramAccounts,
+    program_id: &Pubkey,
+    party: Party,
+    pick: fn(ProgramAccount) -> Option<T>,
+) -> ClientResult<Vec<(Pubkey, T)>> {
+    let offset = filters::offset_of::<T>(party);
+    let mut found = BTreeMap::new();
+    for query in filters::queries::<T>(party) {
+        for (address, account) in rpc.program_accounts(program_id, query)? {
+            let Ok(decoded) = decoder::decode_account(&account.data) else {
+                continue;
+            };
+            let Some(state) = pick(decoded) else { continue };
+            let mut data = vec![0; T::LEN];
+            state.pack_into_slice(&mut data);
+            if data[offset..offset + 32] == party.pubkey().to_bytes() {
+                found.insert(address, state);
+            }
+        }
+    }
+    Ok(found.into_iter().collect())
+}
@@ -0,0 +1,86 @@
+//! Where program accounts are read from: a cluster through [`RpcClient`],
+//! or [`MemoryRpc`] in tests.
+
+use std::collections::BTreeMap;
+
+use solana_account_decoder::UiAccountEncoding;
+use solana_rpc_client::rpc_client::RpcClient;
+use solana_rpc_client_api::{
+    client_error::Result as ClientResult,
+    config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    filter::RpcFilterType,
+};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    pubkey::Pubkey,
+};
+
+/// The `getProgramAccounts` RPC method.
+pub trait ProgramAccounts {
+    /// Accounts owned by `program_id` that pass every filter.
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, Account)>>;
+}
+
+impl ProgramAccounts for RpcClient {
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        let config = RpcProgramAccountsConfig {
+            filters: Some(filters),
+            account_config: RpcAccountInfoConfig {
+                // The default base58 encoding is limited to 128 bytes.
+                encoding: Some(UiAccountEncoding::Base64),
+                ..RpcAccountInfoConfig::default()
+            },
+            ..RpcProgramAccountsConfig::default()
+        };
+        self.get_program_accounts_with_config(program_id, config)
+    }
+}
+
+/// An in-memory stand-in for a cluster, applying filters the way the RPC
+/// node does.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryRpc {
+    accounts: BTreeMap<Pubkey, Account>,
+}
+
+impl MemoryRpc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds or replaces the account at `address`.
+    pub fn set_account(&mut self, address: Pubkey, account: Account) {
+        self.accounts.insert(address, account);
+    }
+
+    pub fn remove_account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.accounts.remove(address)
+    }
+}
+
+impl ProgramAccounts for MemoryRpc {
+    fn program_accounts(
+        &self,
+        program_id: &Pubkey,
+        filters: Vec<RpcFilterType>,
+    ) -> ClientResult<Vec<(Pubkey, Account)>> {
+        Ok(self
+            .accounts
+            .iter()
+            .filter(|(_, account)| account.owner == *program_id)
+            .filter(|(_, account)| {
+                let account = AccountSharedData::from((*account).clone());
+                filters.iter().all(|filter| filter.allows(&account))
+            })
+            .map(|(address, account)| (*address, account.clone()))
+            .collect())
+    }
+}
@@ -0,0 +1,165 @@
+use solana_escrow::{
+    instruction::find_sol_vault,
+    states::{default_escrow::EscrowState, layout::AccountLayout, sol_escrow::SolEscrowState},
+    utils::{constants, time_basis::TimeBasis},
+};
+use solana_escrow_client::{filters, find_escrows, find_sol_escrows, MemoryRpc, Party};
+use solana_rpc_client_api::filter::{Memcmp, RpcFilterType};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+fn escrow(caller: Pubkey, initializer: Pubkey, receiver: Pubkey) -> EscrowState {
+    EscrowState {
+        is_initialized: true,
+        caller_pubkey: caller,
+        initializer_pubkey: initializer,
+        receiver_pubkey: receiver,
+        temp_token_account_pubkey: Pubkey::new_unique(),
+        escrow_amount: 42,
+        expire_date: 1_700_000_000,
+        voucher_nonce: 0,
+        time_basis: TimeBasis::UnixTimestamp,
+        refund_after: 1_700_604_800,
+        reference: Some([7; 32]),
+        log_memo: false,
+        memo: "order 7".to_string(),
+        escrow_index: 1,
+    }
+}
+
+/// A token escrow as written before the layout header existed.
+fn legacy_escrow(caller: &Pubkey, initializer: &Pubkey, receiver: &Pubkey) -> Vec<u8> {
+    let mut data = vec![1];
+    data.extend_from_slice(caller.as_ref());
+    data.extend_from_slice(initializer.as_ref());
+    data.extend_from_slice(receiver.as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(&42u64.to_le_bytes());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    assert_eq!(data.len(), constants::LEGACY_DEFAULT_ESCROW_STATE_LEN);
+    data
+}
+
+fn account(owner: &Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: 1_000_000,
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn packed<T: AccountLayout>(state: &T) -> Vec<u8> {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+    data
+}
+
+fn memcmp(offset: usize, bytes: &[u8]) -> RpcFilterType {
+    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, bytes))
+}
+
+#[test]
+fn filters_sit_at_the_layout_offsets() {
+    let key = Pubkey::new_unique();
+    for (party, offset) in [
+        (Party::Caller(key), 3),
+        (Party::Initializer(key), 35),
+        (Party::Receiver(key), 67),
+    ] {
+        assert_eq!(filters::offset_of::<EscrowState>(party), offset);
+        assert_eq!(filters::offset_of::<SolEscrowState>(party), offset);
+        assert_eq!(
+            filters::filters::<EscrowState>(party),
+            [memcmp(0, &[EscrowState::DISCRIMINATOR]), memcmp(offset, key.as_ref())]
+        );
+        assert_eq!(
+            filters::legacy_filters::<EscrowState>(party).unwrap(),
+            [
+                RpcFilterType::DataSize(constants::LEGACY_DEFAULT_ESCROW_STATE_LEN as u64),
+                memcmp(offset - 2, key.as_ref()),
+            ]
+        );
+    }
+}
+
+#[test]
+fn finds_current_and_legacy_escrows_by_party() {
+    let program_id = Pubkey::new_unique();
+    let (caller, initializer, receiver) =
+        (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut rpc = MemoryRpc::new();
+
+    let current = Pubkey::new_unique();
+    let state = escrow(caller, initializer, receiver);
+    rpc.set_account(current, account(&program_id, packed(&state)));
+    let legacy = Pubkey::new_unique();
+    rpc.set_account(legacy, account(&program_id, legacy_escrow(&caller, &initializer, &receiver)));
+    // Someone else's escrow, and one with the same parties under another program.
+    let other = escrow(Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    rpc.set_account(Pubkey::new_unique(), account(&program_id, packed(&other)));
+    rpc.set_account(Pubkey::new_unique(), account(&Pubkey::new_unique(), packed(&state)));
+
+    for party in [Party::Caller(caller), Party::Initializer(initializer), Party::Receiver(receiver)] {
+        let found = find_escrows(&rpc, &program_id, party).unwrap();
+        let mut expected = vec![current, legacy];
+        expected.sort();
+        assert_eq!(found.iter().map(|(address, _)| *address).collect::<Vec<_>>(), expected);
+        let (_, decoded) = found.iter().find(|(address, _)| *address == current).unwrap();
+        assert_eq!(*decoded, state);
+        let (_, decoded) = found.iter().find(|(address, _)| *address == legacy).unwrap();
+        assert_eq!(decoded.escrow_amount, 42);
+        assert_eq!(decoded.refund_after, 0);
+        assert!(find_sol_escrows(&rpc, &program_id, party).unwrap().is_empty());
+    }
+
+    // Receiver filters don't match escrows the key only initialized.
+    assert!(find_escrows(&rpc, &program_id, Party::Receiver(initializer))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn tells_sol_escrows_from_token_escrows() {
+    let program_id = Pubkey::new_unique();
+    let initializer = Pubkey::new_unique();
+    let mut rpc = MemoryRpc::new();
+
+    let sol_escrow = Pubkey::new_unique();
+    let state = SolEscrowState {
+        is_initialized: true,
+        caller_pubkey: initializer,
+        initializer_pubkey: initializer,
+        receiver_pubkey: Pubkey::new_unique(),
+        vault_pubkey: find_sol_vault(&program_id, &sol_escrow).0,
+        escrow_amount: 1_000,
+        expire_date: 1_700_000_000,
+        refund_after: 1_700_604_800,
+    };
+    rpc.set_account(sol_escrow, account(&program_id, packed(&state)));
+    let token_escrow = Pubkey::new_unique();
+    rpc.set_account(
+        token_escrow,
+        account(&program_id, legacy_escrow(&initializer, &initializer, &Pubkey::new_unique())),
+    );
+
+    let party = Party::Initializer(initializer);
+    assert_eq!(find_sol_escrows(&rpc, &program_id, party).unwrap(), [(sol_escrow, state)]);
+    let found = find_escrows(&rpc, &program_id, party).unwrap();
+    assert_eq!(found.iter().map(|(address, _)| *address).collect::<Vec<_>>(), [token_escrow]);
+}
+
+#[test]
+fn skips_accounts_that_do_not_decode() {
+    let program_id = Pubkey::new_unique();
+    let initializer = Pubkey::new_unique();
+    let mut rpc = MemoryRpc::new();
+
+    let mut uninitialized = packed(&escrow(Pubkey::new_unique(), initializer, Pubkey::new_unique()));
+    uninitialized[2] = 0;
+    rpc.set_account(Pubkey::new_unique(), account(&program_id, uninitialized));
+
+    assert!(find_escrows(&rpc, &program_id, Party::Initializer(initializer))
+        .unwrap()
+        .is_empty());
+}
@@ -19,6 +19,10 @@ const BN = require("bn.js");
 const {
   getKeypair,
   ESCROW_ACCOUNT_DATA_LAYOUT,
+  findEscrowAccount,
+  findProgramConfig,
+  findUserProfile,
+  nextEscrowIndex,
   getPublicKey,
   getTerms,
   getTokenBalance,
@@ -61,23 +65,28 @@ const alice = async () => {
     terms.transferAmount
   );
 
-  const escrowKeypair = new Account();
-  const createEscrowAccountIX = SystemProgram.createAccount({
-    space: ESCROW_ACCOUNT_DATA_LAYOUT.span,
-    lamports: await connection.getMinimumBalanceForRentExemption(
-      ESCROW_ACCOUNT_DATA_LAYOUT.span
-    ),
-    fromPubkey: callerAcc.publicKey,
-    newAccountPubkey: escrowKeypair.publicKey,
-    programId: escrowProgramId,
-  });
+  // The program creates the escrow at the next index of Alice's profile.
+  const escrowIndex = await nextEscrowIndex(
+    connection,
+    escrowProgramId,
+    initializerAccount.publicKey
+  );
+  const escrowPubkey = await findEscrowAccount(
+    escrowProgramId,
+    initializerAccount.publicKey,
+    escrowIndex
+  );
+  const userProfilePubkey = await findUserProfile(
+    escrowProgramId,
+    initializerAccount.publicKey
+  );
 
   const initEscrowIUsdc = new TransactionInstruction({
     programId: escrowProgramId,
     keys: [
       {
         pubkey: initializerAccount.publicKey,
-        isSigner: false,
+        isSigner: true,
         isWritable: false,
       },
       {
@@ -90,17 +99,25 @@ const alice = async () => {
         isSigner: false,
         isWritable: true,
       },
-      { pubkey: escrowKeypair.publicKey, isSigner: false, isWritable: true },
+      { pubkey: escrowPubkey, isSigner: false, isWritable: true },
       { pubkey: SYSVAR_RENT_PUBKEY, isSigner: false, isWritable: false },
       { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
       {
         pubkey: callerAcc.publicKey,
         isSigner: true,
+        isWritable: true,
+      },
+      { pubkey: usdcTokenMintPubkey, isSigner: false, isWritable: false },
+      { pubkey: userProfilePubkey, isSigner: false, isWritable: true },
+      { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      {
+        pubkey: await findProgramConfig(escrowProgramId),
+        isSigner: false,
         isWritable: false,
       },
     ],
     data: Buffer.from(
-      Uint8Array.of(0, ...new BN(terms.transferAmount).toArray("le", 1))
+      Uint8Array.of(0, ...new BN(terms.transferAmount).toArray("le", 8))
     ),
   });
 
@@ -108,7 +125,6 @@ const alice = async () => {
     tempTokenAccountIX,
     initTempAccountIX,
     transferUsdcTokensToTempAccIX,
-    createEscrowAccountIX,
     initEscrowIUsdc
   );
 
@@ -116,17 +132,14 @@ const alice = async () => {
     callerAcc,
     initializerAccount,
     tempUsdcTokenAccountKeypair,
-    escrowKeypair,
   ]);
-  console.log("Escrow Account:", escrowKeypair.publicKey);
+  console.log("Escrow Account:", escrowPubkey);
   console.log("Transaction Hash:", res);
 
   // sleep to allow time to update
   await new Promise((resolve) => setTimeout(resolve, 1000));
 
-  const escrowAccount = await connection.getAccountInfo(
-    escrowKeypair.publicKey
-  );
+  const escrowAccount = await connection.getAccountInfo(escrowPubkey);
 
   if (escrowAccount === null || escrowAccount.data.length === 0) {
     logError("Escrow state account has not been initialized properly");
@@ -159,11 +172,16 @@ const alice = async () => {
       "initializerTempTokenAccountPubkey has not been set correctly / not been set to temp Usdc token account public key"
     );
     process.exit(1);
+  } else if (
+    new BN(decodedEscrowState.escrowIndex, 10, "le").toNumber() !== escrowIndex
+  ) {
+    logError("escrowIndex has not been set to the next index of Alice's profile");
+    process.exit(1);
   }
   console.log(
     `✨Escrow successfully initialized. Alice is offering ${terms.transferAmount}Usdc\n`
   );
-  writePublicKey(escrowKeypair.publicKey, "escrow");
+  writePublicKey(escrowPubkey, "escrow");
   console.table([
     {
       "Alice Token Account Usdc": await getTokenBalance(
@@ -8,6 +8,7 @@ const BN = require("bn.js");
 import {
   EscrowLayout,
   ESCROW_ACCOUNT_DATA_LAYOUT,
+  findUserProfile,
   getKeypair,
   getProgramId,
   getPublicKey,
@@ -84,7 +85,18 @@ const bob = async () => {
       { pubkey: escrowStateAccountPubkey, isSigner: false, isWritable: true },
       { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
       { pubkey: PDA[0], isSigner: false, isWritable: false },
-      { pubkey: callerAcc.publicKey, isSigner: true, isWritable: false },
+      { pubkey: callerAcc.publicKey, isSigner: true, isWritable: true },
+      { pubkey: getPublicKey("mint_usdc"), isSigner: false, isWritable: true },
+      {
+        pubkey: await findUserProfile(
+          escrowProgramId,
+          escrowState.initializerAccountPubkey
+        ),
+        isSigner: false,
+        isWritable: true,
+      },
+      // Gets back the tokens the temp account holds above the escrowed amount
+      { pubkey: getPublicKey("alice_usdc"), isSigner: false, isWritable: true },
     ],
   });
 
@@ -1,5 +1,6 @@
 import { Connection, Keypair, PublicKey } from "@solana/web3.js";
 const BufferLayout = require("buffer-layout");
+const BN = require("bn.js");
 
 import * as fs from "fs";
 
@@ -71,17 +72,36 @@ const uint64 = (property = "uint64") => {
   return BufferLayout.blob(8, property);
 };
 
+/**
+ * Layout for a 64bit signed value
+ */
+const int64 = (property = "int64") => {
+  return BufferLayout.blob(8, property);
+};
+
 export const ESCROW_ACCOUNT_DATA_LAYOUT = BufferLayout.struct([
+  BufferLayout.u8("discriminator"),
+  BufferLayout.u8("version"),
   BufferLayout.u8("isInitialized"),
   publicKey("callerPubkey"),
   publicKey("initializerPubkey"),
   publicKey("receiverAccountPubkey"),
   publicKey("initializerTempTokenAccountPubkey"),
   uint64("expectedAmount"),
-  uint64("expireDate"),
+  int64("expireDate"),
+  uint64("voucherNonce"),
+  BufferLayout.u8("timeBasis"),
+  int64("refundAfter"),
+  BufferLayout.blob(32, "reference"),
+  BufferLayout.u8("logMemo"),
+  BufferLayout.u8("memoLen"),
+  BufferLayout.blob(64, "memo"),
+  uint64("escrowIndex"),
 ]);
 
 export interface EscrowLayout {
+  discriminator: number;
+  version: number;
   isInitialized: number;
   callerPubkey: Uint8Array;
   initializerPubkey: Uint8Array;
@@ -89,4 +109,99 @@ export interface EscrowLayout {
   initializerTempTokenAccountPubkey: Uint8Array;
   expectedAmount: Uint8Array;
   expireDate: Uint8Array;
+  voucherNonce: Uint8Array;
+  timeBasis: number;
+  refundAfter: Uint8Array;
+  reference: Uint8Array;
+  logMemo: number;
+  memoLen: number;
+  memo: Uint8Array;
+  escrowIndex: Uint8Array;
 }
+
+export const USER_PROFILE_ACCOUNT_DATA_LAYOUT = BufferLayout.struct([
+  BufferLayout.u8("discriminator"),
+  BufferLayout.u8("version"),
+  BufferLayout.u8("isInitialized"),
+  publicKey("initializerPubkey"),
+  uint64("escrowCount"),
+  uint64("openEscrows"),
+  uint64("totalVolume"),
+  uint64("disputes"),
+]);
+
+export interface UserProfileLayout {
+  discriminator: number;
+  version: number;
+  isInitialized: number;
+  initializerPubkey: Uint8Array;
+  escrowCount: Uint8Array;
+  openEscrows: Uint8Array;
+  totalVolume: Uint8Array;
+  disputes: Uint8Array;
+}
+
+/**
+ * The PDA counting the token escrows of `initializer`
+ */
+export const findUserProfile = async (
+  programId: PublicKey,
+  initializer: PublicKey
+) => {
+  const [userProfile] = await PublicKey.findProgramAddress(
+    [Buffer.from("user_profile"), initializer.toBuffer()],
+    programId
+  );
+  return userProfile;
+};
+
+/**
+ * The PDA holding the program's settings, such as its open escrow cap
+ */
+export const findProgramConfig = async (programId: PublicKey) => {
+  const [programConfig] = await PublicKey.findProgramAddress(
+    [Buffer.from("program_config")],
+    programId
+  );
+  return programConfig;
+};
+
+/**
+ * The PDA of the token escrow of `initializer` at `escrowIndex`
+ */
+export const findEscrowAccount = async (
+  programId: PublicKey,
+  initializer: PublicKey,
+  escrowIndex: number
+) => {
+  const [escrow] = await PublicKey.findProgramAddress(
+    [
+      Buffer.from("escrow"),
+      initializer.toBuffer(),
+      new BN(escrowIndex).toArrayLike(Buffer, "le", 8),
+    ],
+    programId
+  );
+  return escrow;
+};
+
+/**
+ * Index of the next token escrow of `initializer`, its profile's escrow count
+ * plus one, or 1 before its first escrow
+ */
+export const nextEscrowIndex = async (
+  connection: Connection,
+  programId: PublicKey,
+  initializer: PublicKey
+) => {
+  const userProfile = await connection.getAccountInfo(
+    await findUserProfile(programId, initializer)
+  );
+  if (userProfile === null) {
+    return 1;
+  }
+  const decoded = USER_PROFILE_ACCOUNT_DATA_LAYOUT.decode(
+    userProfile.data
+  ) as UserProfileLayout;
+  return new BN(decoded.escrowCount, 10, "le").toNumber() + 1;
+};
@@ -0,0 +1,467 @@
+//! Runs arbitrary sequences of escrow instructions, sent by arbitrary parties
+//! with arbitrary accounts, against a `ProgramTest` bank, and checks after
+//! every transaction that:
+//!
+//! - tokens only leave a PDA owned account (escrow temp account or registry
+//!   vault) for the recorded receiver on release, the recorded initializer
+//!   on refund, or for the account named
+//!   by the signing depositor or registry owner on withdraw and collect,
+//! - escrowed lamports only go to the recorded receiver or initializer,
+//! - no instruction creates or burns tokens,
+//! - every open escrow is still backed: temp accounts and vaults hold at
+//!   least what the escrow states and deposit ledgers say they do,
+//! - each user profile counts exactly the open token escrows of its
+//!   initializer.
+
+#![no_main]
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use std::{cell::RefCell, sync::OnceLock};
+
+use arbitrary::Arbitrary;
+use common::{Harness, WalletEscrow};
+use libfuzzer_sys::fuzz_target;
+use solana_escrow::{
+    instruction,
+    states::{
+        default_escrow::EscrowState, registry_mint::RegistryMintState, sol_escrow::SolEscrowState,
+        user_profile::UserProfileState,
+    },
+    utils::{constants::ESCROW_RELEASE_WINDOW_SEC, memo::EscrowMemo, time_basis::TimeBasis},
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::Instruction,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+};
+
+const USERS: usize = 3;
+const MAX_ACTIONS: usize = 16;
+const TOKENS: u64 = 10_000;
+/// Each input funds new users from the bank's payer, so keep it small.
+const LAMPORTS: u64 = 100_000_000;
+
+/// User indices and escrow indices wrap around, so every value picks one.
+#[derive(Arbitrary, Debug)]
+enum Action {
+    Init { initializer: u8, receiver: u8, amount: u16, slot_basis: bool, log_memo: bool },
+    Release { escrow: u8, initializer: u8, to: u8 },
+    Refund { escrow: u8, to: u8 },
+    InitSol { initializer: u8, receiver: u8, amount: u32 },
+    ReleaseSol { escrow: u8, to: u8 },
+    RefundSol { escrow: u8, to: u8 },
+    Deposit { depositor: u8, amount: u16 },
+    Withdraw { depositor: u8, to: u8, amount: u16 },
+    Collect { depositor: u8, early: bool },
+    /// `close_window` also skips a release window ahead.
+    Warp { seconds: u8, close_window: bool },
+}
+
+/// Where a successful transaction may move value. Tokens may only leave
+/// `sources`, and only for accounts owned by `recipients`. Escrowed lamports
+/// may only reach `lamports_to`, in full.
+#[derive(Default)]
+struct Flow {
+    sources: Vec<Pubkey>,
+    recipients: Vec<Pubkey>,
+    lamports_to: Option<(Pubkey, u64)>,
+}
+
+#[derive(Clone, Copy)]
+struct Balance {
+    address: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+}
+
+struct TokenEscrowModel {
+    escrow: Pubkey,
+    temp: Pubkey,
+}
+
+struct World<'a> {
+    harness: &'a mut Harness,
+    users: Vec<Keypair>,
+    token_program: Pubkey,
+    mint: Pubkey,
+    /// Each user's token account of `mint`.
+    token_accounts: Vec<Pubkey>,
+    wallet: WalletEscrow,
+    /// Each user's token account of the wallet escrow mint.
+    wallet_accounts: Vec<Pubkey>,
+    escrows: Vec<TokenEscrowModel>,
+    sol_escrows: Vec<Pubkey>,
+}
+
+impl<'a> World<'a> {
+    async fn start(harness: &'a mut Harness) -> World<'a> {
+        let token_program = spl_token::id();
+        let mint = harness.create_mint(&token_program).await;
+        let wallet = WalletEscrow::create(harness, &token_program).await;
+        let caller = harness.caller();
+        let mut users = Vec::new();
+        let mut token_accounts = Vec::new();
+        let mut wallet_accounts = Vec::new();
+        for _ in 0..USERS {
+            let user = Keypair::new();
+            harness
+                .process(&[system_instruction::transfer(&caller, &user.pubkey(), LAMPORTS)], &[])
+                .await
+                .unwrap();
+            let token_account = harness.create_token_account(&token_program, &mint, &user.pubkey()).await;
+            harness.mint_to(&token_program, &mint, &token_account, TOKENS).await;
+            let wallet_account = harness
+                .create_token_account(&token_program, &wallet.mint, &user.pubkey())
+                .await;
+            harness.mint_to(&token_program, &wallet.mint, &wallet_account, TOKENS).await;
+            users.push(user);
+            token_accounts.push(token_account);
+            wallet_accounts.push(wallet_account);
+        }
+        Self {
+            harness,
+            users,
+            token_program,
+            mint,
+            token_accounts,
+            wallet,
+            wallet_accounts,
+            escrows: Vec::new(),
+            sol_escrows: Vec::new(),
+        }
+    }
+
+    fn user(&self, index: u8) -> Pubkey {
+        self.users[index as usize % USERS].pubkey()
+    }
+
+    async fn apply(&mut self, action: &Action) {
+        let program_id = self.harness.program_id;
+        let caller = self.harness.caller();
+        match *action {
+            Action::Init { initializer, receiver, amount, slot_basis, log_memo } => {
+                let temp = self
+                    .harness
+                    .create_token_account(&self.token_program, &self.mint, &caller)
+                    .await;
+                self.harness.mint_to(&self.token_program, &self.mint, &temp, amount.into()).await;
+                let initializer = self.user(initializer);
+                let (user_profile, _) = instruction::find_user_profile(&program_id, &initializer);
+                let escrow_count = self
+                    .state::<UserProfileState>(&user_profile)
+                    .await
+                    .map_or(0, |profile| profile.escrow_count);
+                let (escrow, _) = instruction::find_escrow_account(&program_id, &initializer, escrow_count + 1);
+                self.escrows.push(TokenEscrowModel { escrow, temp });
+                let init = instruction::init_escrow(
+                    &program_id,
+                    &initializer,
+                    &self.user(receiver),
+                    &temp,
+                    &escrow,
+                    &self.token_program,
+                    &caller,
+                    &self.mint,
+                    amount.into(),
+                    if slot_basis { TimeBasis::Slot } else { TimeBasis::UnixTimestamp },
+                    &EscrowMemo {
+                        reference: Some([amount as u8; 32]),
+                        memo: "fuzz".to_string(),
+                        log_memo,
+                    },
+                );
+                self.transact(init, &[initializer], Flow::default()).await;
+            }
+            Action::Release { escrow, initializer, to } => {
+                let Some(model) = pick(&self.escrows, escrow) else { return };
+                let (escrow, temp) = (model.escrow, model.temp);
+                let to = to as usize % USERS;
+                let state = self.state::<EscrowState>(&escrow).await;
+                let flow = match &state {
+                    Some(state) => Flow {
+                        sources: vec![temp],
+                        recipients: vec![state.receiver_pubkey, state.initializer_pubkey],
+                        ..Flow::default()
+                    },
+                    None => Flow::default(),
+                };
+                let mut release = instruction::release_escrow(
+                    &program_id,
+                    &self.token_accounts[to],
+                    &temp,
+                    &self.user(initializer),
+                    &self.token_accounts[initializer as usize % USERS],
+                    &escrow,
+                    &self.token_program,
+                    &caller,
+                    &self.mint,
+                );
+                if state.is_some_and(|state| state.log_memo) {
+                    instruction::append_memo_program(&mut release);
+                }
+                self.transact(release, &[], flow).await;
+            }
+            Action::Refund { escrow, to } => {
+                let Some(model) = pick(&self.escrows, escrow) else { return };
+                let to = to as usize % USERS;
+                let (escrow, temp) = (model.escrow, model.temp);
+                let flow = match self.state::<EscrowState>(&escrow).await {
+                    Some(state) => Flow {
+                        sources: vec![temp],
+                        recipients: vec![state.initializer_pubkey],
+                        ..Flow::default()
+                    },
+                    None => Flow::default(),
+                };
+                let refund = instruction::refund_escrow(
+                    &program_id,
+                    &self.users[to].pubkey(),
+                    &self.token_accounts[to],
+                    &temp,
+                    &escrow,
+                    &self.token_program,
+                    &caller,
+                    &self.mint,
+                );
+                self.transact(refund, &[], flow).await;
+            }
+            Action::InitSol { initializer, receiver, amount } => {
+                let escrow = self.harness.create_state_account(SolEscrowState::LEN).await.pubkey();
+                self.sol_escrows.push(escrow);
+                let initializer = self.user(initializer);
+                let init = instruction::init_sol_escrow(
+                    &program_id,
+                    &initializer,
+                    &self.user(receiver),
+                    &escrow,
+                    &caller,
+                    amount.into(),
+                );
+                self.transact(init, &[initializer], Flow::default()).await;
+            }
+            Action::ReleaseSol { escrow, to } => {
+                let Some(&escrow) = pick(&self.sol_escrows, escrow) else { return };
+                let flow = match self.state::<SolEscrowState>(&escrow).await {
+                    Some(state) => Flow {
+                        lamports_to: Some((state.receiver_pubkey, state.escrow_amount)),
+                        ..Flow::default()
+                    },
+                    None => Flow::default(),
+                };
+                let release = instruction::release_sol_escrow(&program_id, &self.user(to), &escrow, &caller);
+                self.transact(release, &[], flow).await;
+            }
+            Action::RefundSol { escrow, to } => {
+                let Some(&escrow) = pick(&self.sol_escrows, escrow) else { return };
+                let flow = match self.state::<SolEscrowState>(&escrow).await {
+                    Some(state) => Flow {
+                        lamports_to: Some((state.initializer_pubkey, state.escrow_amount)),
+                        ..Flow::default()
+                    },
+                    None => Flow::default(),
+                };
+                let refund = instruction::refund_sol_escrow(&program_id, &self.user(to), &escrow, &caller, true);
+                self.transact(refund, &[], flow).await;
+            }
+            Action::Deposit { depositor, amount } => {
+                let from = self.wallet_accounts[depositor as usize % USERS];
+                let depositor = self.user(depositor);
+                let deposit = self.wallet.deposit(self.harness, &depositor, &from, amount.into());
+                let flow = Flow {
+                    sources: vec![from],
+                    recipients: vec![instruction::find_wallet_escrow_authority(&program_id).0],
+                    ..Flow::default()
+                };
+                self.transact(deposit, &[depositor], flow).await;
+            }
+            Action::Withdraw { depositor, to, amount } => {
+                let recipient = self.user(to);
+                let to = self.wallet_accounts[to as usize % USERS];
+                let depositor = self.user(depositor);
+                let withdraw = self.wallet.withdraw(self.harness, &depositor, &to, amount.into());
+                let flow = Flow {
+                    sources: vec![self.wallet.vault],
+                    recipients: vec![recipient],
+                    ..Flow::default()
+                };
+                self.transact(withdraw, &[depositor], flow).await;
+            }
+            Action::Collect { depositor, early } => {
+                let depositor = self.user(depositor);
+                let collect = self.wallet.collect(self.harness, &depositor, early);
+                let flow = Flow {
+                    sources: vec![self.wallet.vault],
+                    recipients: vec![self.wallet.owner.pubkey()],
+                    ..Flow::default()
+                };
+                let owner = self.wallet.owner.pubkey();
+                let signers = if early { vec![owner, depositor] } else { vec![owner] };
+                self.transact(collect, &signers, flow).await;
+            }
+            Action::Warp { seconds, close_window } => {
+                let now = self.harness.clock().await.unix_timestamp;
+                let window = if close_window { ESCROW_RELEASE_WINDOW_SEC } else { 0 };
+                self.harness.warp_past(now + window + i64::from(seconds)).await;
+            }
+        }
+    }
+
+    /// Sends `instruction` signed by `signers`, and checks that whatever it
+    /// moved matches `flow`. A failed transaction must move nothing.
+    async fn transact(&mut self, instruction: Instruction, signers: &[Pubkey], flow: Flow) {
+        let tokens_before = self.token_balances().await;
+        let lamports_before = self.user_lamports().await;
+        let keypairs: Vec<&Keypair> = signers
+            .iter()
+            .map(|signer| {
+                self.users
+                    .iter()
+                    .chain([&self.wallet.owner])
+                    .find(|keypair| keypair.pubkey() == *signer)
+                    .unwrap()
+            })
+            .collect();
+        let succeeded = self.harness.process(&[instruction], &keypairs).await.is_ok();
+        let tokens_after = self.token_balances().await;
+        let lamports_after = self.user_lamports().await;
+
+        let flow = if succeeded { flow } else { Flow::default() };
+        let (mut sent, mut received) = (0, 0);
+        for (before, after) in tokens_before.iter().zip(&tokens_after) {
+            if after.amount < before.amount {
+                assert!(
+                    flow.sources.contains(&before.address),
+                    "tokens left {} unexpectedly",
+                    before.address
+                );
+                sent += before.amount - after.amount;
+            } else if after.amount > before.amount {
+                assert!(
+                    flow.recipients.contains(&before.owner),
+                    "tokens reached {}, owned by {}",
+                    before.address,
+                    before.owner
+                );
+                received += after.amount - before.amount;
+            }
+        }
+        assert_eq!(sent, received, "tokens were created or burnt");
+
+        if let Some((recipient, amount)) = flow.lamports_to {
+            for ((user, before), after) in self.users.iter().zip(&lamports_before).zip(&lamports_after) {
+                let expected = if user.pubkey() == recipient { before + amount } else { *before };
+                assert_eq!(*after, expected, "escrowed lamports reached {}", user.pubkey());
+            }
+        }
+    }
+
+    /// Balances of every token account the fuzzer knows of, closed accounts
+    /// read as empty.
+    async fn token_balances(&mut self) -> Vec<Balance> {
+        let mut addresses = self.token_accounts.clone();
+        addresses.extend(&self.wallet_accounts);
+        addresses.extend([self.wallet.vault, self.wallet.owner_token_account]);
+        addresses.extend(self.escrows.iter().map(|model| model.temp));
+        let mut balances = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let (owner, amount) = match self.harness.account(&address).await {
+                Some(_) => {
+                    let account = self.harness.token_account(&address).await;
+                    (account.owner, account.amount)
+                }
+                None => (Pubkey::default(), 0),
+            };
+            balances.push(Balance { address, owner, amount });
+        }
+        balances
+    }
+
+    async fn user_lamports(&mut self) -> Vec<u64> {
+        let mut lamports = Vec::with_capacity(USERS);
+        for index in 0..USERS {
+            let user = self.users[index].pubkey();
+            lamports.push(self.harness.lamports(&user).await);
+        }
+        lamports
+    }
+
+    /// The state held in `address`, if it is still open.
+    async fn state<T: Pack + IsInitialized>(&mut self, address: &Pubkey) -> Option<T> {
+        let account = self.harness.account(address).await?;
+        T::unpack(&account.data).ok()
+    }
+
+    /// Every open escrow is backed by what its vault holds.
+    async fn check_backing(&mut self) {
+        for index in 0..self.escrows.len() {
+            let (escrow, temp) = (self.escrows[index].escrow, self.escrows[index].temp);
+            if let Some(state) = self.state::<EscrowState>(&escrow).await {
+                assert!(self.harness.token_balance(&temp).await >= state.escrow_amount);
+            }
+        }
+        for index in 0..self.sol_escrows.len() {
+            let escrow = self.sol_escrows[index];
+            if let Some(state) = self.state::<SolEscrowState>(&escrow).await {
+                assert!(self.harness.lamports(&state.vault_pubkey).await >= state.escrow_amount);
+            }
+        }
+        let registry_mint = self.wallet.registry_mint(self.harness);
+        let ledger: RegistryMintState = self.harness.state(&registry_mint).await;
+        assert!(self.harness.token_balance(&self.wallet.vault).await >= ledger.total_deposits);
+    }
+
+    /// Every profile counts the open token escrows of its initializer.
+    async fn check_profiles(&mut self) {
+        let mut open = [0; USERS];
+        for index in 0..self.escrows.len() {
+            let escrow = self.escrows[index].escrow;
+            if let Some(state) = self.state::<EscrowState>(&escrow).await {
+                let user = self.users.iter().position(|user| user.pubkey() == state.initializer_pubkey);
+                open[user.unwrap()] += 1;
+            }
+        }
+        for (index, open) in open.into_iter().enumerate() {
+            let user = self.users[index].pubkey();
+            let (user_profile, _) = instruction::find_user_profile(&self.harness.program_id, &user);
+            let profile = self.state::<UserProfileState>(&user_profile).await;
+            assert_eq!(profile.map_or(0, |profile| profile.open_escrows), open);
+        }
+    }
+}
+
+fn pick<T>(items: &[T], index: u8) -> Option<&T> {
+    items.get(index as usize % items.len().max(1))
+}
+
+static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+
+thread_local! {
+    /// One bank for the whole run, as a bank started per input leaks memory.
+    /// Each input gets its own parties, mint and registry on it instead.
+    static HARNESS: RefCell<Option<Harness>> = const { RefCell::new(None) };
+}
+
+fuzz_target!(|actions: Vec<Action>| {
+    let runtime = RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap()
+    });
+    HARNESS.with_borrow_mut(|harness| {
+        runtime.block_on(async {
+            if harness.is_none() {
+                *harness = Some(Harness::start().await);
+            }
+            let mut world = World::start(harness.as_mut().unwrap()).await;
+            for action in actions.iter().take(MAX_ACTIONS) {
+                world.apply(action).await;
+                world.check_backing().await;
+                world.check_profiles().await;
+            }
+        })
+    });
+});
@@ -0,0 +1,15 @@
+//! Instruction data is untrusted: decoding must never panic, and whatever
+//! decodes must encode back to the bytes it was read from.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_escrow::route::EscrowRoutes;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(route) = EscrowRoutes::unpack(data) {
+        let packed = route.pack();
+        assert_eq!(packed[..], data[..packed.len()]);
+        assert_eq!(EscrowRoutes::unpack(&packed).unwrap(), route);
+    }
+});
@@ -0,0 +1,80 @@
+//! Account data is untrusted: unpacking any state type, at any layout
+//! version, must never panic, and whatever unpacks must pack back to the
+//! bytes it was read from. Zero-copy views must accept exactly the accounts
+//! `Pack` does, and read the same fields from them.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use solana_escrow::{
+    decoder,
+    states::{
+        default_escrow::{EscrowState, PodEscrowState},
+        deposit_record::DepositRecordState,
+        escrow_registry::{EscrowRegistryState, PodEscrowRegistryState},
+        layout::{AccountLayout, ZeroCopy},
+        program_config::ProgramConfigState,
+        registry_mint::RegistryMintState,
+        sol_escrow::SolEscrowState,
+        user_profile::UserProfileState,
+    },
+    utils::voucher::{Voucher, VOUCHER_LEN},
+};
+use solana_sdk::program_pack::{IsInitialized, Pack};
+
+fn check<T: AccountLayout + IsInitialized>(data: &[u8]) {
+    // An uninitialized account may have a zeroed header, which packing
+    // fills in.
+    if let Ok(state) = T::unpack(data.get(..T::LEN).unwrap_or(data)) {
+        let mut packed = vec![0; T::LEN];
+        state.pack_into_slice(&mut packed);
+        assert_eq!(packed[..], data[..T::LEN]);
+    }
+    let _ = T::unpack_unchecked(data.get(..T::LEN).unwrap_or(data));
+    let _ = T::unpack_from_slice(data);
+    if let Ok(state) = T::unpack_versioned(data) {
+        assert!(state.is_initialized());
+    }
+}
+
+fn check_zero_copy<P: ZeroCopy>(data: &[u8]) -> Option<(&P, P::State)>
+where
+    P::State: IsInitialized,
+{
+    let data = data.get(..P::State::LEN).unwrap_or(data);
+    let state = P::State::unpack(data);
+    assert_eq!(P::load(data).is_ok(), state.is_ok());
+    Some((P::load(data).ok()?, state.ok()?))
+}
+
+fuzz_target!(|data: &[u8]| {
+    if let Some((pod, state)) = check_zero_copy::<PodEscrowState>(data) {
+        assert_eq!(pod.caller_pubkey, state.caller_pubkey);
+        assert_eq!(pod.initializer_pubkey, state.initializer_pubkey);
+        assert_eq!(pod.receiver_pubkey, state.receiver_pubkey);
+        assert_eq!(pod.temp_token_account_pubkey, state.temp_token_account_pubkey);
+        assert_eq!(pod.escrow_amount(), state.escrow_amount);
+        assert_eq!(pod.expire_date(), state.expire_date);
+        assert_eq!(pod.voucher_nonce(), state.voucher_nonce);
+        assert_eq!(pod.time_basis(), state.time_basis);
+        assert_eq!(pod.refund_after(), state.refund_after);
+        assert_eq!(pod.escrow_memo(), state.escrow_memo());
+        assert_eq!(pod.escrow_index(), state.escrow_index);
+    }
+    if let Some((pod, state)) = check_zero_copy::<PodEscrowRegistryState>(data) {
+        assert_eq!(pod.initializer_pubkey, state.initializer_pubkey);
+        assert_eq!(pod.token_account_pubkey, state.token_account_pubkey);
+        assert_eq!(pod.mint_count(), state.mint_count);
+    }
+    check::<EscrowState>(data);
+    check::<SolEscrowState>(data);
+    check::<EscrowRegistryState>(data);
+    check::<DepositRecordState>(data);
+    check::<RegistryMintState>(data);
+    check::<UserProfileState>(data);
+    check::<ProgramConfigState>(data);
+    if let Ok(voucher) = Voucher::unpack(data) {
+        assert_eq!(voucher.pack()[..], data[..VOUCHER_LEN]);
+    }
+    let _ = decoder::decode_account(data);
+});
@@ -0,0 +1,268 @@
+//! Off-chain decoding of program owned accounts and the JSON schema of their
+//! layouts, both driven by the `AccountLayout` of each state type.
+
+use chrono::{DateTime, SecondsFormat};
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use solana_program::{
+    clock::{Clock, DEFAULT_MS_PER_SLOT, DEFAULT_SLOTS_PER_EPOCH},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    states::{
+        default_escrow::EscrowState,
+        deposit_record::DepositRecordState,
+        escrow_registry::EscrowRegistryState,
+        layout::{AccountLayout, FieldType},
+        program_config::ProgramConfigState,
+        registry_mint::RegistryMintState,
+        sol_escrow::SolEscrowState,
+        user_profile::UserProfileState,
+    },
+    utils::{
+        constants::{LEGACY_DEFAULT_ESCROW_STATE_LEN, LEGACY_ESCROW_REGISTRY_STATE_LEN},
+        errors::EscrowError,
+        memo::{reference_from_bytes, to_hex},
+        time_basis::TimeBasis,
+    },
+};
+
+/// Any account owned by the escrow program. Serializes to JSON as an object
+/// tagged with `type`, pubkeys in base58 and timestamps in ISO 8601. Expire
+/// dates in slots or epochs stay numbers, [`ProgramAccount::at`] adds their
+/// estimated wall-clock time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ProgramAccount {
+    Escrow(EscrowState),
+    SolEscrow(SolEscrowState),
+    Registry(EscrowRegistryState),
+    DepositRecord(DepositRecordState),
+    RegistryMint(RegistryMintState),
+    UserProfile(UserProfileState),
+    ProgramConfig(ProgramConfigState),
+}
+
+/// Decodes `data`, dispatching on its discriminator. Headerless legacy
+/// escrows and registries are told apart by their size.
+pub fn decode_account(data: &[u8]) -> Result<ProgramAccount, ProgramError> {
+    match data.len() {
+        LEGACY_DEFAULT_ESCROW_STATE_LEN => {
+            Ok(ProgramAccount::Escrow(EscrowState::unpack_versioned(data)?))
+        }
+        LEGACY_ESCROW_REGISTRY_STATE_LEN => Ok(ProgramAccount::Registry(
+            EscrowRegistryState::unpack_versioned(data)?,
+        )),
+        _ => match data.first() {
+            Some(&EscrowState::DISCRIMINATOR) => {
+                Ok(ProgramAccount::Escrow(EscrowState::unpack_versioned(data)?))
+            }
+            Some(&SolEscrowState::DISCRIMINATOR) => {
+                Ok(ProgramAccount::SolEscrow(SolEscrowState::unpack_versioned(data)?))
+            }
+            Some(&EscrowRegistryState::DISCRIMINATOR) => Ok(ProgramAccount::Registry(
+                EscrowRegistryState::unpack_versioned(data)?,
+            )),
+            Some(&DepositRecordState::DISCRIMINATOR) => Ok(ProgramAccount::DepositRecord(
+                DepositRecordState::unpack_versioned(data)?,
+            )),
+            Some(&RegistryMintState::DISCRIMINATOR) => Ok(ProgramAccount::RegistryMint(
+                RegistryMintState::unpack_versioned(data)?,
+            )),
+            Some(&UserProfileState::DISCRIMINATOR) => Ok(ProgramAccount::UserProfile(
+                UserProfileState::unpack_versioned(data)?,
+            )),
+            Some(&ProgramConfigState::DISCRIMINATOR) => Ok(ProgramAccount::ProgramConfig(
+                ProgramConfigState::unpack_versioned(data)?,
+            )),
+            _ => Err(EscrowError::InvalidAccountDiscriminator.into()),
+        },
+    }
+}
+
+/// A [`ProgramAccount`] serialized with the wall-clock estimates of its slot
+/// and epoch based expire dates.
+pub struct AccountAtClock<'a> {
+    account: &'a ProgramAccount,
+    clock: &'a Clock,
+}
+
+impl ProgramAccount {
+    /// Serializes as the account seen at `clock`, typically the `Clock`
+    /// sysvar fetched alongside it.
+    pub fn at<'a>(&'a self, clock: &'a Clock) -> AccountAtClock<'a> {
+        AccountAtClock { account: self, clock }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ProgramAccount::Escrow(_) => EscrowState::NAME,
+            ProgramAccount::SolEscrow(_) => SolEscrowState::NAME,
+            ProgramAccount::Registry(_) => EscrowRegistryState::NAME,
+            ProgramAccount::DepositRecord(_) => DepositRecordState::NAME,
+            ProgramAccount::RegistryMint(_) => RegistryMintState::NAME,
+            ProgramAccount::UserProfile(_) => UserProfileState::NAME,
+            ProgramAccount::ProgramConfig(_) => ProgramConfigState::NAME,
+        }
+    }
+}
+
+impl Serialize for ProgramAccount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_account(self, None, serializer)
+    }
+}
+
+impl Serialize for AccountAtClock<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize_account(self.account, Some(self.clock), serializer)
+    }
+}
+
+fn serialize_account<S: Serializer>(
+    account: &ProgramAccount,
+    clock: Option<&Clock>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let name = account.name();
+    match account {
+        ProgramAccount::Escrow(state) => serialize_state(name, state, clock, serializer),
+        ProgramAccount::SolEscrow(state) => serialize_state(name, state, clock, serializer),
+        ProgramAccount::Registry(state) => serialize_state(name, state, clock, serializer),
+        ProgramAccount::DepositRecord(state) => serialize_state(name, state, clock, serializer),
+        ProgramAccount::RegistryMint(state) => serialize_state(name, state, clock, serializer),
+        ProgramAccount::UserProfile(state) => serialize_state(name, state, clock, serializer),
+        ProgramAccount::ProgramConfig(state) => serialize_state(name, state, clock, serializer),
+    }
+}
+
+/// Walks the packed bytes of `state` field by field, so the JSON always
+/// matches the on-chain layout.
+fn serialize_state<T: AccountLayout + Clone, S: Serializer>(
+    name: &str,
+    state: &T,
+    clock: Option<&Clock>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut data = vec![0; T::LEN];
+    state.pack_into_slice(&mut data);
+    let time_basis = T::offset_of("time_basis")
+        .and_then(|offset| TimeBasis::from_u8(data[offset]))
+        .unwrap_or_default();
+
+    let estimates = usize::from(clock.is_some() && time_basis != TimeBasis::UnixTimestamp);
+    let mut map = serializer.serialize_map(Some(T::FIELDS.len() + 1 + estimates))?;
+    map.serialize_entry("type", name)?;
+    let mut offset = 0;
+    for field in T::FIELDS {
+        let bytes = &data[offset..offset + field.field_type.size()];
+        match field.field_type {
+            FieldType::U8 => map.serialize_entry(field.name, &bytes[0])?,
+            FieldType::Bool => map.serialize_entry(field.name, &(bytes[0] != 0))?,
+            FieldType::Pubkey => {
+                let pubkey = Pubkey::try_from(bytes).map_err(serde::ser::Error::custom)?;
+                map.serialize_entry(field.name, &pubkey.to_string())?
+            }
+            FieldType::U64 => {
+                map.serialize_entry(field.name, &u64::from_le_bytes(to_array(bytes)))?
+            }
+            FieldType::UnixTimestamp => {
+                let timestamp = i64::from_le_bytes(to_array(bytes));
+                map.serialize_entry(field.name, &to_iso8601(timestamp))?
+            }
+            FieldType::TimeBasis => map.serialize_entry(field.name, time_basis.name())?,
+            FieldType::Maturity => {
+                let value = i64::from_le_bytes(to_array(bytes));
+                if time_basis == TimeBasis::UnixTimestamp {
+                    map.serialize_entry(field.name, &to_iso8601(value))?;
+                } else {
+                    map.serialize_entry(field.name, &value)?;
+                    if let Some(clock) = clock {
+                        let estimate = estimate_timestamp(time_basis, value, clock);
+                        let key = format!("{}_estimate", field.name);
+                        map.serialize_entry(&key, &to_iso8601(estimate))?;
+                    }
+                }
+            }
+            FieldType::Reference => {
+                let reference = reference_from_bytes(bytes).map(|reference| to_hex(&reference));
+                map.serialize_entry(field.name, &reference)?
+            }
+            FieldType::Memo => {
+                let len = T::offset_of("memo_len").map_or(0, |offset| data[offset] as usize);
+                let memo = String::from_utf8_lossy(&bytes[..len.min(bytes.len())]);
+                map.serialize_entry(field.name, &memo)?
+            }
+        }
+        offset += field.field_type.size();
+    }
+    map.end()
+}
+
+fn to_array(bytes: &[u8]) -> [u8; 8] {
+    let mut array = [0; 8];
+    array.copy_from_slice(bytes);
+    array
+}
+
+/// Estimated unix timestamp of `value` in `time_basis`, extrapolated from
+/// `clock` at the target slot time. Epochs are taken to last
+/// `DEFAULT_SLOTS_PER_EPOCH` slots without warmup, as on mainnet, devnet and
+/// testnet.
+pub fn estimate_timestamp(time_basis: TimeBasis, value: i64, clock: &Clock) -> i64 {
+    let slot = match time_basis {
+        TimeBasis::UnixTimestamp => return value,
+        TimeBasis::Slot => i128::from(value),
+        TimeBasis::Epoch => i128::from(value) * i128::from(DEFAULT_SLOTS_PER_EPOCH),
+    };
+    let elapsed_ms = (slot - i128::from(clock.slot)) * i128::from(DEFAULT_MS_PER_SLOT);
+    let estimate = i128::from(clock.unix_timestamp) + elapsed_ms / 1000;
+    estimate.clamp(i64::MIN.into(), i64::MAX.into()) as i64
+}
+
+/// Formats unix seconds as ISO 8601 in UTC, falls back to the raw number
+/// when the timestamp is out of range.
+pub fn to_iso8601(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|datetime| datetime.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct SchemaField {
+    pub key: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: &'static str,
+}
+
+#[derive(Clone, Debug, Serialize, PartialEq, Eq)]
+pub struct AccountSchema {
+    pub key: &'static str,
+    pub schema: Vec<SchemaField>,
+}
+
+fn account_schema<T: AccountLayout>() -> AccountSchema {
+    AccountSchema {
+        key: T::NAME,
+        schema: T::FIELDS
+            .iter()
+            .map(|field| SchemaField {
+                key: field.name,
+                field_type: field.field_type.schema_type(),
+            })
+            .collect(),
+    }
+}
+
+/// The contents of `configs/schema.json`.
+pub fn program_schema() -> Vec<AccountSchema> {
+    vec![
+        account_schema::<EscrowState>(),
+        account_schema::<SolEscrowState>(),
+        account_schema::<EscrowRegistryState>(),
+        account_schema::<DepositRecordState>(),
+        account_schema::<RegistryMintState>(),
+        account_schema::<UserProfileState>(),
+        account_schema::<ProgramConfigState>(),
+    ]
+}
@@ -0,0 +1,425 @@
+//! Typed events emitted through `sol_log_data` so indexers don't have to parse
+//! free-text `msg!` lines.
+//!
+//! Every event is logged as three base64 fields on one `Program data:` line:
+//! [`EVENT_MAGIC`], the schema version byte and the Borsh encoded [`EscrowEvent`].
+//!
+//! Schema versions:
+//!
+//! - `1`: `EscrowCreated`, `EscrowReleased`, `EscrowRefunded`, `DepositCollected`,
+//!   `OracleAttested`, `AccountMigrated`, `DepositMade`, `DepositWithdrawn`,
+//!   `MintAdded`, `MintRemoved`, `DepositIncreased`, `EscrowAmountLowered`,
+//!   `EscrowExpiryExtended`, `EscrowReceiverChanged`.
+//! - `2`: `EscrowCreated` gains `time_basis`, `refund_after`, `reference` and
+//!   `memo`, `EscrowReleased` gains `reference` and `memo`, `EscrowRefunded`
+//!   gains `fee`, `reference` and `memo`.
+//! - `3`: `DepositCollected` gains `mint` and `depositor`.
+//!
+//! Only new variants can be appended without a new version: Borsh has no
+//! field tags, so any change to the fields of an existing variant, appended
+//! ones included, bumps the version. Payloads of older versions still decode,
+//! into the current types with the missing fields at their defaults.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, log::sol_log_data, pubkey::Pubkey};
+
+use crate::utils::{constants::REFERENCE_LEN, time_basis::TimeBasis};
+
+pub const EVENT_MAGIC: &[u8] = b"escrowev";
+pub const EVENT_SCHEMA_VERSION: u8 = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowCreated {
+    pub escrow: Pubkey,
+    pub caller: Pubkey,
+    pub initializer: Pubkey,
+    pub receiver: Pubkey,
+    /// `None` for native SOL escrows.
+    pub mint: Option<Pubkey>,
+    pub amount: u64,
+    pub expire_date: i64,
+    /// Unit of `expire_date`, always unix seconds for native SOL escrows.
+    pub time_basis: TimeBasis,
+    /// End of the release window, `0` for native SOL escrows which have none.
+    pub refund_after: i64,
+    /// Order ID or hash, always `None` for native SOL escrows.
+    pub reference: Option<[u8; REFERENCE_LEN]>,
+    pub memo: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowReleased {
+    pub escrow: Pubkey,
+    pub receiver: Pubkey,
+    pub amount: u64,
+    /// Transfer fee withheld by the mint, the receiver got `amount - fee`.
+    pub fee: u64,
+    /// Order ID or hash, always `None` for native SOL escrows.
+    pub reference: Option<[u8; REFERENCE_LEN]>,
+    pub memo: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowRefunded {
+    pub escrow: Pubkey,
+    pub initializer: Pubkey,
+    pub amount: u64,
+    /// Transfer fee withheld by the mint, `0` for native SOL escrows.
+    pub fee: u64,
+    /// Order ID or hash, always `None` for native SOL escrows.
+    pub reference: Option<[u8; REFERENCE_LEN]>,
+    pub memo: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositCollected {
+    /// The wallet escrow registry.
+    pub escrow: Pubkey,
+    /// The registry owner.
+    pub receiver: Pubkey,
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct OracleAttested {
+    pub slot: u64,
+    pub unix_timestamp: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccountMigrated {
+    pub account: Pubkey,
+    /// `0` for accounts written before the layout header existed.
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositMade {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    /// Amount credited to the deposit record, net of any transfer fee.
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositWithdrawn {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MintAdded {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MintRemoved {
+    pub registry: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct DepositIncreased {
+    pub escrow: Pubkey,
+    /// Amount added to the escrow, net of any transfer fee.
+    pub amount: u64,
+    pub escrow_amount: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowAmountLowered {
+    pub escrow: Pubkey,
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowExpiryExtended {
+    pub escrow: Pubkey,
+    pub from: i64,
+    pub to: i64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EscrowReceiverChanged {
+    pub escrow: Pubkey,
+    pub from: Pubkey,
+    pub to: Pubkey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum EscrowEvent {
+    EscrowCreated(EscrowCreated),
+    EscrowReleased(EscrowReleased),
+    EscrowRefunded(EscrowRefunded),
+    DepositCollected(DepositCollected),
+    OracleAttested(OracleAttested),
+    AccountMigrated(AccountMigrated),
+    DepositMade(DepositMade),
+    DepositWithdrawn(DepositWithdrawn),
+    MintAdded(MintAdded),
+    MintRemoved(MintRemoved),
+    DepositIncreased(DepositIncreased),
+    EscrowAmountLowered(EscrowAmountLowered),
+    EscrowExpiryExtended(EscrowExpiryExtended),
+    EscrowReceiverChanged(EscrowReceiverChanged),
+}
+
+pub fn emit(event: EscrowEvent) -> ProgramResult {
+    let payload = event.try_to_vec()?;
+    sol_log_data(&[EVENT_MAGIC, &[EVENT_SCHEMA_VERSION], &payload]);
+    Ok(())
+}
+
+#[cfg(not(target_os = "solana"))]
+pub use decoder::*;
+
+#[cfg(not(target_os = "solana"))]
+mod decoder {
+    use super::*;
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use thiserror::Error;
+
+    #[derive(Error, Debug, PartialEq, Eq)]
+    pub enum EventDecodeError {
+        #[error("Not an escrow event")]
+        NotAnEvent,
+        #[error("Unsupported event schema version {0}")]
+        UnsupportedVersion(u8),
+        #[error("Malformed event payload")]
+        Malformed,
+    }
+
+    /// Decodes the fields of one `sol_log_data` call.
+    pub fn decode_event(fields: &[Vec<u8>]) -> Result<EscrowEvent, EventDecodeError> {
+        let [magic, version, payload] = fields else {
+            return Err(EventDecodeError::NotAnEvent);
+        };
+        if magic.as_slice() != EVENT_MAGIC {
+            return Err(EventDecodeError::NotAnEvent);
+        }
+        match version.as_slice() {
+            [EVENT_SCHEMA_VERSION] => {
+                EscrowEvent::try_from_slice(payload).map_err(|_| EventDecodeError::Malformed)
+            }
+            [2] => v2::EscrowEvent::try_from_slice(payload)
+                .map(EscrowEvent::from)
+                .map_err(|_| EventDecodeError::Malformed),
+            [1] => v1::EscrowEvent::try_from_slice(payload)
+                .map(|event| EscrowEvent::from(v2::EscrowEvent::from(event)))
+                .map_err(|_| EventDecodeError::Malformed),
+            [version] => Err(EventDecodeError::UnsupportedVersion(*version)),
+            _ => Err(EventDecodeError::Malformed),
+        }
+    }
+
+    /// Walks the log messages of a transaction and returns the events emitted
+    /// by `program_id`, in order. Data logged by other programs, including
+    /// ones invoked through CPI, is skipped.
+    pub fn decode_logs<S: AsRef<str>>(
+        program_id: &Pubkey,
+        logs: &[S],
+    ) -> Result<Vec<EscrowEvent>, EventDecodeError> {
+        let program_id = program_id.to_string();
+        let mut invoke_stack: Vec<&str> = Vec::new();
+        let mut events = Vec::new();
+
+        for log in logs {
+            let log = log.as_ref();
+            if let Some(data) = log.strip_prefix("Program data: ") {
+                if invoke_stack.last() != Some(&program_id.as_str()) {
+                    continue;
+                }
+                let fields = data
+                    .split(' ')
+                    .map(|field| STANDARD.decode(field))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| EventDecodeError::Malformed)?;
+                match decode_event(&fields) {
+                    Ok(event) => events.push(event),
+                    Err(EventDecodeError::NotAnEvent) => {}
+                    Err(err) => return Err(err),
+                }
+            } else if let Some(rest) = log.strip_prefix("Program ") {
+                let mut words = rest.split(' ');
+                let (Some(id), Some(status)) = (words.next(), words.next()) else {
+                    continue;
+                };
+                match status {
+                    "invoke" => invoke_stack.push(id),
+                    "success" | "failed:" => {
+                        invoke_stack.pop();
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(events)
+    }
+
+    /// Events as emitted under schema version `2`, before collected deposits
+    /// named their mint and depositor.
+    mod v2 {
+        use super::*;
+
+        #[derive(BorshDeserialize)]
+        pub struct DepositCollected {
+            pub escrow: Pubkey,
+            pub receiver: Pubkey,
+            pub amount: u64,
+        }
+
+        #[derive(BorshDeserialize)]
+        pub enum EscrowEvent {
+            EscrowCreated(super::EscrowCreated),
+            EscrowReleased(super::EscrowReleased),
+            EscrowRefunded(super::EscrowRefunded),
+            DepositCollected(DepositCollected),
+            OracleAttested(super::OracleAttested),
+            AccountMigrated(super::AccountMigrated),
+            DepositMade(super::DepositMade),
+            DepositWithdrawn(super::DepositWithdrawn),
+            MintAdded(super::MintAdded),
+            MintRemoved(super::MintRemoved),
+            DepositIncreased(super::DepositIncreased),
+            EscrowAmountLowered(super::EscrowAmountLowered),
+            EscrowExpiryExtended(super::EscrowExpiryExtended),
+            EscrowReceiverChanged(super::EscrowReceiverChanged),
+        }
+    }
+
+    impl From<v2::EscrowEvent> for EscrowEvent {
+        fn from(event: v2::EscrowEvent) -> Self {
+            match event {
+                v2::EscrowEvent::EscrowCreated(event) => Self::EscrowCreated(event),
+                v2::EscrowEvent::EscrowReleased(event) => Self::EscrowReleased(event),
+                v2::EscrowEvent::EscrowRefunded(event) => Self::EscrowRefunded(event),
+                v2::EscrowEvent::DepositCollected(collected) => {
+                    Self::DepositCollected(DepositCollected {
+                        escrow: collected.escrow,
+                        receiver: collected.receiver,
+                        amount: collected.amount,
+                        mint: Pubkey::default(),
+                        depositor: Pubkey::default(),
+                    })
+                }
+                v2::EscrowEvent::OracleAttested(event) => Self::OracleAttested(event),
+                v2::EscrowEvent::AccountMigrated(event) => Self::AccountMigrated(event),
+                v2::EscrowEvent::DepositMade(event) => Self::DepositMade(event),
+                v2::EscrowEvent::DepositWithdrawn(event) => Self::DepositWithdrawn(event),
+                v2::EscrowEvent::MintAdded(event) => Self::MintAdded(event),
+                v2::EscrowEvent::MintRemoved(event) => Self::MintRemoved(event),
+                v2::EscrowEvent::DepositIncreased(event) => Self::DepositIncreased(event),
+                v2::EscrowEvent::EscrowAmountLowered(event) => Self::EscrowAmountLowered(event),
+                v2::EscrowEvent::EscrowExpiryExtended(event) => Self::EscrowExpiryExtended(event),
+                v2::EscrowEvent::EscrowReceiverChanged(event) => Self::EscrowReceiverChanged(event),
+            }
+        }
+    }
+
+    /// Events as emitted under schema version `1`, before escrows had a time
+    /// basis, a release window or a memo.
+    mod v1 {
+        use super::*;
+
+        #[derive(BorshDeserialize)]
+        pub struct EscrowCreated {
+            pub escrow: Pubkey,
+            pub caller: Pubkey,
+            pub initializer: Pubkey,
+            pub receiver: Pubkey,
+            pub mint: Option<Pubkey>,
+            pub amount: u64,
+            pub expire_date: i64,
+        }
+
+        #[derive(BorshDeserialize)]
+        pub struct EscrowReleased {
+            pub escrow: Pubkey,
+            pub receiver: Pubkey,
+            pub amount: u64,
+            pub fee: u64,
+        }
+
+        #[derive(BorshDeserialize)]
+        pub struct EscrowRefunded {
+            pub escrow: Pubkey,
+            pub initializer: Pubkey,
+            pub amount: u64,
+        }
+
+        #[derive(BorshDeserialize)]
+        pub enum EscrowEvent {
+            EscrowCreated(EscrowCreated),
+            EscrowReleased(EscrowReleased),
+            EscrowRefunded(EscrowRefunded),
+            DepositCollected(v2::DepositCollected),
+            OracleAttested(super::OracleAttested),
+            AccountMigrated(super::AccountMigrated),
+            DepositMade(super::DepositMade),
+            DepositWithdrawn(super::DepositWithdrawn),
+            MintAdded(super::MintAdded),
+            MintRemoved(super::MintRemoved),
+            DepositIncreased(super::DepositIncreased),
+            EscrowAmountLowered(super::EscrowAmountLowered),
+            EscrowExpiryExtended(super::EscrowExpiryExtended),
+            EscrowReceiverChanged(super::EscrowReceiverChanged),
+        }
+    }
+
+    impl From<v1::EscrowEvent> for v2::EscrowEvent {
+        fn from(event: v1::EscrowEvent) -> Self {
+            match event {
+                v1::EscrowEvent::EscrowCreated(created) => Self::EscrowCreated(EscrowCreated {
+                    escrow: created.escrow,
+                    caller: created.caller,
+                    initializer: created.initializer,
+                    receiver: created.receiver,
+                    mint: created.mint,
+                    amount: created.amount,
+                    expire_date: created.expire_date,
+                    time_basis: TimeBasis::UnixTimestamp,
+                    refund_after: 0,
+                    reference: None,
+                    memo: String::new(),
+                }),
+                v1::EscrowEvent::EscrowReleased(released) => Self::EscrowReleased(EscrowReleased {
+                    escrow: released.escrow,
+                    receiver: released.receiver,
+                    amount: released.amount,
+                    fee: released.fee,
+                    reference: None,
+                    memo: String::new(),
+                }),
+                v1::EscrowEvent::EscrowRefunded(refunded) => Self::EscrowRefunded(EscrowRefunded {
+                    escrow: refunded.escrow,
+                    initializer: refunded.initializer,
+                    amount: refunded.amount,
+                    fee: 0,
+                    reference: None,
+                    memo: String::new(),
+                }),
+                v1::EscrowEvent::DepositCollected(event) => Self::DepositCollected(event),
+                v1::EscrowEvent::OracleAttested(event) => Self::OracleAttested(event),
+                v1::EscrowEvent::AccountMigrated(event) => Self::AccountMigrated(event),
+                v1::EscrowEvent::DepositMade(event) => Self::DepositMade(event),
+                v1::EscrowEvent::DepositWithdrawn(event) => Self::DepositWithdrawn(event),
+                v1::EscrowEvent::MintAdded(event) => Self::MintAdded(event),
+                v1::EscrowEvent::MintRemoved(event) => Self::MintRemoved(event),
+                v1::EscrowEvent::DepositIncreased(event) => Self::DepositIncreased(event),
+                v1::EscrowEvent::EscrowAmountLowered(event) => Self::EscrowAmountLowered(event),
+                v1::EscrowEvent::EscrowExpiryExtended(event) => Self::EscrowExpiryExtended(event),
+                v1::EscrowEvent::EscrowReceiverChanged(event) => Self::EscrowReceiverChanged(event),
+            }
+        }
+    }
+}
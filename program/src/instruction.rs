@@ -0,0 +1,725 @@
+//! Instruction builders for Rust clients. Each builder lays out its accounts
+//! through the matching `*Keys` struct, the same declaration the program
+//! parses them with.
+
+use solana_program::{
+    bpf_loader_upgradeable, ed25519_program,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+
+use crate::{
+    instructions::{
+        admin::{migrate_account::MigrateAccountKeys, set_config::SetConfigKeys},
+        default_escrow::{
+            amend_escrow::AmendEscrowKeys, assign_receiver::AssignReceiverKeys,
+            increase_deposit::IncreaseDepositKeys,
+            init_escrow::InitEscrowKeys, refund_escrow::RefundEscrowKeys,
+            release_escrow::ReleaseEscrowKeys,
+        },
+        sol_escrow::{
+            init_sol_escrow::InitSolEscrowKeys, refund_sol_escrow::RefundSolEscrowKeys,
+            release_sol_escrow::ReleaseSolEscrowKeys,
+        },
+        wallet_escrow::{
+            add_mint::AddMintKeys, collect_deposit::CollectDepositKeys, deposit::DepositKeys,
+            register_escrow::RegisterEscrowKeys, remove_mint::RemoveMintKeys,
+            withdraw::WithdrawKeys,
+        },
+    },
+    route::EscrowRoutes,
+    utils::{constants, memo::EscrowMemo, time_basis::TimeBasis, voucher::Voucher},
+};
+
+/// The PDA that owns the temp token accounts of token escrows.
+pub fn find_escrow_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[constants::ESCROW_SEED], program_id)
+}
+
+/// The PDA that owns the token accounts of registered wallet escrows.
+pub fn find_wallet_escrow_authority(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[constants::ESCROW_WALLET_SEED], program_id)
+}
+
+/// The PDA recording what `depositor` holds of `mint` in a wallet escrow registry.
+pub fn find_deposit_record(
+    program_id: &Pubkey,
+    registry_account: &Pubkey,
+    mint: &Pubkey,
+    depositor: &Pubkey,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[constants::DEPOSIT_SEED, registry_account.as_ref(), mint.as_ref(), depositor.as_ref()],
+        program_id,
+    )
+}
+
+/// The PDA recording a mint accepted by a wallet escrow registry.
+pub fn find_registry_mint(program_id: &Pubkey, registry_account: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[constants::REGISTRY_MINT_SEED, registry_account.as_ref(), mint.as_ref()],
+        program_id,
+    )
+}
+
+/// The PDA token account `AddMint` creates as the vault of a mint.
+pub fn find_registry_vault(program_id: &Pubkey, registry_account: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[constants::REGISTRY_VAULT_SEED, registry_account.as_ref(), mint.as_ref()],
+        program_id,
+    )
+}
+
+/// The PDA counting the token escrows of `initializer`.
+pub fn find_user_profile(program_id: &Pubkey, initializer: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[constants::USER_PROFILE_SEED, initializer.as_ref()],
+        program_id,
+    )
+}
+
+/// The PDA holding the program's settings, see [`set_config`].
+pub fn find_program_config(program_id: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[constants::PROGRAM_CONFIG_SEED], program_id)
+}
+
+/// The address of the token escrow `initializer`'s profile counts at
+/// `escrow_index`. The next escrow gets the profile's `escrow_count + 1`,
+/// or 1 before the profile exists.
+pub fn find_escrow_account(program_id: &Pubkey, initializer: &Pubkey, escrow_index: u64) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[constants::ESCROW_SEED, initializer.as_ref(), &escrow_index.to_le_bytes()],
+        program_id,
+    )
+}
+
+/// The PDA vault holding the lamports of a native SOL escrow.
+pub fn find_sol_vault(program_id: &Pubkey, escrow_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[constants::SOL_VAULT_SEED, escrow_account.as_ref()],
+        program_id,
+    )
+}
+
+/// Creates an `InitEscrow` instruction signed by `initializer` and `caller`.
+/// `temp_token_account` has to hold at least `amount`, which can't be zero,
+/// and be owned by `caller`. `escrow_account` is the next
+/// [`find_escrow_account`] of `initializer`. The escrow matures after the
+/// wait time of `time_basis`, counted in unix seconds, slots or epochs.
+/// `memo` is stored in the escrow, and the SPL Memo program is appended when
+/// it has to be logged.
+#[allow(clippy::too_many_arguments)]
+pub fn init_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    receiver: &Pubkey,
+    temp_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    caller: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    time_basis: TimeBasis,
+    memo: &EscrowMemo,
+) -> Instruction {
+    let keys = InitEscrowKeys {
+        initializer: *initializer,
+        receiver_account: *receiver,
+        temp_token_account: *temp_token_account,
+        escrow_account: *escrow_account,
+        rent_account: sysvar::rent::id(),
+        token_program: *token_program,
+        caller: *caller,
+        mint_account: *mint,
+        user_profile: find_user_profile(program_id, initializer).0,
+        system_program: system_program::id(),
+        program_config: find_program_config(program_id).0,
+    };
+
+    let mut instruction = Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::InitEscrow {
+            amount,
+            time_basis,
+            memo: memo.clone(),
+        }
+        .pack(),
+    };
+    if memo.log_memo {
+        append_memo_program(&mut instruction);
+    }
+    instruction
+}
+
+/// Appends the SPL Memo program to the accounts of a `ReleaseEscrow` or
+/// `ReleaseWithVoucher` instruction, required when the escrow logs memos.
+/// Transfer hook accounts go after it.
+pub fn append_memo_program(instruction: &mut Instruction) {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(spl_memo::id(), false));
+}
+
+/// Creates a `ReleaseEscrow` instruction. Tokens the temp account holds above
+/// the escrowed amount go back to `initializer_token_account` when the escrow
+/// closes. Mints with a transfer hook need the hook's extra accounts appended
+/// to `accounts`.
+#[allow(clippy::too_many_arguments)]
+pub fn release_escrow(
+    program_id: &Pubkey,
+    receiver_token_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    initializer: &Pubkey,
+    initializer_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    caller: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let keys = ReleaseEscrowKeys {
+        receiver_token_account: *receiver_token_account,
+        pdas_temp_token_account: *temp_token_account,
+        initializers_main_account: *initializer,
+        escrow_account: *escrow_account,
+        token_program: *token_program,
+        pda_account: find_escrow_authority(program_id).0,
+        caller: *caller,
+        mint_account: *mint,
+        user_profile: find_user_profile(program_id, initializer).0,
+        initializer_token_account: *initializer_token_account,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::ReleaseEscrow.pack(),
+    }
+}
+
+/// Creates a `ReleaseWithVoucher` instruction. It has to come right after the
+/// [`ed25519_verify`] instruction of the voucher signature. Mints with a
+/// transfer hook need the hook's extra accounts appended to `accounts`.
+#[allow(clippy::too_many_arguments)]
+pub fn release_with_voucher(
+    program_id: &Pubkey,
+    receiver_token_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    initializer: &Pubkey,
+    initializer_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    caller: &Pubkey,
+    mint: &Pubkey,
+    voucher: Voucher,
+) -> Instruction {
+    let mut instruction = release_escrow(
+        program_id,
+        receiver_token_account,
+        temp_token_account,
+        initializer,
+        initializer_token_account,
+        escrow_account,
+        token_program,
+        caller,
+        mint,
+    );
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    instruction.data = EscrowRoutes::ReleaseWithVoucher { voucher }.pack();
+    instruction
+}
+
+/// Creates an `IncreaseDeposit` instruction moving `amount` more tokens from
+/// `initializer_token_account` into the escrow.
+#[allow(clippy::too_many_arguments)]
+pub fn increase_deposit(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    initializer_token_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let keys = IncreaseDepositKeys {
+        initializer: *initializer,
+        initializer_token_account: *initializer_token_account,
+        pdas_temp_token_account: *temp_token_account,
+        escrow_account: *escrow_account,
+        token_program: *token_program,
+        mint_account: *mint,
+        user_profile: find_user_profile(program_id, initializer).0,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::IncreaseDeposit { amount }.pack(),
+    }
+}
+
+/// Creates an `AmendEscrow` instruction setting the escrow terms to `amount`,
+/// `expire_date` and `new_receiver`, signed by the initializer and the current
+/// receiver.
+#[allow(clippy::too_many_arguments)]
+pub fn amend_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    receiver: &Pubkey,
+    initializer_token_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    expire_date: i64,
+    new_receiver: &Pubkey,
+) -> Instruction {
+    let keys = AmendEscrowKeys {
+        initializer: *initializer,
+        receiver: *receiver,
+        initializer_token_account: *initializer_token_account,
+        pdas_temp_token_account: *temp_token_account,
+        escrow_account: *escrow_account,
+        token_program: *token_program,
+        pda_account: find_escrow_authority(program_id).0,
+        mint_account: *mint,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::AmendEscrow {
+            amount,
+            expire_date,
+            receiver: *new_receiver,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `AssignReceiver` instruction handing `receiver`'s claim on a
+/// token or native SOL escrow over to `new_receiver`.
+pub fn assign_receiver(
+    program_id: &Pubkey,
+    receiver: &Pubkey,
+    escrow_account: &Pubkey,
+    new_receiver: &Pubkey,
+) -> Instruction {
+    let keys = AssignReceiverKeys {
+        receiver: *receiver,
+        escrow_account: *escrow_account,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::AssignReceiver {
+            new_receiver: *new_receiver,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `RefundEscrow` instruction returning the tokens of an escrow
+/// whose release window has closed, the temp account's whole balance, to
+/// `initializer_token_account`. Nobody
+/// signs it, the rent goes back to the escrow's recorded `caller`. Mints with
+/// a transfer hook need the hook's extra accounts appended to `accounts`.
+#[allow(clippy::too_many_arguments)]
+pub fn refund_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    initializer_token_account: &Pubkey,
+    temp_token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    caller: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let keys = RefundEscrowKeys {
+        initializer_token_account: *initializer_token_account,
+        pdas_temp_token_account: *temp_token_account,
+        escrow_account: *escrow_account,
+        token_program: *token_program,
+        pda_account: find_escrow_authority(program_id).0,
+        caller: *caller,
+        mint_account: *mint,
+        user_profile: find_user_profile(program_id, initializer).0,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::RefundEscrow.pack(),
+    }
+}
+
+/// Creates the Ed25519 precompile instruction checking `signature` of
+/// `signer` over `voucher`.
+pub fn ed25519_verify(signer: &Pubkey, signature: &[u8; 64], voucher: &Voucher) -> Instruction {
+    const DATA_START: u16 = 16;
+    let message = voucher.message();
+    let public_key_offset = DATA_START;
+    let signature_offset = public_key_offset + 32;
+    let message_offset = signature_offset + 64;
+
+    let mut data = vec![1, 0];
+    for value in [
+        signature_offset,
+        u16::MAX,
+        public_key_offset,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(signer.as_ref());
+    data.extend_from_slice(signature);
+    data.extend_from_slice(&message);
+
+    Instruction {
+        program_id: ed25519_program::id(),
+        accounts: vec![],
+        data,
+    }
+}
+
+/// Creates a `CollectDeposit` instruction for the whole balance of
+/// `depositor`'s record. Before the record matures the depositor has to
+/// release it by signing too, `depositor_signs` adds that signature.
+#[allow(clippy::too_many_arguments)]
+pub fn collect_deposit(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    receiver_token_account: &Pubkey,
+    vault_account: &Pubkey,
+    registry_account: &Pubkey,
+    depositor: &Pubkey,
+    depositor_signs: bool,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let keys = CollectDepositKeys {
+        owner: *owner,
+        receiver_token_account: *receiver_token_account,
+        vault_account: *vault_account,
+        registry_account: *registry_account,
+        registry_mint: find_registry_mint(program_id, registry_account, mint).0,
+        deposit_record: find_deposit_record(program_id, registry_account, mint, depositor).0,
+        depositor: *depositor,
+        pda_account: find_wallet_escrow_authority(program_id).0,
+        mint_account: *mint,
+        token_program: *token_program,
+    };
+    let mut accounts = keys.to_account_metas();
+    if depositor_signs {
+        accounts
+            .iter_mut()
+            .filter(|meta| meta.pubkey == *depositor)
+            .for_each(|meta| meta.is_signer = true);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: EscrowRoutes::CollectDeposit.pack(),
+    }
+}
+
+/// Creates a `Deposit` instruction. The deposit record is created on the
+/// first deposit, paid for by `depositor`.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    depositor_token_account: &Pubkey,
+    vault_account: &Pubkey,
+    registry_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let keys = DepositKeys {
+        depositor: *depositor,
+        depositor_token_account: *depositor_token_account,
+        vault_account: *vault_account,
+        registry_account: *registry_account,
+        registry_mint: find_registry_mint(program_id, registry_account, mint).0,
+        deposit_record: find_deposit_record(program_id, registry_account, mint, depositor).0,
+        mint_account: *mint,
+        rent_account: sysvar::rent::id(),
+        token_program: *token_program,
+        system_program: system_program::id(),
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::Deposit { amount }.pack(),
+    }
+}
+
+/// Creates a `Withdraw` instruction.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    depositor_token_account: &Pubkey,
+    vault_account: &Pubkey,
+    registry_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let keys = WithdrawKeys {
+        depositor: *depositor,
+        depositor_token_account: *depositor_token_account,
+        vault_account: *vault_account,
+        registry_account: *registry_account,
+        registry_mint: find_registry_mint(program_id, registry_account, mint).0,
+        deposit_record: find_deposit_record(program_id, registry_account, mint, depositor).0,
+        pda_account: find_wallet_escrow_authority(program_id).0,
+        mint_account: *mint,
+        token_program: *token_program,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::Withdraw { amount }.pack(),
+    }
+}
+
+/// Creates an `Oracle` instruction.
+pub fn oracle(program_id: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![],
+        data: EscrowRoutes::Oracle.pack(),
+    }
+}
+
+/// Creates an `InitSolEscrow` instruction. `initializer` funds the vault with
+/// `amount` plus its rent exemption.
+pub fn init_sol_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    receiver: &Pubkey,
+    escrow_account: &Pubkey,
+    caller: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let keys = InitSolEscrowKeys {
+        initializer: *initializer,
+        receiver_account: *receiver,
+        vault_account: find_sol_vault(program_id, escrow_account).0,
+        escrow_account: *escrow_account,
+        rent_account: sysvar::rent::id(),
+        system_program: system_program::id(),
+        caller: *caller,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::InitSolEscrow { amount }.pack(),
+    }
+}
+
+/// Creates a `ReleaseSolEscrow` instruction.
+pub fn release_sol_escrow(
+    program_id: &Pubkey,
+    receiver: &Pubkey,
+    escrow_account: &Pubkey,
+    caller: &Pubkey,
+) -> Instruction {
+    let keys = ReleaseSolEscrowKeys {
+        receiver_account: *receiver,
+        vault_account: find_sol_vault(program_id, escrow_account).0,
+        escrow_account: *escrow_account,
+        caller: *caller,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::ReleaseSolEscrow.pack(),
+    }
+}
+
+/// Creates a `RefundSolEscrow` instruction. Before the escrow matures the
+/// escrow's `caller` has to sign it, `caller_signs` adds that signature; once
+/// the release window has closed anyone can send it.
+pub fn refund_sol_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    escrow_account: &Pubkey,
+    caller: &Pubkey,
+    caller_signs: bool,
+) -> Instruction {
+    let keys = RefundSolEscrowKeys {
+        initializer_account: *initializer,
+        vault_account: find_sol_vault(program_id, escrow_account).0,
+        escrow_account: *escrow_account,
+        caller: *caller,
+    };
+    let mut accounts = keys.to_account_metas();
+    if caller_signs {
+        accounts
+            .iter_mut()
+            .filter(|meta| meta.pubkey == *caller)
+            .for_each(|meta| meta.is_signer = true);
+    }
+
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: EscrowRoutes::RefundSolEscrow.pack(),
+    }
+}
+
+/// Creates a `RegisterEscrow` instruction. `initializer` has to own `token_account`.
+pub fn register_escrow(
+    program_id: &Pubkey,
+    initializer: &Pubkey,
+    token_account: &Pubkey,
+    escrow_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let keys = RegisterEscrowKeys {
+        initializer: *initializer,
+        temp_token_account: *token_account,
+        escrow_account: *escrow_account,
+        rent_account: sysvar::rent::id(),
+        token_program: *token_program,
+        mint_account: *mint,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::RegisterEscrow.pack(),
+    }
+}
+
+/// Creates a `MigrateAccount` instruction, signed by the program's upgrade
+/// `authority`. `payer` covers the rent of the grown account.
+pub fn migrate_account(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    state_account: &Pubkey,
+) -> Instruction {
+    let keys = MigrateAccountKeys {
+        authority: *authority,
+        payer: *payer,
+        state_account: *state_account,
+        program_data: Pubkey::find_program_address(
+            &[program_id.as_ref()],
+            &bpf_loader_upgradeable::id(),
+        )
+        .0,
+        rent_account: sysvar::rent::id(),
+        system_program: system_program::id(),
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::MigrateAccount.pack(),
+    }
+}
+
+/// Creates a `SetConfig` instruction, signed by the program's upgrade
+/// `authority`. `payer` covers the rent of the config when it is created.
+pub fn set_config(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    max_open_escrows_per_user: u64,
+) -> Instruction {
+    let keys = SetConfigKeys {
+        authority: *authority,
+        payer: *payer,
+        program_config: find_program_config(program_id).0,
+        program_data: Pubkey::find_program_address(
+            &[program_id.as_ref()],
+            &bpf_loader_upgradeable::id(),
+        )
+        .0,
+        rent_account: sysvar::rent::id(),
+        system_program: system_program::id(),
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::SetConfig { max_open_escrows_per_user }.pack(),
+    }
+}
+
+/// Creates an `AddMint` instruction. `vault_account` is either the token
+/// account registered with the registry or [`find_registry_vault`].
+pub fn add_mint(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    registry_account: &Pubkey,
+    vault_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let keys = AddMintKeys {
+        owner: *owner,
+        registry_account: *registry_account,
+        registry_mint: find_registry_mint(program_id, registry_account, mint).0,
+        vault_account: *vault_account,
+        mint_account: *mint,
+        pda_account: find_wallet_escrow_authority(program_id).0,
+        rent_account: sysvar::rent::id(),
+        token_program: *token_program,
+        system_program: system_program::id(),
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::AddMint.pack(),
+    }
+}
+
+/// Creates a `RemoveMint` instruction. Tokens in the vault that no deposit
+/// accounts for go to `receiver_token_account`.
+pub fn remove_mint(
+    program_id: &Pubkey,
+    owner: &Pubkey,
+    receiver_token_account: &Pubkey,
+    registry_account: &Pubkey,
+    vault_account: &Pubkey,
+    token_program: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let keys = RemoveMintKeys {
+        owner: *owner,
+        receiver_token_account: *receiver_token_account,
+        registry_account: *registry_account,
+        registry_mint: find_registry_mint(program_id, registry_account, mint).0,
+        vault_account: *vault_account,
+        mint_account: *mint,
+        pda_account: find_wallet_escrow_authority(program_id).0,
+        token_program: *token_program,
+    };
+
+    Instruction {
+        program_id: *program_id,
+        accounts: keys.to_account_metas(),
+        data: EscrowRoutes::RemoveMint.pack(),
+    }
+}
@@ -0,0 +1,139 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::IsInitialized,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    events::{self, AccountMigrated, EscrowEvent},
+    states::{
+        default_escrow::EscrowState,
+        deposit_record::DepositRecordState,
+        escrow_registry::EscrowRegistryState,
+        layout::AccountLayout,
+        program_config::ProgramConfigState,
+        registry_mint::RegistryMintState,
+        sol_escrow::SolEscrowState,
+        user_profile::UserProfileState,
+    },
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, logging::verbose,
+        lamport_lib, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `MigrateAccount`.
+    pub struct MigrateAccountAccounts / MigrateAccountKeys {
+        /// The upgrade authority of this program
+        authority: signer = true, writable = false, owner = Any;
+        /// Pays the rent for the extra bytes
+        payer: signer = true, writable = true, owner = Any;
+        /// The state account to migrate
+        state_account: signer = false, writable = true, owner = Program;
+        /// The program data account of this program, holding its upgrade authority
+        program_data: signer = false, writable = false, owner = Any;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The system program
+        system_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/** Migrate Account
+
+    Rewrites a state account written by an older layout in the
+    current one, growing it and topping up its rent from the payer. Accounts
+    already on the current layout are left untouched.
+
+    Accounts expected: see `MigrateAccountAccounts`.
+
+**/
+pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose!("Migrate Account starting!");
+    let MigrateAccountAccounts {
+        authority,
+        payer,
+        state_account,
+        program_data,
+        rent_account,
+        system_program,
+        ..
+    } = MigrateAccountAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_upgrade_authority(program_data, authority, program_id)?;
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_system_program(system_program)?;
+    let rent = Rent::from_account_info(rent_account)?;
+
+    let data_len = state_account.data_len();
+    let discriminator = state_account.try_borrow_data()?.first().copied();
+    let versions = match (data_len, discriminator) {
+        (constants::LEGACY_DEFAULT_ESCROW_STATE_LEN, _) => {
+            migrate::<EscrowState>(state_account, payer, system_program, &rent)?
+        }
+        (constants::LEGACY_ESCROW_REGISTRY_STATE_LEN, _) => {
+            migrate::<EscrowRegistryState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(EscrowState::DISCRIMINATOR)) => {
+            migrate::<EscrowState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(SolEscrowState::DISCRIMINATOR)) => {
+            migrate::<SolEscrowState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(EscrowRegistryState::DISCRIMINATOR)) => {
+            migrate::<EscrowRegistryState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(DepositRecordState::DISCRIMINATOR)) => {
+            migrate::<DepositRecordState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(RegistryMintState::DISCRIMINATOR)) => {
+            migrate::<RegistryMintState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(UserProfileState::DISCRIMINATOR)) => {
+            migrate::<UserProfileState>(state_account, payer, system_program, &rent)?
+        }
+        (_, Some(ProgramConfigState::DISCRIMINATOR)) => {
+            migrate::<ProgramConfigState>(state_account, payer, system_program, &rent)?
+        }
+        _ => return Err(EscrowError::InvalidAccountDiscriminator.into()),
+    };
+
+    let Some((from_version, to_version)) = versions else {
+        verbose!("Account already on the current layout!");
+        return Ok(());
+    };
+
+    events::emit(EscrowEvent::AccountMigrated(AccountMigrated {
+        account: *state_account.key,
+        from_version,
+        to_version,
+    }))
+}
+
+/// Returns the versions migrated between, `None` when the account is already
+/// current. Headerless accounts count as version 0.
+fn migrate<'a, T: AccountLayout + IsInitialized>(
+    state_account: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent: &Rent,
+) -> Result<Option<(u8, u8)>, ProgramError> {
+    let (state, from_version) = {
+        let data = state_account.try_borrow_data()?;
+        if data.len() == T::LEN && data[1] == T::VERSION {
+            return Ok(None);
+        }
+        let from_version = if Some(data.len()) == T::LEGACY_LEN { 0 } else { data[1] };
+        (T::unpack_versioned(&data)?, from_version)
+    };
+
+    lamport_lib::top_up(payer, state_account, system_program, rent.minimum_balance(T::LEN))?;
+    state_account.realloc(T::LEN, true)?;
+    T::pack(state, &mut state_account.try_borrow_mut_data()?)?;
+
+    Ok(Some((from_version, T::VERSION)))
+}
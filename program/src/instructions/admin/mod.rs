@@ -0,0 +1,2 @@
+pub mod migrate_account;
+pub mod set_config;
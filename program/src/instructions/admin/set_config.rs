@@ -0,0 +1,88 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    states::program_config::ProgramConfigState,
+    utils::{
+        accounts::instruction_accounts, constants, lamport_lib, logging::verbose, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `SetConfig`.
+    pub struct SetConfigAccounts / SetConfigKeys {
+        /// The upgrade authority of this program
+        authority: signer = true, writable = false, owner = Any;
+        /// Pays the rent of the config when it is created
+        payer: signer = true, writable = true, owner = Any;
+        /// The config PDA `[PROGRAM_CONFIG_SEED]`, created on first use
+        program_config: signer = false, writable = true, owner = Any;
+        /// The program data account of this program, holding its upgrade authority
+        program_data: signer = false, writable = false, owner = Any;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The system program
+        system_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/** Set Config
+
+    Creates the program config or overwrites its settings. A cap of zero
+    open escrows per initializer is rejected.
+
+    Accounts expected: see `SetConfigAccounts`.
+
+**/
+pub fn handler(
+    accounts: &[AccountInfo],
+    max_open_escrows_per_user: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose!("Set Config starting!");
+    let SetConfigAccounts {
+        authority,
+        payer,
+        program_config,
+        program_data,
+        rent_account,
+        system_program,
+        ..
+    } = SetConfigAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_upgrade_authority(program_data, authority, program_id)?;
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_system_program(system_program)?;
+    if max_open_escrows_per_user == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let bump_seed =
+        validation::assert_pda(program_config, &[constants::PROGRAM_CONFIG_SEED], program_id)?;
+
+    if program_config.data_is_empty() {
+        verbose!("Creating the program config...");
+        let rent = Rent::from_account_info(rent_account)?;
+        lamport_lib::create_pda_account(
+            payer.clone(),
+            program_config.clone(),
+            system_program.clone(),
+            rent.minimum_balance(ProgramConfigState::LEN),
+            ProgramConfigState::LEN,
+            program_id,
+            &[constants::PROGRAM_CONFIG_SEED, &[bump_seed]],
+        )?;
+    }
+    validation::assert_owned_by(program_config, program_id)?;
+
+    let config = ProgramConfigState {
+        is_initialized: true,
+        max_open_escrows_per_user,
+    };
+    ProgramConfigState::pack(config, &mut program_config.try_borrow_mut_data()?)
+}
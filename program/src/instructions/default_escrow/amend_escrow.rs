@@ -0,0 +1,167 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{
+        self, EscrowAmountLowered, EscrowEvent, EscrowExpiryExtended, EscrowReceiverChanged,
+    },
+    states::{default_escrow::PodEscrowState, layout::ZeroCopy},
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, logging::verbose, token_lib,
+        validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `AmendEscrow`.
+    pub struct AmendEscrowAccounts / AmendEscrowKeys {
+        /// The initializer of the escrow
+        initializer: signer = true, writable = false, owner = Any;
+        /// The current receiver of the escrow
+        receiver: signer = true, writable = false, owner = Any;
+        /// The initializer's token account, it gets the tokens taken off the escrow
+        initializer_token_account: signer = false, writable = true, owner = Token;
+        /// The PDA's temp token account holding the escrowed tokens
+        pdas_temp_token_account: signer = false, writable = true, owner = Token;
+        /// The escrow account holding the escrow info
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The PDA account
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The mint of the escrowed token
+        mint_account: signer = false, writable = false, owner = Token;
+    }
+}
+
+/** Amend Escrow
+
+    Rewrites the terms of an escrow with the consent of both parties. The
+    amount can only go down, the difference going back to the initializer,
+    and the expire date can only move later, taking the end of the release
+    window along with it. Each change is logged as its own event.
+
+    Accounts expected: see `AmendEscrowAccounts`, followed by any extra
+    accounts required by the mint's transfer hook.
+
+**/
+pub fn handler(
+    accounts: &[AccountInfo],
+    amount: u64,
+    expire_date: i64,
+    receiver: Pubkey,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose!("Amend Escrow starting!");
+    let AmendEscrowAccounts {
+        initializer,
+        receiver: current_receiver,
+        initializer_token_account,
+        pdas_temp_token_account,
+        escrow_account,
+        token_program,
+        pda_account,
+        mint_account,
+        remaining: transfer_hook_accounts,
+    } = AmendEscrowAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_token_program(token_program)?;
+    let bump_seed = validation::assert_pda(pda_account, &[constants::ESCROW_SEED], program_id)?;
+
+    verbose!("Loading Escrow!");
+    let escrow_data = escrow_account.try_borrow_data()?;
+    let escrow_info = PodEscrowState::load(&escrow_data)?;
+
+    verbose!("Running Checks!");
+    if escrow_info.initializer_pubkey != *initializer.key {
+        return Err(EscrowError::InitializerMismatch.into());
+    }
+    if escrow_info.receiver_pubkey != *current_receiver.key {
+        return Err(EscrowError::ReceiverMismatch.into());
+    }
+    if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+        return Err(EscrowError::TempTokenAccountMismatch.into());
+    }
+    let escrow_amount = escrow_info.escrow_amount();
+    let previous_expire_date = escrow_info.expire_date();
+    let refund_after = escrow_info.refund_after();
+    drop(escrow_data);
+    if amount == 0 || amount > escrow_amount || expire_date < previous_expire_date {
+        return Err(EscrowError::InvalidAmendment.into());
+    }
+    if amount == escrow_amount
+        && expire_date == previous_expire_date
+        && receiver == *current_receiver.key
+    {
+        return Err(EscrowError::InvalidAmendment.into());
+    }
+
+    let escrow = *escrow_account.key;
+    let mut amended = Vec::new();
+    if amount < escrow_amount {
+        let decimals = token_lib::check_mint(token_program, mint_account)?;
+        let initializer_token_account_info =
+            token_lib::unpack_token_account(token_program, initializer_token_account)?;
+        if initializer_token_account_info.owner != *initializer.key {
+            return Err(EscrowError::TokenOwnerMismatch.into());
+        }
+        if initializer_token_account_info.mint != *mint_account.key {
+            return Err(EscrowError::MintMismatch.into());
+        }
+
+        verbose!("Returning {} tokens!", escrow_amount - amount);
+        token_lib::transfer_tokens(
+            token_program.clone(),
+            pdas_temp_token_account.clone(),
+            mint_account.clone(),
+            initializer_token_account.clone(),
+            pda_account.clone(),
+            transfer_hook_accounts,
+            escrow_amount - amount,
+            decimals,
+            &[constants::ESCROW_SEED, &[bump_seed]],
+        )?;
+        amended.push(EscrowEvent::EscrowAmountLowered(EscrowAmountLowered {
+            escrow,
+            from: escrow_amount,
+            to: amount,
+        }));
+    }
+    let refund_after = match refund_after {
+        0 => 0,
+        refund_after => refund_after
+            .checked_add(expire_date - previous_expire_date)
+            .ok_or(EscrowError::InvalidAmendment)?,
+    };
+    if expire_date > previous_expire_date {
+        amended.push(EscrowEvent::EscrowExpiryExtended(EscrowExpiryExtended {
+            escrow,
+            from: previous_expire_date,
+            to: expire_date,
+        }));
+    }
+    if receiver != *current_receiver.key {
+        amended.push(EscrowEvent::EscrowReceiverChanged(EscrowReceiverChanged {
+            escrow,
+            from: *current_receiver.key,
+            to: receiver,
+        }));
+    }
+
+    verbose!("Updating Escrow!");
+    let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+    let escrow_info = PodEscrowState::load_mut(&mut escrow_data)?;
+    escrow_info.set_escrow_amount(amount);
+    escrow_info.set_expire_date(expire_date);
+    escrow_info.set_refund_after(refund_after);
+    escrow_info.receiver_pubkey = receiver;
+    drop(escrow_data);
+    for event in amended {
+        events::emit(event)?;
+    }
+
+    verbose!("Escrow amended successfully");
+    Ok(())
+}
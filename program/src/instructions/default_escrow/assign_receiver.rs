@@ -0,0 +1,83 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    program_pack::IsInitialized, pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, EscrowEvent, EscrowReceiverChanged},
+    states::{default_escrow::EscrowState, layout::AccountLayout, sol_escrow::SolEscrowState},
+    utils::{accounts::instruction_accounts, errors::EscrowError, logging::verbose},
+};
+
+instruction_accounts! {
+    /// Accounts expected by `AssignReceiver`.
+    pub struct AssignReceiverAccounts / AssignReceiverKeys {
+        /// The current receiver, holder of the claim
+        receiver: signer = true, writable = false, owner = Any;
+        /// The token or native SOL escrow account
+        escrow_account: signer = false, writable = true, owner = Program;
+    }
+}
+
+/** Assign Receiver
+
+    Hands the receiver's claim on a token or native SOL escrow over to
+    `new_receiver`, who then gets the funds on release and can sign
+    release vouchers. Only the current receiver can assign the claim.
+
+    Accounts expected: see `AssignReceiverAccounts`.
+
+**/
+pub fn handler(accounts: &[AccountInfo], new_receiver: Pubkey, program_id: &Pubkey) -> ProgramResult {
+    verbose!("Assign Receiver starting!");
+    let AssignReceiverAccounts {
+        receiver,
+        escrow_account,
+        ..
+    } = AssignReceiverAccounts::try_from_accounts(accounts, program_id)?;
+
+    let discriminator = escrow_account.try_borrow_data()?.first().copied();
+    let previous = match discriminator {
+        Some(EscrowState::DISCRIMINATOR) => {
+            assign::<EscrowState>(escrow_account, receiver, new_receiver, |state| {
+                &mut state.receiver_pubkey
+            })?
+        }
+        Some(SolEscrowState::DISCRIMINATOR) => {
+            assign::<SolEscrowState>(escrow_account, receiver, new_receiver, |state| {
+                &mut state.receiver_pubkey
+            })?
+        }
+        _ => return Err(EscrowError::InvalidAccountDiscriminator.into()),
+    };
+
+    events::emit(EscrowEvent::EscrowReceiverChanged(EscrowReceiverChanged {
+        escrow: *escrow_account.key,
+        from: previous,
+        to: new_receiver,
+    }))?;
+
+    verbose!("Receiver assigned successfully");
+    Ok(())
+}
+
+/// Swaps the receiver of `escrow_account` for `new_receiver`, returning the
+/// previous one.
+fn assign<T: AccountLayout + IsInitialized>(
+    escrow_account: &AccountInfo,
+    receiver: &AccountInfo,
+    new_receiver: Pubkey,
+    receiver_of: fn(&mut T) -> &mut Pubkey,
+) -> Result<Pubkey, ProgramError> {
+    let mut state = T::unpack(&escrow_account.try_borrow_data()?)?;
+    let current = receiver_of(&mut state);
+    if *current != *receiver.key {
+        return Err(EscrowError::ReceiverMismatch.into());
+    }
+    if *current == new_receiver {
+        return Err(ProgramError::InvalidArgument);
+    }
+    let previous = std::mem::replace(current, new_receiver);
+    T::pack(state, &mut escrow_account.try_borrow_mut_data()?)?;
+    Ok(previous)
+}
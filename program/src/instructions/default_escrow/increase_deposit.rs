@@ -0,0 +1,118 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, DepositIncreased, EscrowEvent},
+    states::{default_escrow::PodEscrowState, layout::ZeroCopy},
+    utils::{
+        accounts::instruction_accounts, errors::EscrowError, logging::verbose, token_lib,
+        user_profile, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `IncreaseDeposit`.
+    pub struct IncreaseDepositAccounts / IncreaseDepositKeys {
+        /// The initializer of the escrow
+        initializer: signer = true, writable = false, owner = Any;
+        /// The initializer's token account to take the tokens from
+        initializer_token_account: signer = false, writable = true, owner = Token;
+        /// The PDA's temp token account holding the escrowed tokens
+        pdas_temp_token_account: signer = false, writable = true, owner = Token;
+        /// The escrow account holding the escrow info
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The mint of the escrowed token
+        mint_account: signer = false, writable = false, owner = Token;
+        /// The initializer's profile PDA, its volume grows with the escrow
+        user_profile: signer = false, writable = true, owner = Any;
+    }
+}
+
+/** Increase Escrow Deposit
+
+    Moves `amount` more tokens into the escrow. The escrowed amount grows by
+    what the temp account actually received, net of any transfer fee.
+
+    Accounts expected: see `IncreaseDepositAccounts`, followed by any extra
+    accounts required by the mint's transfer hook.
+
+**/
+pub fn handler(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    verbose!("Increase Deposit starting!");
+    let IncreaseDepositAccounts {
+        initializer,
+        initializer_token_account,
+        pdas_temp_token_account,
+        escrow_account,
+        token_program,
+        mint_account,
+        user_profile,
+        remaining: transfer_hook_accounts,
+    } = IncreaseDepositAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_token_program(token_program)?;
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    verbose!("Loading Escrow!");
+    let escrow_data = escrow_account.try_borrow_data()?;
+    let escrow_info = PodEscrowState::load(&escrow_data)?;
+
+    verbose!("Running Checks!");
+    if escrow_info.initializer_pubkey != *initializer.key {
+        return Err(EscrowError::InitializerMismatch.into());
+    }
+    if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+        return Err(EscrowError::TempTokenAccountMismatch.into());
+    }
+    let escrow_index = escrow_info.escrow_index();
+    drop(escrow_data);
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
+    let temp_before = token_lib::unpack_token_account(token_program, pdas_temp_token_account)?;
+    if temp_before.mint != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
+    }
+
+    verbose!("Transfer Tokens!");
+    token_lib::transfer_tokens(
+        token_program.clone(),
+        initializer_token_account.clone(),
+        mint_account.clone(),
+        pdas_temp_token_account.clone(),
+        initializer.clone(),
+        transfer_hook_accounts,
+        amount,
+        decimals,
+        &[],
+    )?;
+    let credited = token_lib::unpack_token_account(token_program, pdas_temp_token_account)?
+        .amount
+        .checked_sub(temp_before.amount)
+        .ok_or(EscrowError::AmountOverflow)?;
+
+    verbose!("Updating Escrow!");
+    let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+    let escrow_info = PodEscrowState::load_mut(&mut escrow_data)?;
+    let escrow_amount = escrow_info
+        .escrow_amount()
+        .checked_add(credited)
+        .ok_or(EscrowError::AmountOverflow)?;
+    token_lib::check_vault_balance(token_program, pdas_temp_token_account, escrow_amount)?;
+    escrow_info.set_escrow_amount(escrow_amount);
+    drop(escrow_data);
+    user_profile::add_volume(user_profile, initializer.key, escrow_index, credited, program_id)?;
+
+    events::emit(EscrowEvent::DepositIncreased(DepositIncreased {
+        escrow: *escrow_account.key,
+        amount: credited,
+        escrow_amount,
+    }))?;
+
+    verbose!("Deposit increased successfully");
+    Ok(())
+}
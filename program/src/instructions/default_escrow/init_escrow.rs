@@ -1,71 +1,164 @@
 use solana_program::{
-    account_info::next_account_info,
     account_info::AccountInfo,
-    clock::Clock,
     entrypoint::ProgramResult,
-    msg,
     program_error::ProgramError,
-    program_pack::{IsInitialized, Pack},
+    program_pack::Pack,
     pubkey::Pubkey,
     sysvar::{rent::Rent, Sysvar},
 };
 
+use spl_token_2022::instruction::AuthorityType;
+
 use crate::{
-    states::default_escrow::EscrowState,
-    utils::{self, errors::EscrowError, token_lib},
+    events::{self, EscrowCreated, EscrowEvent},
+    states::{
+        default_escrow::{EscrowState, PodEscrowState},
+        layout::ZeroCopy,
+    },
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, lamport_lib,
+        logging::verbose, memo::{self, EscrowMemo}, time_basis::TimeBasis, token_lib,
+        user_profile, validation,
+    },
 };
 
+instruction_accounts! {
+    /// Accounts expected by `InitEscrow`.
+    pub struct InitEscrowAccounts / InitEscrowKeys {
+        /// The account of the person initializing the escrow, it signs so nobody else fills its profile
+        initializer: signer = true, writable = false, owner = Any;
+        /// The account of the receiver
+        receiver_account: signer = false, writable = false, owner = Any;
+        /// Temporary token account that should be created prior to this instruction and owned by the caller
+        temp_token_account: signer = false, writable = true, owner = Token;
+        /// The escrow PDA `[ESCROW_SEED, initializer, escrow_index]`, created here to hold all necessary info about the trade.
+        escrow_account: signer = false, writable = true, owner = Any;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The caller / relayer, it pays for the escrow and the initializer's profile
+        caller: signer = true, writable = true, owner = Any;
+        /// The mint of the escrowed token
+        mint_account: signer = false, writable = false, owner = Token;
+        /// The initializer's profile PDA, created with its first escrow
+        user_profile: signer = false, writable = true, owner = Any;
+        /// The system program
+        system_program: signer = false, writable = false, owner = Any;
+        /// The program config PDA, capping the initializer's open escrows
+        program_config: signer = false, writable = false, owner = Program;
+    }
+}
+
 /** Initialize Escrow
 
-    Accounts expected:
+    The escrow matures once the wait time of `time_basis` has passed, in
+    unix seconds, slots or epochs, and can be released until its release
+    window closes. Afterwards anyone can refund it with `RefundEscrow`.
+    `memo` links it to an order or invoice, and is logged through SPL Memo
+    when `log_memo` is set.
 
-    0. [] The account of the person initializing the escrow
-    1. [] The account of the receiver
-    2. [writable] Temporary token account that should be created prior to this instruction and owned by the caller
-    3. [writable] The escrow account, it will hold all necessary info about the trade.
-    4. [] The rent sysvar
-    5. [] The token program
-    6. [signer] The caller / relayer
+    The escrow is the next one counted by the initializer's profile, its
+    index is part of the escrow's address. The initializer can have at most
+    the program config's `max_open_escrows_per_user` escrows open.
+
+    Accounts expected: see `InitEscrowAccounts`, followed by the SPL Memo
+    program when `log_memo` is set.
 
 **/
-pub fn handler(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
-    msg!("Escrow starting!");
-    let account_info_iter = &mut accounts.iter();
-    let initializer = next_account_info(account_info_iter)?;
-    let receiver_account = next_account_info(account_info_iter)?;
-    let temp_token_account = next_account_info(account_info_iter)?;
-    let escrow_account = next_account_info(account_info_iter)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let caller = next_account_info(account_info_iter)?;
-
-    if !caller.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+pub fn handler(
+    accounts: &[AccountInfo],
+    amount: u64,
+    time_basis: TimeBasis,
+    memo: EscrowMemo,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose!("Escrow starting!");
+    let InitEscrowAccounts {
+        initializer,
+        receiver_account,
+        temp_token_account,
+        escrow_account,
+        rent_account,
+        token_program,
+        caller,
+        mint_account,
+        user_profile,
+        system_program,
+        program_config,
+        remaining,
+    } = InitEscrowAccounts::try_from_accounts(accounts, program_id)?;
 
-    if !rent.is_exempt(escrow_account.lamports(), escrow_account.data_len()) {
-        return Err(EscrowError::NotRentExempt.into());
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_token_program(token_program)?;
+    validation::assert_system_program(system_program)?;
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
     }
+    memo.validate()?;
+    let memo_program = if memo.log_memo {
+        Some(remaining.first().ok_or(ProgramError::NotEnoughAccountKeys)?)
+    } else {
+        None
+    };
+
+    let rent = &Rent::from_account_info(rent_account)?;
+    token_lib::check_mint(token_program, mint_account)?;
 
-    msg!("Escrow unpacking!");
-    let mut escrow_info = EscrowState::unpack_from_slice(&escrow_account.try_borrow_data()?)?;
-    if escrow_info.is_initialized() {
-        return Err(ProgramError::AccountAlreadyInitialized);
+    // The temp account already holds the deposit, net of any transfer fee
+    // withheld on the way in, so it has to cover the recorded amount.
+    let temp_token_account_info = token_lib::unpack_token_account(token_program, temp_token_account)?;
+    if temp_token_account_info.mint != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
     }
+    if temp_token_account_info.amount < amount {
+        return Err(EscrowError::ExpectedAmountMismatch.into());
+    }
+
+    verbose!("Creating the escrow account...");
+    let escrow_index = user_profile::open_escrow(
+        user_profile,
+        initializer.key,
+        program_config,
+        caller,
+        system_program,
+        rent,
+        amount,
+        program_id,
+    )?;
+    let index_seed = escrow_index.to_le_bytes();
+    let escrow_seeds: &[&[u8]] = &[constants::ESCROW_SEED, initializer.key.as_ref(), &index_seed];
+    let escrow_bump = validation::assert_pda(escrow_account, escrow_seeds, program_id)?;
+    lamport_lib::create_pda_account(
+        caller.clone(),
+        escrow_account.clone(),
+        system_program.clone(),
+        rent.minimum_balance(EscrowState::LEN),
+        EscrowState::LEN,
+        program_id,
+        &[escrow_seeds, &[&[escrow_bump]]].concat(),
+    )?;
 
-    escrow_info.is_initialized = true;
+    verbose!("Escrow initializing!");
+    let expire_date = time_basis.now(&validation::clock()?) + time_basis.wait_time();
+    let refund_after = expire_date + time_basis.release_window();
+    let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+    let escrow_info = PodEscrowState::initialize(&mut escrow_data)?;
     escrow_info.caller_pubkey = *caller.key;
     escrow_info.initializer_pubkey = *initializer.key;
     escrow_info.receiver_pubkey = *receiver_account.key;
     escrow_info.temp_token_account_pubkey = *temp_token_account.key;
-    escrow_info.escrow_amount = amount;
-    escrow_info.expire_date = Clock::get().unwrap().unix_timestamp + utils::constants::ESCROW_WAIT_TIME_SEC;
+    escrow_info.set_escrow_amount(amount);
+    escrow_info.set_expire_date(expire_date);
+    escrow_info.set_voucher_nonce(0);
+    escrow_info.set_time_basis(time_basis);
+    escrow_info.set_refund_after(refund_after);
+    escrow_info.set_escrow_memo(&memo);
+    escrow_info.set_escrow_index(escrow_index);
+    drop(escrow_data);
+    let (pda, _bump_seed) = Pubkey::find_program_address(&[constants::ESCROW_SEED], program_id);
 
-    msg!("Escrow packing!");
-    EscrowState::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
-    let (pda, _bump_seed) = Pubkey::find_program_address(&[b"escrow"], program_id);
-
-    let authority_type = spl_token::instruction::AuthorityType::AccountOwner;
+    let authority_type = AuthorityType::AccountOwner;
     let account_infos = &[
         temp_token_account.clone(),
         caller.clone(),
@@ -73,15 +166,32 @@ pub fn handler(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> Pr
     ];
 
     // This line makes a call to transfer authority from the caller to this program's dervied address
-    let _ = token_lib::change_account_authority(
+    token_lib::change_account_authority(
         token_program.clone(),
         temp_token_account.clone(),
         pda,
         authority_type,
         caller.clone(),
         account_infos,
-    );
+    )?;
+    if let Some(memo_program) = memo_program {
+        memo::log(memo_program, &memo)?;
+    }
+
+    events::emit(EscrowEvent::EscrowCreated(EscrowCreated {
+        escrow: *escrow_account.key,
+        caller: *caller.key,
+        initializer: *initializer.key,
+        receiver: *receiver_account.key,
+        mint: Some(*mint_account.key),
+        amount,
+        expire_date,
+        time_basis,
+        refund_after,
+        reference: memo.reference,
+        memo: memo.memo,
+    }))?;
 
-    msg!("Escrow creation was successful");
+    verbose!("Escrow creation was successful");
     Ok(())
 }
@@ -1,3 +1,7 @@
 pub mod init_escrow;
 pub mod release_escrow;
 pub mod oracle_call;
+pub mod increase_deposit;
+pub mod amend_escrow;
+pub mod assign_receiver;
+pub mod refund_escrow;
@@ -1,14 +1,22 @@
 use solana_program::{
     account_info::AccountInfo,
     pubkey::Pubkey,
-    msg,
-    entrypoint::ProgramResult
+    entrypoint::ProgramResult,
+};
+
+use crate::{
+    events::{self, EscrowEvent, OracleAttested},
+    utils::{logging::verbose, validation},
 };
 
 pub fn handler(
     _accounts: &[AccountInfo],
     _program_id: &Pubkey,
 ) -> ProgramResult {
-    msg!("Oracle Call!");
-    Ok(())
+    verbose!("Oracle Call!");
+    let clock = validation::clock()?;
+    events::emit(EscrowEvent::OracleAttested(OracleAttested {
+        slot: clock.slot,
+        unix_timestamp: clock.unix_timestamp,
+    }))
 }
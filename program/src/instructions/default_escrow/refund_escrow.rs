@@ -0,0 +1,142 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, EscrowEvent, EscrowRefunded},
+    states::{default_escrow::PodEscrowState, layout::ZeroCopy},
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, lamport_lib,
+        logging::verbose, token_lib, user_profile, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `RefundEscrow`.
+    pub struct RefundEscrowAccounts / RefundEscrowKeys {
+        /// The initializer's token account, it gets the escrowed tokens back
+        initializer_token_account: signer = false, writable = true, owner = Token;
+        /// The PDA's temp token account to get tokens from and eventually close
+        pdas_temp_token_account: signer = false, writable = true, owner = Token;
+        /// The escrow account holding the escrow info, closed on refund
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The PDA account
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The caller / relayer recorded on the escrow, it gets the rent back
+        caller: signer = false, writable = true, owner = Any;
+        /// The mint of the escrowed token, written when withheld transfer fees are harvested
+        mint_account: signer = false, writable = true, owner = Token;
+        /// The initializer's profile PDA, the escrow is uncounted from its open escrows
+        user_profile: signer = false, writable = true, owner = Any;
+    }
+}
+
+/** Refund Escrow
+
+    Returns the escrowed tokens to the initializer once the release window
+    has closed, along with anything else the temp account holds. Nobody has
+    to sign, so any crank can trigger it.
+
+    Accounts expected: see `RefundEscrowAccounts`, followed by any extra
+    accounts required by the mint's transfer hook.
+
+**/
+pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose!("Refund Escrow starting!");
+    let RefundEscrowAccounts {
+        initializer_token_account,
+        pdas_temp_token_account,
+        escrow_account,
+        token_program,
+        pda_account,
+        caller,
+        mint_account,
+        user_profile,
+        remaining: transfer_hook_accounts,
+    } = RefundEscrowAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_token_program(token_program)?;
+    let bump_seed = validation::assert_pda(pda_account, &[constants::ESCROW_SEED], program_id)?;
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
+
+    verbose!("Loading Escrow!");
+    let escrow_data = escrow_account.try_borrow_data()?;
+    let escrow_info = PodEscrowState::load(&escrow_data)?;
+
+    verbose!("Running Checks!");
+    if !escrow_info.refund_due(escrow_info.time_basis().now(&validation::clock()?)) {
+        return Err(EscrowError::RefundNotDue.into());
+    }
+    if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
+        return Err(EscrowError::TempTokenAccountMismatch.into());
+    }
+    if escrow_info.caller_pubkey != *caller.key {
+        return Err(EscrowError::CallerMismatch.into());
+    }
+    let initializer = escrow_info.initializer_pubkey;
+    let memo = escrow_info.escrow_memo();
+    let escrow_index = escrow_info.escrow_index();
+    drop(escrow_data);
+
+    let initializer_token_account_info =
+        token_lib::unpack_token_account(token_program, initializer_token_account)?;
+    if initializer_token_account_info.owner != initializer {
+        return Err(EscrowError::TokenOwnerMismatch.into());
+    }
+    if initializer_token_account_info.mint != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
+    }
+    // The whole balance, the temp account can only be closed empty.
+    let amount = token_lib::unpack_token_account(token_program, pdas_temp_token_account)?.amount;
+
+    verbose!("Transfer Tokens!");
+    let fee = token_lib::transfer_fee(mint_account, amount)?;
+    let signer_seeds: &[&[u8]] = &[constants::ESCROW_SEED, &[bump_seed]];
+    token_lib::transfer_tokens(
+        token_program.clone(),
+        pdas_temp_token_account.clone(),
+        mint_account.clone(),
+        initializer_token_account.clone(),
+        pda_account.clone(),
+        transfer_hook_accounts,
+        amount,
+        decimals,
+        signer_seeds,
+    )?;
+
+    verbose!("Closing Token Account!");
+    if *token_program.key == spl_token_2022::id() {
+        token_lib::harvest_withheld_fees(
+            token_program.clone(),
+            mint_account.clone(),
+            pdas_temp_token_account.clone(),
+        )?;
+    }
+    token_lib::close_token_account(
+        token_program.clone(),
+        pdas_temp_token_account.clone(),
+        caller.clone(),
+        pda_account.clone(),
+        signer_seeds,
+    )?;
+
+    verbose!("Closing the escrow account...");
+    lamport_lib::close_account(escrow_account, caller)?;
+    user_profile::close_escrow(user_profile, &initializer, escrow_index, program_id)?;
+
+    events::emit(EscrowEvent::EscrowRefunded(EscrowRefunded {
+        escrow: *escrow_account.key,
+        initializer,
+        amount,
+        fee,
+        reference: memo.reference,
+        memo: memo.memo,
+    }))?;
+
+    verbose!("Escrow refunded successfully");
+    Ok(())
+}
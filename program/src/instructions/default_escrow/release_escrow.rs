@@ -1,119 +1,316 @@
 use solana_program::{
-    account_info::next_account_info, account_info::AccountInfo, clock::Clock,
-    entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError,
-    program_pack::Pack, pubkey::Pubkey, sysvar::Sysvar,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult, program_error::ProgramError,
+    program_pack::Pack, pubkey::Pubkey,
 };
 
 use crate::{
-    states::default_escrow::EscrowState,
-    utils::{errors::EscrowError, token_lib, constants},
+    events::{self, EscrowEvent, EscrowReleased},
+    states::{
+        default_escrow::{EscrowState, PodEscrowState},
+        layout::{AccountLayout, ZeroCopy},
+    },
+    utils::{
+        accounts::instruction_accounts, errors::EscrowError, logging::verbose, memo, token_lib,
+        constants, user_profile, validation,
+        voucher::{self, Voucher},
+    },
 };
-use spl_token::state::Account as TokenAccount;
+
+instruction_accounts! {
+    /// Accounts expected by `ReleaseEscrow`.
+    pub struct ReleaseEscrowAccounts / ReleaseEscrowKeys {
+        /// The receiver's token account for the token they will receive
+        receiver_token_account: signer = false, writable = true, owner = Token;
+        /// The PDA's temp token account to get tokens from and eventually close
+        pdas_temp_token_account: signer = false, writable = true, owner = Token;
+        /// The initializer's main account
+        initializers_main_account: signer = false, writable = false, owner = Any;
+        /// The escrow account holding the escrow info, closed on release
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The PDA account
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The caller / relayer, it gets the rent back
+        caller: signer = true, writable = true, owner = Any;
+        /// The mint of the escrowed token, written when withheld transfer fees are harvested
+        mint_account: signer = false, writable = true, owner = Token;
+        /// The initializer's profile PDA, the escrow is uncounted from its open escrows when closed
+        user_profile: signer = false, writable = true, owner = Any;
+        /// The initializer's token account, it gets back the tokens held above the escrowed
+        /// amount when the escrow closes. Only checked when there are any
+        initializer_token_account: signer = false, writable = true, owner = Any;
+    }
+}
 
 /** Release Escrow Funds
 
-    Accounts expected:
+    Only possible inside the release window, from maturity until the escrow
+    is due for a refund. Tokens the temp account holds above the escrowed
+    amount, sent to it on init or since, go back to the initializer when
+    the escrow closes.
 
-    0. [] The account of the person taking the trade
-    1. [writable] The taker's token account for the token they will receive should the trade go through
-    2. [writable] The PDA's temp token account to get tokens from and eventually close
-    3. [writable] The initializer's main account to send their rent fees to
-    4. [writable] The escrow account holding the escrow info
-    5. [] The token program
-    6. [] The PDA account
-    7. [signer] The caller / relayer
+    Accounts expected: see `ReleaseEscrowAccounts`, followed by the SPL Memo
+    program when the escrow logs memos, and any extra accounts required by
+    the mint's transfer hook.
 
 **/
 pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    msg!("Release Escrow starting!");
-
-    let receiver_token_account = next_account_info(account_info_iter)?;
-    let pdas_temp_token_account = next_account_info(account_info_iter)?;
-    let initializers_main_account = next_account_info(account_info_iter)?;
-    let escrow_account = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let pda_account = next_account_info(account_info_iter)?;
-    let caller = next_account_info(account_info_iter)?;
-
-    let (pda, bump_seed) = Pubkey::find_program_address(&[constants::ESCROW_SEED], program_id);
-    msg!("Unpacking Escrow!");
-    let escrow_info = EscrowState::unpack_from_slice(&escrow_account.try_borrow_data()?)?;
-    let current_timestamp = Clock::get().unwrap().unix_timestamp;
+    verbose!("Release Escrow starting!");
+    let accounts = ReleaseEscrowAccounts::try_from_accounts(accounts, program_id)?;
+    release(accounts, None, program_id)
+}
+
+/** Release Escrow Funds with a Voucher
+
+    The voucher is signed off-chain and checked by an Ed25519 precompile
+    instruction placed right before this one, so its signer doesn't have
+    to send it: the caller recorded on the escrow relays it, as it does
+    plain releases, and gets the rent back.
+    Signed by the initializer it releases to the receiver before maturity,
+    signed by the receiver it releases to any recipient once matured. A
+    voucher for less than the escrowed amount leaves the rest in escrow.
+
+    Accounts expected: see `ReleaseEscrowAccounts`, followed by the
+    instructions sysvar, the SPL Memo program when the escrow logs memos,
+    and any extra accounts required by the mint's transfer hook.
+
+**/
+pub fn voucher_handler(
+    accounts: &[AccountInfo],
+    voucher: Voucher,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    verbose!("Release Escrow with voucher starting!");
+    let mut accounts = ReleaseEscrowAccounts::try_from_accounts(accounts, program_id)?;
+    let (instructions_sysvar, transfer_hook_accounts) = accounts
+        .remaining
+        .split_first()
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let signer = voucher::ed25519_signer(instructions_sysvar, &voucher.message())?;
+    accounts.remaining = transfer_hook_accounts;
+    release(accounts, Some((voucher, signer)), program_id)
+}
+
+fn release(
+    accounts: ReleaseEscrowAccounts,
+    voucher: Option<(Voucher, Pubkey)>,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    let ReleaseEscrowAccounts {
+        receiver_token_account,
+        pdas_temp_token_account,
+        initializers_main_account,
+        escrow_account,
+        token_program,
+        pda_account,
+        caller,
+        mint_account,
+        user_profile,
+        initializer_token_account,
+        remaining,
+    } = accounts;
+
+    validation::assert_token_program(token_program)?;
+    let bump_seed = validation::assert_pda(pda_account, &[constants::ESCROW_SEED], program_id)?;
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
+
+    verbose!("Loading Escrow!");
+    let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+    // A legacy escrow is read into a copy in the current layout, which is
+    // never written back.
+    let mut legacy_data;
+    let is_legacy = escrow_data.len() == constants::LEGACY_DEFAULT_ESCROW_STATE_LEN;
+    let escrow_info = if is_legacy {
+        legacy_data = vec![0; EscrowState::LEN];
+        EscrowState::pack(EscrowState::unpack_versioned(&escrow_data)?, &mut legacy_data)?;
+        PodEscrowState::load_mut(&mut legacy_data)?
+    } else {
+        PodEscrowState::load_mut(&mut escrow_data)?
+    };
+    let clock = validation::clock()?;
     let receiver_token_account_info =
-        TokenAccount::unpack(&receiver_token_account.try_borrow_data()?)?;
+        token_lib::unpack_token_account(token_program, receiver_token_account)?;
+    let pdas_temp_token_account_info =
+        token_lib::unpack_token_account(token_program, pdas_temp_token_account)?;
 
-    msg!("Running Checks!");
-    if current_timestamp < escrow_info.expire_date {
+    verbose!("Running Checks!");
+    let escrow_amount = escrow_info.escrow_amount();
+    let (amount, recipient, matures) = match voucher {
+        None => (escrow_amount, escrow_info.receiver_pubkey, true),
+        Some((voucher, signer)) => {
+            verbose!("Running Checks: Voucher!");
+            if voucher.escrow != *escrow_account.key
+                || voucher.amount == 0
+                || voucher.amount > escrow_amount
+            {
+                return Err(EscrowError::InvalidVoucher.into());
+            }
+            if clock.unix_timestamp > voucher.expiry {
+                return Err(EscrowError::VoucherExpired.into());
+            }
+            if voucher.nonce <= escrow_info.voucher_nonce() {
+                return Err(EscrowError::VoucherReplayed.into());
+            }
+            escrow_info.set_voucher_nonce(voucher.nonce);
+            if signer == escrow_info.initializer_pubkey {
+                if voucher.recipient != escrow_info.receiver_pubkey {
+                    return Err(EscrowError::InvalidVoucher.into());
+                }
+                (voucher.amount, voucher.recipient, false)
+            } else if signer == escrow_info.receiver_pubkey {
+                (voucher.amount, voucher.recipient, true)
+            } else {
+                return Err(EscrowError::InvalidVoucher.into());
+            }
+        }
+    };
+
+    // A legacy escrow has no room for what a partial release leaves.
+    if is_legacy && amount < escrow_amount {
+        return Err(EscrowError::AccountTooSmall.into());
+    }
+    let now = escrow_info.time_basis().now(&clock);
+    if matures && now < escrow_info.expire_date() {
         return Err(EscrowError::EscrowNotMaturedYet.into());
     }
-
-    msg!("Running Checks: Receiver PubKey! {:?}", receiver_token_account_info);
-    if escrow_info.receiver_pubkey != receiver_token_account_info.owner {
-        return Err(ProgramError::InvalidAccountData);
+    if escrow_info.refund_due(now) {
+        return Err(EscrowError::ReleaseWindowClosed.into());
     }
 
-    msg!("Running Checks: Pda Token PubKey!");
+    verbose!("Running Checks: Receiver PubKey! {:?}", receiver_token_account_info);
+    if recipient != receiver_token_account_info.owner {
+        return Err(EscrowError::TokenOwnerMismatch.into());
+    }
+    verbose!("Running Checks: Pda Token PubKey!");
     if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(EscrowError::TempTokenAccountMismatch.into());
     }
 
-    msg!("Running Checks: Initializer PubKey!");
+    verbose!("Running Checks: Initializer PubKey!");
     if escrow_info.initializer_pubkey != *initializers_main_account.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(EscrowError::InitializerMismatch.into());
     }
 
     if escrow_info.caller_pubkey != *caller.key {
-        return Err(ProgramError::InvalidAccountData);
+        return Err(EscrowError::CallerMismatch.into());
     }
 
-    msg!("Transfer Tokens!");
-    let amount = escrow_info.escrow_amount;
-    let account_infos = &[
-        pdas_temp_token_account.clone(),
-        receiver_token_account.clone(),
-        pda_account.clone(),
-        token_program.clone(),
-    ];
+    verbose!("Running Checks: Mint PubKey!");
+    if pdas_temp_token_account_info.mint != *mint_account.key
+        || receiver_token_account_info.mint != *mint_account.key
+    {
+        return Err(EscrowError::MintMismatch.into());
+    }
+    let initializer = escrow_info.initializer_pubkey;
+    let escrow_index = escrow_info.escrow_index();
+    let memo = escrow_info.escrow_memo();
+    drop(escrow_data);
+
+    let (memo_program, transfer_hook_accounts) = if memo.log_memo {
+        let (memo_program, rest) = remaining
+            .split_first()
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        (Some(memo_program), rest)
+    } else {
+        (None, remaining)
+    };
 
-    let _ = token_lib::transfer_tokens(
+    verbose!("Transfer Tokens!");
+    let fee = token_lib::transfer_fee(mint_account, amount)?;
+    verbose!("Releasing {} tokens, {} withheld as transfer fee", amount, fee);
+    let signer_seeds: &[&[u8]] = &[constants::ESCROW_SEED, &[bump_seed]];
+
+    token_lib::transfer_tokens(
         token_program.clone(),
         pdas_temp_token_account.clone(),
+        mint_account.clone(),
         receiver_token_account.clone(),
-        pda,
+        pda_account.clone(),
+        transfer_hook_accounts,
         amount,
-        account_infos,
-        bump_seed,
-    );
-
-    msg!("Closing Token Account!");
-    let close_pdas_temp_acc_ix = spl_token::instruction::close_account(
-        token_program.key,
-        pdas_temp_token_account.key,
-        caller.key,
-        &pda,
-        &[&pda],
+        decimals,
+        signer_seeds,
     )?;
-    msg!("Calling the token program to close pda's temp account...");
-    invoke_signed(
-        &close_pdas_temp_acc_ix,
-        &[
+    if let Some(memo_program) = memo_program {
+        memo::log(memo_program, &memo)?;
+    }
+
+    if amount < escrow_amount {
+        verbose!("Keeping {} tokens in escrow", escrow_amount - amount);
+        let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+        PodEscrowState::load_mut(&mut escrow_data)?.set_escrow_amount(escrow_amount - amount);
+        drop(escrow_data);
+        events::emit(EscrowEvent::EscrowReleased(EscrowReleased {
+            escrow: *escrow_account.key,
+            receiver: recipient,
+            amount,
+            fee,
+            reference: memo.reference,
+            memo: memo.memo,
+        }))?;
+        return Ok(());
+    }
+
+    let surplus = pdas_temp_token_account_info.amount - amount;
+    if surplus > 0 {
+        verbose!("Returning {} tokens held above the escrowed amount", surplus);
+        let initializer_token_account_info =
+            token_lib::unpack_token_account(token_program, initializer_token_account)?;
+        if initializer_token_account_info.owner != initializer {
+            return Err(EscrowError::TokenOwnerMismatch.into());
+        }
+        if initializer_token_account_info.mint != *mint_account.key {
+            return Err(EscrowError::MintMismatch.into());
+        }
+        token_lib::transfer_tokens(
+            token_program.clone(),
             pdas_temp_token_account.clone(),
-            caller.clone(),
+            mint_account.clone(),
+            initializer_token_account.clone(),
             pda_account.clone(),
+            transfer_hook_accounts,
+            surplus,
+            decimals,
+            signer_seeds,
+        )?;
+    }
+
+    verbose!("Closing Token Account!");
+    if *token_program.key == spl_token_2022::id() {
+        token_lib::harvest_withheld_fees(
             token_program.clone(),
-        ],
-        &[&[&constants::ESCROW_SEED[..], &[bump_seed]]],
+            mint_account.clone(),
+            pdas_temp_token_account.clone(),
+        )?;
+    }
+    token_lib::close_token_account(
+        token_program.clone(),
+        pdas_temp_token_account.clone(),
+        caller.clone(),
+        pda_account.clone(),
+        signer_seeds,
     )?;
 
-    msg!("Closing the escrow account...");
+    verbose!("Closing the escrow account...");
     **caller.lamports.borrow_mut() = caller
         .lamports()
         .checked_add(escrow_account.lamports())
         .ok_or(EscrowError::AmountOverflow)?;
     **escrow_account.lamports.borrow_mut() = 0;
     *escrow_account.try_borrow_mut_data()? = &mut [];
+    user_profile::close_escrow(user_profile, &initializer, escrow_index, program_id)?;
+
+    events::emit(EscrowEvent::EscrowReleased(EscrowReleased {
+        escrow: *escrow_account.key,
+        receiver: recipient,
+        amount,
+        fee,
+        reference: memo.reference,
+        memo: memo.memo,
+    }))?;
 
-    msg!("Escrow released successfully");
+    verbose!("Escrow released successfully");
     Ok(())
 }
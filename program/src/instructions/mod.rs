@@ -1,5 +1,9 @@
 pub mod default_escrow;
 pub mod wallet_escrow;
+pub mod sol_escrow;
+pub mod admin;
 
 pub use default_escrow::*;
-pub use wallet_escrow::*;
\ No newline at end of file
+pub use wallet_escrow::*;
+pub use sol_escrow::*;
+pub use admin::*;
@@ -0,0 +1,124 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    events::{self, EscrowCreated, EscrowEvent},
+    states::sol_escrow::SolEscrowState,
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, logging::verbose,
+        lamport_lib, time_basis::TimeBasis, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `InitSolEscrow`.
+    pub struct InitSolEscrowAccounts / InitSolEscrowKeys {
+        /// The account of the person initializing the escrow, it funds the vault
+        initializer: signer = true, writable = true, owner = Any;
+        /// The account of the receiver
+        receiver_account: signer = false, writable = false, owner = Any;
+        /// The vault PDA, derived from the escrow account, that will hold the lamports
+        vault_account: signer = false, writable = true, owner = Any;
+        /// The escrow account, it will hold all necessary info about the trade.
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The system program
+        system_program: signer = false, writable = false, owner = Any;
+        /// The caller / relayer
+        caller: signer = true, writable = false, owner = Any;
+    }
+}
+
+/** Initialize Native SOL Escrow
+
+    The escrow matures after `ESCROW_WAIT_TIME_SEC` and can be released
+    for `ESCROW_RELEASE_WINDOW_SEC` more, then only refunded.
+
+    Accounts expected: see `InitSolEscrowAccounts`.
+
+**/
+pub fn handler(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    verbose!("Sol Escrow starting!");
+    let InitSolEscrowAccounts {
+        initializer,
+        receiver_account,
+        vault_account,
+        escrow_account,
+        rent_account,
+        system_program,
+        caller,
+        ..
+    } = InitSolEscrowAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_system_program(system_program)?;
+
+    let rent = &Rent::from_account_info(rent_account)?;
+    if !rent.is_exempt(escrow_account.lamports(), escrow_account.data_len()) {
+        return Err(EscrowError::NotRentExempt.into());
+    }
+
+    let vault_bump = validation::assert_pda(
+        vault_account,
+        &[constants::SOL_VAULT_SEED, escrow_account.key.as_ref()],
+        program_id,
+    )?;
+
+    verbose!("Sol Escrow unpacking!");
+    let mut escrow_info = SolEscrowState::unpack_unchecked(&escrow_account.try_borrow_data()?)?;
+    if escrow_info.is_initialized() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let vault_lamports = rent
+        .minimum_balance(0)
+        .checked_add(amount)
+        .ok_or(EscrowError::AmountOverflow)?;
+    lamport_lib::create_pda_account(
+        initializer.clone(),
+        vault_account.clone(),
+        system_program.clone(),
+        vault_lamports,
+        0,
+        program_id,
+        &[constants::SOL_VAULT_SEED, escrow_account.key.as_ref(), &[vault_bump]],
+    )?;
+
+    let expire_date = validation::clock()?.unix_timestamp + constants::ESCROW_WAIT_TIME_SEC;
+    let refund_after = expire_date + TimeBasis::UnixTimestamp.release_window();
+    escrow_info.is_initialized = true;
+    escrow_info.caller_pubkey = *caller.key;
+    escrow_info.initializer_pubkey = *initializer.key;
+    escrow_info.receiver_pubkey = *receiver_account.key;
+    escrow_info.vault_pubkey = *vault_account.key;
+    escrow_info.escrow_amount = amount;
+    escrow_info.expire_date = expire_date;
+    escrow_info.refund_after = refund_after;
+
+    verbose!("Sol Escrow packing!");
+    SolEscrowState::pack(escrow_info, &mut escrow_account.try_borrow_mut_data()?)?;
+
+    events::emit(EscrowEvent::EscrowCreated(EscrowCreated {
+        escrow: *escrow_account.key,
+        caller: *caller.key,
+        initializer: *initializer.key,
+        receiver: *receiver_account.key,
+        mint: None,
+        amount,
+        expire_date,
+        time_basis: TimeBasis::UnixTimestamp,
+        refund_after,
+        reference: None,
+        memo: String::new(),
+    }))?;
+
+    verbose!("Sol Escrow creation was successful");
+    Ok(())
+}
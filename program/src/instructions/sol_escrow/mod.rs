@@ -0,0 +1,3 @@
+pub mod init_sol_escrow;
+pub mod release_sol_escrow;
+pub mod refund_sol_escrow;
@@ -0,0 +1,99 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, EscrowEvent, EscrowRefunded},
+    states::{layout::AccountLayout, sol_escrow::SolEscrowState},
+    utils::{
+        accounts::instruction_accounts, errors::EscrowError, logging::verbose, lamport_lib,
+        validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `RefundSolEscrow`.
+    pub struct RefundSolEscrowAccounts / RefundSolEscrowKeys {
+        /// The initializer's system account, it gets the escrowed lamports back
+        initializer_account: signer = false, writable = true, owner = Any;
+        /// The vault PDA holding the lamports
+        vault_account: signer = false, writable = true, owner = Program;
+        /// The escrow account holding the escrow info
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The caller / relayer recorded on the escrow, it gets the rent back. Signs
+        /// refunds before maturity
+        caller: signer = false, writable = true, owner = Any;
+    }
+}
+
+/** Refund Native SOL Escrow
+
+    The escrowed lamports go back to the initializer. Before the escrow
+    matures the recorded caller can cancel it by signing. Once the release
+    window has closed nobody has to sign, so any crank can trigger it. In
+    between the funds belong to the receiver.
+
+    Accounts expected: see `RefundSolEscrowAccounts`.
+
+**/
+pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose!("Refund Sol Escrow starting!");
+    let RefundSolEscrowAccounts {
+        initializer_account,
+        vault_account,
+        escrow_account,
+        caller,
+        ..
+    } = RefundSolEscrowAccounts::try_from_accounts(accounts, program_id)?;
+
+    verbose!("Unpacking Sol Escrow!");
+    let escrow_info = SolEscrowState::unpack_versioned(&escrow_account.try_borrow_data()?)?;
+    let current_timestamp = validation::clock()?.unix_timestamp;
+
+    verbose!("Running Checks!");
+    if !escrow_info.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    if !escrow_info.refund_due(current_timestamp) {
+        if current_timestamp >= escrow_info.expire_date {
+            return Err(EscrowError::RefundNotDue.into());
+        }
+        if !caller.is_signer {
+            return Err(EscrowError::AccountNotSigner.into());
+        }
+    }
+
+    if escrow_info.initializer_pubkey != *initializer_account.key {
+        return Err(EscrowError::InitializerMismatch.into());
+    }
+
+    if escrow_info.vault_pubkey != *vault_account.key {
+        return Err(EscrowError::VaultMismatch.into());
+    }
+
+    if escrow_info.caller_pubkey != *caller.key {
+        return Err(EscrowError::CallerMismatch.into());
+    }
+
+    verbose!("Transfer Lamports!");
+    lamport_lib::transfer_lamports(vault_account, initializer_account, escrow_info.escrow_amount)?;
+
+    verbose!("Closing the vault and escrow accounts...");
+    lamport_lib::close_account(vault_account, caller)?;
+    lamport_lib::close_account(escrow_account, caller)?;
+
+    events::emit(EscrowEvent::EscrowRefunded(EscrowRefunded {
+        escrow: *escrow_account.key,
+        initializer: escrow_info.initializer_pubkey,
+        amount: escrow_info.escrow_amount,
+        fee: 0,
+        reference: None,
+        memo: String::new(),
+    }))?;
+
+    verbose!("Sol Escrow refunded successfully");
+    Ok(())
+}
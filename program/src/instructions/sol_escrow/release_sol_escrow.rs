@@ -0,0 +1,95 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, EscrowEvent, EscrowReleased},
+    states::{layout::AccountLayout, sol_escrow::SolEscrowState},
+    utils::{
+        accounts::instruction_accounts, errors::EscrowError, logging::verbose, lamport_lib,
+        validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `ReleaseSolEscrow`.
+    pub struct ReleaseSolEscrowAccounts / ReleaseSolEscrowKeys {
+        /// The receiver's system account, it gets the escrowed lamports
+        receiver_account: signer = false, writable = true, owner = Any;
+        /// The vault PDA holding the lamports
+        vault_account: signer = false, writable = true, owner = Program;
+        /// The escrow account holding the escrow info
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The caller / relayer, it gets the rent back
+        caller: signer = true, writable = true, owner = Any;
+    }
+}
+
+/** Release Native SOL Escrow
+
+    Pays the escrowed lamports to the receiver. Only possible inside the
+    release window, from maturity until the escrow is due for a refund.
+
+    Accounts expected: see `ReleaseSolEscrowAccounts`.
+
+**/
+pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose!("Release Sol Escrow starting!");
+    let ReleaseSolEscrowAccounts {
+        receiver_account,
+        vault_account,
+        escrow_account,
+        caller,
+        ..
+    } = ReleaseSolEscrowAccounts::try_from_accounts(accounts, program_id)?;
+
+    verbose!("Unpacking Sol Escrow!");
+    let escrow_info = SolEscrowState::unpack_versioned(&escrow_account.try_borrow_data()?)?;
+    let current_timestamp = validation::clock()?.unix_timestamp;
+
+    verbose!("Running Checks!");
+    if !escrow_info.is_initialized {
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    if current_timestamp < escrow_info.expire_date {
+        return Err(EscrowError::EscrowNotMaturedYet.into());
+    }
+
+    if escrow_info.refund_due(current_timestamp) {
+        return Err(EscrowError::ReleaseWindowClosed.into());
+    }
+
+    if escrow_info.receiver_pubkey != *receiver_account.key {
+        return Err(EscrowError::ReceiverMismatch.into());
+    }
+
+    if escrow_info.vault_pubkey != *vault_account.key {
+        return Err(EscrowError::VaultMismatch.into());
+    }
+
+    if escrow_info.caller_pubkey != *caller.key {
+        return Err(EscrowError::CallerMismatch.into());
+    }
+
+    verbose!("Transfer Lamports!");
+    lamport_lib::transfer_lamports(vault_account, receiver_account, escrow_info.escrow_amount)?;
+
+    verbose!("Closing the vault and escrow accounts...");
+    lamport_lib::close_account(vault_account, caller)?;
+    lamport_lib::close_account(escrow_account, caller)?;
+
+    events::emit(EscrowEvent::EscrowReleased(EscrowReleased {
+        escrow: *escrow_account.key,
+        receiver: escrow_info.receiver_pubkey,
+        amount: escrow_info.escrow_amount,
+        fee: 0,
+        reference: None,
+        memo: String::new(),
+    }))?;
+
+    verbose!("Sol Escrow released successfully");
+    Ok(())
+}
@@ -0,0 +1,151 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    events::{self, EscrowEvent, MintAdded},
+    states::{
+        escrow_registry::PodEscrowRegistryState, layout::ZeroCopy, registry_mint::RegistryMintState,
+    },
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, lamport_lib, logging::verbose,
+        token_lib, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `AddMint`.
+    pub struct AddMintAccounts / AddMintKeys {
+        /// The registry owner, it pays for the new accounts
+        owner: signer = true, writable = true, owner = Any;
+        /// The registry account
+        registry_account: signer = false, writable = true, owner = Program;
+        /// The registry mint PDA to create
+        registry_mint: signer = false, writable = true, owner = Any;
+        /// The vault PDA to create, or the token account registered with the registry
+        vault_account: signer = false, writable = true, owner = Any;
+        /// The mint to accept
+        mint_account: signer = false, writable = false, owner = Token;
+        /// The wallet escrow PDA, authority of every vault
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The system program
+        system_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/// Add a Mint to a Wallet Escrow
+///
+/// The vault is a new token account at `[REGISTRY_VAULT_SEED, registry, mint]`,
+/// except for the mint of the token account given to `RegisterEscrow`, which
+/// is adopted as is.
+///
+/// Accounts expected: see [`AddMintAccounts`].
+pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose!("Add Mint starting!");
+    let AddMintAccounts {
+        owner,
+        registry_account,
+        registry_mint,
+        vault_account,
+        mint_account,
+        pda_account,
+        rent_account,
+        token_program,
+        system_program,
+        ..
+    } = AddMintAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_token_program(token_program)?;
+    validation::assert_system_program(system_program)?;
+    validation::assert_pda(pda_account, &[constants::ESCROW_WALLET_SEED], program_id)?;
+    token_lib::check_mint(token_program, mint_account)?;
+    let rent = Rent::from_account_info(rent_account)?;
+
+    verbose!("Loading Registry!");
+    let registry_data = registry_account.try_borrow_data()?;
+    let registry = PodEscrowRegistryState::load(&registry_data)?;
+    if registry.initializer_pubkey != *owner.key {
+        return Err(EscrowError::RegistryOwnerMismatch.into());
+    }
+    let registered_token_account = registry.token_account_pubkey;
+    drop(registry_data);
+
+    let registry_mint_seeds: &[&[u8]] =
+        &[constants::REGISTRY_MINT_SEED, registry_account.key.as_ref(), mint_account.key.as_ref()];
+    let registry_mint_bump = validation::assert_pda(registry_mint, registry_mint_seeds, program_id)?;
+    if !registry_mint.data_is_empty() {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    if *vault_account.key == registered_token_account {
+        verbose!("Adopting the registered token account!");
+        let vault = token_lib::unpack_token_account(token_program, vault_account)?;
+        if vault.mint != *mint_account.key {
+            return Err(EscrowError::MintMismatch.into());
+        }
+        if vault.owner != *pda_account.key {
+            return Err(EscrowError::TokenOwnerMismatch.into());
+        }
+    } else {
+        let vault_seeds: &[&[u8]] = &[
+            constants::REGISTRY_VAULT_SEED,
+            registry_account.key.as_ref(),
+            mint_account.key.as_ref(),
+        ];
+        let vault_bump = validation::assert_pda(vault_account, vault_seeds, program_id)?;
+        token_lib::create_pda_token_account(
+            owner.clone(),
+            vault_account.clone(),
+            mint_account.clone(),
+            token_program.clone(),
+            system_program.clone(),
+            pda_account.key,
+            &rent,
+            &[vault_seeds, &[&[vault_bump]]].concat(),
+        )?;
+    }
+
+    lamport_lib::create_pda_account(
+        owner.clone(),
+        registry_mint.clone(),
+        system_program.clone(),
+        rent.minimum_balance(RegistryMintState::LEN),
+        RegistryMintState::LEN,
+        program_id,
+        &[registry_mint_seeds, &[&[registry_mint_bump]]].concat(),
+    )?;
+    let state = RegistryMintState {
+        is_initialized: true,
+        registry_pubkey: *registry_account.key,
+        mint_pubkey: *mint_account.key,
+        vault_pubkey: *vault_account.key,
+        total_deposits: 0,
+    };
+
+    verbose!("Packing!");
+    RegistryMintState::pack(state, &mut registry_mint.try_borrow_mut_data()?)?;
+    let mut registry_data = registry_account.try_borrow_mut_data()?;
+    let registry = PodEscrowRegistryState::load_mut(&mut registry_data)?;
+    let mint_count = registry.mint_count().checked_add(1).ok_or(EscrowError::AmountOverflow)?;
+    registry.set_mint_count(mint_count);
+    drop(registry_data);
+
+    events::emit(EscrowEvent::MintAdded(MintAdded {
+        registry: *registry_account.key,
+        mint: *mint_account.key,
+        vault: *vault_account.key,
+    }))?;
+
+    verbose!("Mint added successfully");
+    Ok(())
+}
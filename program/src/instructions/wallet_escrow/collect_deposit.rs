@@ -1,111 +1,149 @@
 use solana_program::{
-    account_info::next_account_info, account_info::AccountInfo, clock::Clock,
-    entrypoint::ProgramResult, msg, program::invoke_signed, program_error::ProgramError,
-    program_pack::Pack, pubkey::Pubkey, sysvar::Sysvar,
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_pack::Pack,
+    pubkey::Pubkey,
 };
 
 use crate::{
-    states::default_escrow::EscrowState,
-    utils::{errors::EscrowError, token_lib, constants},
+    events::{self, DepositCollected, EscrowEvent},
+    states::{
+        deposit_record::DepositRecordState, escrow_registry::PodEscrowRegistryState,
+        layout::ZeroCopy, registry_mint::RegistryMintState,
+    },
+    instructions::wallet_escrow::deposit::load_registry_mint,
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, logging::verbose, token_lib,
+        validation,
+    },
 };
-use spl_token::state::Account as TokenAccount;
 
-/// Release Escrow Funds
+instruction_accounts! {
+    /// Accounts expected by `CollectDeposit`.
+    pub struct CollectDepositAccounts / CollectDepositKeys {
+        /// The registry owner
+        owner: signer = true, writable = false, owner = Any;
+        /// The token account to send the collected tokens to
+        receiver_token_account: signer = false, writable = true, owner = Token;
+        /// The vault of the mint
+        vault_account: signer = false, writable = true, owner = Token;
+        /// The registry account
+        registry_account: signer = false, writable = false, owner = Program;
+        /// The registry mint PDA of the mint
+        registry_mint: signer = false, writable = true, owner = Program;
+        /// The deposit record PDA to collect
+        deposit_record: signer = false, writable = true, owner = Program;
+        /// The depositor, signing releases the deposit before it matures
+        depositor: signer = false, writable = false, owner = Any;
+        /// The wallet escrow PDA, authority of the vault
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The mint of the deposit
+        mint_account: signer = false, writable = false, owner = Token;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/// Collect a Wallet Escrow Deposit
 ///
+/// The registry owner takes the whole balance of a deposit record once it
+/// matured, or earlier when the depositor co-signs to release it.
 ///
-/// Accounts expected:
-/// 0. `[signer]` The account of the person taking the trade
-/// 1. `[writable]` The taker's token account for the token they will receive should the trade go through
-/// 2. `[writable]` The PDA's temp token account to get tokens from and eventually close
-/// 3. `[writable]` The initializer's main account to send their rent fees to
-/// 4. `[writable]` The escrow account holding the escrow info
-/// 5. `[]` The token program
-/// 6. `[]` The PDA account
+/// Accounts expected: see [`CollectDepositAccounts`], followed by any extra
+/// accounts required by the mint's transfer hook.
 pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    let account_info_iter = &mut accounts.iter();
-    msg!("Release Escrow starting!");
+    verbose!("Collect Deposit starting!");
+    let CollectDepositAccounts {
+        owner,
+        receiver_token_account,
+        vault_account,
+        registry_account,
+        registry_mint,
+        deposit_record,
+        depositor,
+        pda_account,
+        mint_account,
+        token_program,
+        remaining: transfer_hook_accounts,
+    } = CollectDepositAccounts::try_from_accounts(accounts, program_id)?;
 
-    let receiver_token_account = next_account_info(account_info_iter)?;
-    let pdas_temp_token_account = next_account_info(account_info_iter)?;
-    let initializers_main_account = next_account_info(account_info_iter)?;
-    let escrow_account = next_account_info(account_info_iter)?;
-    let token_program = next_account_info(account_info_iter)?;
-    let pda_account = next_account_info(account_info_iter)?;
+    validation::assert_token_program(token_program)?;
+    let bump_seed = validation::assert_pda(pda_account, &[constants::ESCROW_WALLET_SEED], program_id)?;
+    validation::assert_pda(
+        deposit_record,
+        &[
+            constants::DEPOSIT_SEED,
+            registry_account.key.as_ref(),
+            mint_account.key.as_ref(),
+            depositor.key.as_ref(),
+        ],
+        program_id,
+    )?;
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
 
-    let (pda, bump_seed) = Pubkey::find_program_address(&[constants::ESCROW_SEED], program_id);
-    msg!("Unpacking Escrow!");
-    let escrow_info = EscrowState::unpack_from_slice(&escrow_account.try_borrow_data()?)?;
-    let current_timestamp = Clock::get().unwrap().unix_timestamp;
-    let receiver_token_account_info =
-        TokenAccount::unpack(&receiver_token_account.try_borrow_data()?)?;
+    verbose!("Unpacking!");
+    let registry_owner =
+        PodEscrowRegistryState::load(&registry_account.try_borrow_data()?)?.initializer_pubkey;
+    let mut registry_mint_state = load_registry_mint(
+        registry_mint,
+        registry_account,
+        mint_account,
+        vault_account,
+        program_id,
+    )?;
+    let mut record = DepositRecordState::unpack(&deposit_record.try_borrow_data()?)?;
 
-    msg!("Running Checks!");
-    if current_timestamp < escrow_info.expire_date {
-        return Err(EscrowError::EscrowNotMaturedYet.into());
+    verbose!("Running Checks!");
+    if registry_owner != *owner.key {
+        return Err(EscrowError::RegistryOwnerMismatch.into());
     }
-
-    msg!("Running Checks: Receiver PubKey!");
-    if escrow_info.receiver_pubkey != receiver_token_account_info.owner {
-        return Err(ProgramError::InvalidAccountData);
+    if record.registry_pubkey != *registry_account.key
+        || record.depositor_pubkey != *depositor.key
+    {
+        return Err(EscrowError::DepositRecordMismatch.into());
     }
-
-    msg!("Running Checks: Pda Token PubKey!");
-    if escrow_info.temp_token_account_pubkey != *pdas_temp_token_account.key {
-        return Err(ProgramError::InvalidAccountData);
+    if record.mint_pubkey != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
     }
-
-    msg!("Running Checks: Initializer PubKey!");
-    if escrow_info.initializer_pubkey != *initializers_main_account.key {
-        return Err(ProgramError::InvalidAccountData);
+    if !depositor.is_signer && validation::clock()?.unix_timestamp < record.expire_date {
+        return Err(EscrowError::EscrowNotMaturedYet.into());
+    }
+    let amount = record.amount;
+    if amount == 0 {
+        return Err(EscrowError::InsufficientFunds.into());
     }
 
-    msg!("Transfer Tokens!");
-    let amount = escrow_info.escrow_amount;
-    let account_infos = &[
-        pdas_temp_token_account.clone(),
-        receiver_token_account.clone(),
-        pda_account.clone(),
-        token_program.clone(),
-    ];
-
-    let _ = token_lib::transfer_tokens(
+    token_lib::transfer_tokens(
         token_program.clone(),
-        pdas_temp_token_account.clone(),
+        vault_account.clone(),
+        mint_account.clone(),
         receiver_token_account.clone(),
-        pda,
+        pda_account.clone(),
+        transfer_hook_accounts,
         amount,
-        account_infos,
-        bump_seed,
-    );
-
-    msg!("Closing Token Account!");
-    let close_pdas_temp_acc_ix = spl_token::instruction::close_account(
-        token_program.key,
-        pdas_temp_token_account.key,
-        initializers_main_account.key,
-        &pda,
-        &[&pda],
-    )?;
-    msg!("Calling the token program to close pda's temp account...");
-    invoke_signed(
-        &close_pdas_temp_acc_ix,
-        &[
-            pdas_temp_token_account.clone(),
-            initializers_main_account.clone(),
-            pda_account.clone(),
-            token_program.clone(),
-        ],
-        &[&[&constants::ESCROW_SEED[..], &[bump_seed]]],
+        decimals,
+        &[constants::ESCROW_WALLET_SEED, &[bump_seed]],
     )?;
 
-    msg!("Closing the escrow account...");
-    **initializers_main_account.lamports.borrow_mut() = initializers_main_account
-        .lamports()
-        .checked_add(escrow_account.lamports())
+    record.amount = 0;
+    registry_mint_state.total_deposits = registry_mint_state
+        .total_deposits
+        .checked_sub(amount)
         .ok_or(EscrowError::AmountOverflow)?;
-    **escrow_account.lamports.borrow_mut() = 0;
-    *escrow_account.try_borrow_mut_data()? = &mut [];
+    token_lib::check_vault_balance(token_program, vault_account, registry_mint_state.total_deposits)?;
+
+    verbose!("Packing!");
+    DepositRecordState::pack(record, &mut deposit_record.try_borrow_mut_data()?)?;
+    RegistryMintState::pack(registry_mint_state, &mut registry_mint.try_borrow_mut_data()?)?;
+
+    events::emit(EscrowEvent::DepositCollected(DepositCollected {
+        escrow: *registry_account.key,
+        receiver: *owner.key,
+        amount,
+        mint: *mint_account.key,
+        depositor: *depositor.key,
+    }))?;
 
-    msg!("Escrow released successfully");
+    verbose!("Deposit collected successfully");
     Ok(())
 }
@@ -0,0 +1,196 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    sysvar::{rent::Rent, Sysvar},
+};
+
+use crate::{
+    events::{self, DepositMade, EscrowEvent},
+    states::{
+        deposit_record::DepositRecordState, escrow_registry::PodEscrowRegistryState,
+        layout::ZeroCopy, registry_mint::RegistryMintState,
+    },
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, lamport_lib, logging::verbose,
+        token_lib, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `Deposit`.
+    pub struct DepositAccounts / DepositKeys {
+        /// The depositor, it also pays for its deposit record
+        depositor: signer = true, writable = true, owner = Any;
+        /// The depositor's token account to take the deposit from
+        depositor_token_account: signer = false, writable = true, owner = Token;
+        /// The vault of the mint
+        vault_account: signer = false, writable = true, owner = Token;
+        /// The registry account
+        registry_account: signer = false, writable = false, owner = Program;
+        /// The registry mint PDA of the mint
+        registry_mint: signer = false, writable = true, owner = Program;
+        /// The deposit record PDA of the depositor, created on the first deposit
+        deposit_record: signer = false, writable = true, owner = Any;
+        /// The mint of the deposit
+        mint_account: signer = false, writable = false, owner = Token;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The system program
+        system_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/// Loads the registry mint of `mint_account` and checks `vault_account` is
+/// its vault.
+pub(crate) fn load_registry_mint(
+    registry_mint: &AccountInfo,
+    registry_account: &AccountInfo,
+    mint_account: &AccountInfo,
+    vault_account: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<RegistryMintState, ProgramError> {
+    validation::assert_pda(
+        registry_mint,
+        &[constants::REGISTRY_MINT_SEED, registry_account.key.as_ref(), mint_account.key.as_ref()],
+        program_id,
+    )?;
+    let state = RegistryMintState::unpack(&registry_mint.try_borrow_data()?)?;
+    if state.vault_pubkey != *vault_account.key {
+        return Err(EscrowError::VaultMismatch.into());
+    }
+    Ok(state)
+}
+
+/// Deposit into a Wallet Escrow
+///
+/// Credits the depositor's record with what the vault actually received, net
+/// of any transfer fee, and restarts the record's wait time. A matured record
+/// still holding a balance takes no top-ups: the restarted wait would let the
+/// depositor withdraw what the registry owner can already collect.
+///
+/// Accounts expected: see [`DepositAccounts`], followed by any extra
+/// accounts required by the mint's transfer hook.
+pub fn handler(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    verbose!("Deposit starting!");
+    let DepositAccounts {
+        depositor,
+        depositor_token_account,
+        vault_account,
+        registry_account,
+        registry_mint,
+        deposit_record,
+        mint_account,
+        rent_account,
+        token_program,
+        system_program,
+        remaining: transfer_hook_accounts,
+    } = DepositAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_token_program(token_program)?;
+    validation::assert_system_program(system_program)?;
+    if amount == 0 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    verbose!("Unpacking Registry!");
+    PodEscrowRegistryState::load(&registry_account.try_borrow_data()?)?;
+    let mut registry_mint_state = load_registry_mint(
+        registry_mint,
+        registry_account,
+        mint_account,
+        vault_account,
+        program_id,
+    )?;
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
+    let vault_before = token_lib::unpack_token_account(token_program, vault_account)?;
+    if vault_before.mint != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
+    }
+
+    let record_bump = validation::assert_pda(
+        deposit_record,
+        &[
+            constants::DEPOSIT_SEED,
+            registry_account.key.as_ref(),
+            mint_account.key.as_ref(),
+            depositor.key.as_ref(),
+        ],
+        program_id,
+    )?;
+    if deposit_record.data_is_empty() {
+        let rent = Rent::from_account_info(rent_account)?;
+        lamport_lib::create_pda_account(
+            depositor.clone(),
+            deposit_record.clone(),
+            system_program.clone(),
+            rent.minimum_balance(DepositRecordState::LEN),
+            DepositRecordState::LEN,
+            program_id,
+            &[
+                constants::DEPOSIT_SEED,
+                registry_account.key.as_ref(),
+                mint_account.key.as_ref(),
+                depositor.key.as_ref(),
+                &[record_bump],
+            ],
+        )?;
+    }
+    validation::assert_owned_by(deposit_record, program_id)?;
+
+    verbose!("Unpacking Deposit Record!");
+    let mut record = DepositRecordState::unpack_unchecked(&deposit_record.try_borrow_data()?)?;
+    if !record.is_initialized() {
+        record.is_initialized = true;
+        record.registry_pubkey = *registry_account.key;
+        record.mint_pubkey = *mint_account.key;
+        record.depositor_pubkey = *depositor.key;
+    }
+    let now = validation::clock()?.unix_timestamp;
+    if record.amount > 0 && now >= record.expire_date {
+        return Err(EscrowError::EscrowAlreadyMatured.into());
+    }
+
+    token_lib::transfer_tokens(
+        token_program.clone(),
+        depositor_token_account.clone(),
+        mint_account.clone(),
+        vault_account.clone(),
+        depositor.clone(),
+        transfer_hook_accounts,
+        amount,
+        decimals,
+        &[],
+    )?;
+    let credited = token_lib::unpack_token_account(token_program, vault_account)?
+        .amount
+        .checked_sub(vault_before.amount)
+        .ok_or(EscrowError::AmountOverflow)?;
+
+    record.amount = record.amount.checked_add(credited).ok_or(EscrowError::AmountOverflow)?;
+    record.expire_date = now + constants::ESCROW_WAIT_TIME_SEC;
+    registry_mint_state.total_deposits = registry_mint_state
+        .total_deposits
+        .checked_add(credited)
+        .ok_or(EscrowError::AmountOverflow)?;
+    token_lib::check_vault_balance(token_program, vault_account, registry_mint_state.total_deposits)?;
+
+    verbose!("Packing!");
+    DepositRecordState::pack(record, &mut deposit_record.try_borrow_mut_data()?)?;
+    RegistryMintState::pack(registry_mint_state, &mut registry_mint.try_borrow_mut_data()?)?;
+
+    events::emit(EscrowEvent::DepositMade(DepositMade {
+        registry: *registry_account.key,
+        mint: *mint_account.key,
+        depositor: *depositor.key,
+        amount: credited,
+    }))?;
+
+    verbose!("Deposit made successfully");
+    Ok(())
+}
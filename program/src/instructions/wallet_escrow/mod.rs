@@ -1,2 +1,6 @@
 pub mod register_escrow;
 pub mod collect_deposit;
+pub mod deposit;
+pub mod withdraw;
+pub mod add_mint;
+pub mod remove_mint;
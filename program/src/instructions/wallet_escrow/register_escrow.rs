@@ -1,74 +1,81 @@
 use solana_program::{
-    account_info::next_account_info,
     account_info::AccountInfo,
     entrypoint::ProgramResult,
-    msg,
-    program::invoke,
-    program_error::ProgramError,
-    program_pack::{IsInitialized, Pack},
     pubkey::Pubkey,
     sysvar::{rent::Rent, Sysvar},
 };
 
 use crate::{
-    states::escrow_registry::EscrowRegistryState,
-    utils::{self, errors::EscrowError},
+    states::{escrow_registry::PodEscrowRegistryState, layout::ZeroCopy},
+    utils::{
+        self, accounts::instruction_accounts, errors::EscrowError, logging::verbose, token_lib,
+        validation,
+    },
 };
+use spl_token_2022::instruction::AuthorityType;
 
-/** Initialize Escrow
-**/
-///
-/// Accounts expected:
+instruction_accounts! {
+    /// Accounts expected by `RegisterEscrow`.
+    pub struct RegisterEscrowAccounts / RegisterEscrowKeys {
+        /// The account of the person initializing the escrow
+        initializer: signer = true, writable = false, owner = Any;
+        /// Token account that should be created prior to this instruction and owned by the initializer
+        temp_token_account: signer = false, writable = true, owner = Token;
+        /// The escrow account, it will hold all necessary info about the trade.
+        escrow_account: signer = false, writable = true, owner = Program;
+        /// The rent sysvar
+        rent_account: signer = false, writable = false, owner = Any;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+        /// The mint of the registered token account
+        mint_account: signer = false, writable = false, owner = Token;
+    }
+}
+
+/// Initialize Escrow
 ///
-/// 0. `[signer]` The account of the person initializing the escrow
-/// 1. `[writable]` Token account that should be created prior to this instruction and owned by the initializer
-/// 2. `[writable]` The escrow account, it will hold all necessary info about the trade.
-/// 3. `[]` The rent sysvar
-/// 4. `[]` The token program
+/// Accounts expected: see [`RegisterEscrowAccounts`].
 pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
-    msg!("Escrow starting!");
-    let account_info_iter = &mut accounts.iter();
-    let initializer = next_account_info(account_info_iter)?;
-    let temp_token_account = next_account_info(account_info_iter)?;
-    let escrow_account = next_account_info(account_info_iter)?;
-    let rent = &Rent::from_account_info(next_account_info(account_info_iter)?)?;
-    let token_program = next_account_info(account_info_iter)?;
+    verbose!("Escrow starting!");
+    let RegisterEscrowAccounts {
+        initializer,
+        temp_token_account,
+        escrow_account,
+        rent_account,
+        token_program,
+        mint_account,
+        ..
+    } = RegisterEscrowAccounts::try_from_accounts(accounts, program_id)?;
 
-    if !initializer.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    validation::assert_rent_sysvar(rent_account)?;
+    validation::assert_token_program(token_program)?;
 
+    let rent = &Rent::from_account_info(rent_account)?;
     if !rent.is_exempt(escrow_account.lamports(), escrow_account.data_len()) {
         return Err(EscrowError::NotRentExempt.into());
     }
 
-    msg!("Escrow unpacking!");
-    let mut escrow_wallet_registry = EscrowRegistryState::unpack_from_slice(&escrow_account.try_borrow_data()?)?;
-    if escrow_wallet_registry.is_initialized() {
-        return Err(ProgramError::AccountAlreadyInitialized);
+    token_lib::check_mint(token_program, mint_account)?;
+    if token_lib::unpack_token_account(token_program, temp_token_account)?.mint != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
     }
 
-    escrow_wallet_registry.is_initialized = true;
+    verbose!("Escrow initializing!");
+    let mut escrow_data = escrow_account.try_borrow_mut_data()?;
+    let escrow_wallet_registry = PodEscrowRegistryState::initialize(&mut escrow_data)?;
     escrow_wallet_registry.initializer_pubkey = *initializer.key;
     escrow_wallet_registry.token_account_pubkey = *temp_token_account.key;
-
-    msg!("Escrow packing!");
-    EscrowRegistryState::pack(escrow_wallet_registry, &mut escrow_account.try_borrow_mut_data()?)?;
+    escrow_wallet_registry.set_mint_count(0);
+    drop(escrow_data);
     let (pda, _bump_seed) =
         Pubkey::find_program_address(&[utils::constants::ESCROW_WALLET_SEED], program_id);
 
-    let owner_change_ix = spl_token::instruction::set_authority(
-        token_program.key,
-        temp_token_account.key,
-        Some(&pda),
-        spl_token::instruction::AuthorityType::AccountOwner,
-        initializer.key,
-        &[&initializer.key],
-    )?;
-
-    msg!("Calling the token program to transfer token account ownership...");
-    invoke(
-        &owner_change_ix,
+    token_lib::change_account_authority(
+        token_program.clone(),
+        temp_token_account.clone(),
+        pda,
+        AuthorityType::AccountOwner,
+        initializer.clone(),
         &[
             temp_token_account.clone(),
             initializer.clone(),
@@ -76,6 +83,6 @@ pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
         ],
     )?;
 
-    msg!("Escrow have been registered successfully");
+    verbose!("Escrow have been registered successfully");
     Ok(())
 }
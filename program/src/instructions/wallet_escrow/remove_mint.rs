@@ -0,0 +1,134 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, EscrowEvent, MintRemoved},
+    states::{
+        escrow_registry::PodEscrowRegistryState, layout::ZeroCopy, registry_mint::RegistryMintState,
+    },
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, lamport_lib, logging::verbose,
+        token_lib, validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `RemoveMint`.
+    pub struct RemoveMintAccounts / RemoveMintKeys {
+        /// The registry owner, it receives the rent of the closed accounts
+        owner: signer = true, writable = true, owner = Any;
+        /// The token account to sweep tokens sent to the vault outside of deposits to
+        receiver_token_account: signer = false, writable = true, owner = Token;
+        /// The registry account
+        registry_account: signer = false, writable = true, owner = Program;
+        /// The registry mint PDA to close
+        registry_mint: signer = false, writable = true, owner = Program;
+        /// The vault of the mint, closed as well
+        vault_account: signer = false, writable = true, owner = Token;
+        /// The mint to stop accepting
+        mint_account: signer = false, writable = true, owner = Token;
+        /// The wallet escrow PDA, authority of every vault
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/// Remove a Mint from a Wallet Escrow
+///
+/// Only mints without outstanding deposits can be removed.
+///
+/// Accounts expected: see [`RemoveMintAccounts`], followed by any extra
+/// accounts required by the mint's transfer hook.
+pub fn handler(accounts: &[AccountInfo], program_id: &Pubkey) -> ProgramResult {
+    verbose!("Remove Mint starting!");
+    let RemoveMintAccounts {
+        owner,
+        receiver_token_account,
+        registry_account,
+        registry_mint,
+        vault_account,
+        mint_account,
+        pda_account,
+        token_program,
+        remaining: transfer_hook_accounts,
+    } = RemoveMintAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_token_program(token_program)?;
+    let bump_seed = validation::assert_pda(pda_account, &[constants::ESCROW_WALLET_SEED], program_id)?;
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
+
+    verbose!("Unpacking!");
+    let registry_data = registry_account.try_borrow_data()?;
+    let registry = PodEscrowRegistryState::load(&registry_data)?;
+    let state = RegistryMintState::unpack(&registry_mint.try_borrow_data()?)?;
+
+    verbose!("Running Checks!");
+    if registry.initializer_pubkey != *owner.key {
+        return Err(EscrowError::RegistryOwnerMismatch.into());
+    }
+    drop(registry_data);
+    if state.registry_pubkey != *registry_account.key {
+        return Err(EscrowError::DepositRecordMismatch.into());
+    }
+    if state.mint_pubkey != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
+    }
+    if state.vault_pubkey != *vault_account.key {
+        return Err(EscrowError::VaultMismatch.into());
+    }
+    if state.total_deposits != 0 {
+        return Err(EscrowError::OutstandingDeposits.into());
+    }
+
+    let signer_seeds: &[&[u8]] = &[constants::ESCROW_WALLET_SEED, &[bump_seed]];
+    let surplus = token_lib::unpack_token_account(token_program, vault_account)?.amount;
+    if surplus > 0 {
+        verbose!("Sweeping the vault!");
+        token_lib::transfer_tokens(
+            token_program.clone(),
+            vault_account.clone(),
+            mint_account.clone(),
+            receiver_token_account.clone(),
+            pda_account.clone(),
+            transfer_hook_accounts,
+            surplus,
+            decimals,
+            signer_seeds,
+        )?;
+    }
+
+    verbose!("Closing the vault!");
+    if *token_program.key == spl_token_2022::id() {
+        token_lib::harvest_withheld_fees(
+            token_program.clone(),
+            mint_account.clone(),
+            vault_account.clone(),
+        )?;
+    }
+    token_lib::close_token_account(
+        token_program.clone(),
+        vault_account.clone(),
+        owner.clone(),
+        pda_account.clone(),
+        signer_seeds,
+    )?;
+    lamport_lib::close_account(registry_mint, owner)?;
+
+    let mut registry_data = registry_account.try_borrow_mut_data()?;
+    let registry = PodEscrowRegistryState::load_mut(&mut registry_data)?;
+    registry.set_mint_count(registry.mint_count().saturating_sub(1));
+    drop(registry_data);
+
+    events::emit(EscrowEvent::MintRemoved(MintRemoved {
+        registry: *registry_account.key,
+        mint: *mint_account.key,
+    }))?;
+
+    verbose!("Mint removed successfully");
+    Ok(())
+}
@@ -0,0 +1,140 @@
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    events::{self, DepositWithdrawn, EscrowEvent},
+    states::{
+        deposit_record::DepositRecordState, escrow_registry::PodEscrowRegistryState,
+        layout::ZeroCopy, registry_mint::RegistryMintState,
+    },
+    instructions::wallet_escrow::deposit::load_registry_mint,
+    utils::{
+        accounts::instruction_accounts, constants, errors::EscrowError, logging::verbose, token_lib,
+        validation,
+    },
+};
+
+instruction_accounts! {
+    /// Accounts expected by `Withdraw`.
+    pub struct WithdrawAccounts / WithdrawKeys {
+        /// The depositor
+        depositor: signer = true, writable = false, owner = Any;
+        /// The token account to send the withdrawn tokens to
+        depositor_token_account: signer = false, writable = true, owner = Token;
+        /// The vault of the mint
+        vault_account: signer = false, writable = true, owner = Token;
+        /// The registry account
+        registry_account: signer = false, writable = false, owner = Program;
+        /// The registry mint PDA of the mint
+        registry_mint: signer = false, writable = true, owner = Program;
+        /// The deposit record PDA of the depositor
+        deposit_record: signer = false, writable = true, owner = Program;
+        /// The wallet escrow PDA, authority of the vault
+        pda_account: signer = false, writable = false, owner = Any;
+        /// The mint of the deposit
+        mint_account: signer = false, writable = false, owner = Token;
+        /// The token program, SPL Token or Token-2022
+        token_program: signer = false, writable = false, owner = Any;
+    }
+}
+
+/// Withdraw from a Wallet Escrow
+///
+/// Depositors can take their funds back until the record matures, after
+/// that they belong to the registry owner.
+///
+/// Accounts expected: see [`WithdrawAccounts`], followed by any extra
+/// accounts required by the mint's transfer hook.
+pub fn handler(accounts: &[AccountInfo], amount: u64, program_id: &Pubkey) -> ProgramResult {
+    verbose!("Withdraw starting!");
+    let WithdrawAccounts {
+        depositor,
+        depositor_token_account,
+        vault_account,
+        registry_account,
+        registry_mint,
+        deposit_record,
+        pda_account,
+        mint_account,
+        token_program,
+        remaining: transfer_hook_accounts,
+    } = WithdrawAccounts::try_from_accounts(accounts, program_id)?;
+
+    validation::assert_token_program(token_program)?;
+    let bump_seed = validation::assert_pda(pda_account, &[constants::ESCROW_WALLET_SEED], program_id)?;
+    validation::assert_pda(
+        deposit_record,
+        &[
+            constants::DEPOSIT_SEED,
+            registry_account.key.as_ref(),
+            mint_account.key.as_ref(),
+            depositor.key.as_ref(),
+        ],
+        program_id,
+    )?;
+    let decimals = token_lib::check_mint(token_program, mint_account)?;
+
+    verbose!("Unpacking!");
+    PodEscrowRegistryState::load(&registry_account.try_borrow_data()?)?;
+    let mut registry_mint_state = load_registry_mint(
+        registry_mint,
+        registry_account,
+        mint_account,
+        vault_account,
+        program_id,
+    )?;
+    let mut record = DepositRecordState::unpack(&deposit_record.try_borrow_data()?)?;
+
+    verbose!("Running Checks!");
+    if record.registry_pubkey != *registry_account.key
+        || record.depositor_pubkey != *depositor.key
+    {
+        return Err(EscrowError::DepositRecordMismatch.into());
+    }
+    if record.mint_pubkey != *mint_account.key {
+        return Err(EscrowError::MintMismatch.into());
+    }
+    if validation::clock()?.unix_timestamp >= record.expire_date {
+        return Err(EscrowError::EscrowAlreadyMatured.into());
+    }
+    if amount == 0 || amount > record.amount {
+        return Err(EscrowError::InsufficientFunds.into());
+    }
+
+    token_lib::transfer_tokens(
+        token_program.clone(),
+        vault_account.clone(),
+        mint_account.clone(),
+        depositor_token_account.clone(),
+        pda_account.clone(),
+        transfer_hook_accounts,
+        amount,
+        decimals,
+        &[constants::ESCROW_WALLET_SEED, &[bump_seed]],
+    )?;
+
+    record.amount -= amount;
+    registry_mint_state.total_deposits = registry_mint_state
+        .total_deposits
+        .checked_sub(amount)
+        .ok_or(EscrowError::AmountOverflow)?;
+    token_lib::check_vault_balance(token_program, vault_account, registry_mint_state.total_deposits)?;
+
+    verbose!("Packing!");
+    DepositRecordState::pack(record, &mut deposit_record.try_borrow_mut_data()?)?;
+    RegistryMintState::pack(registry_mint_state, &mut registry_mint.try_borrow_mut_data()?)?;
+
+    events::emit(EscrowEvent::DepositWithdrawn(DepositWithdrawn {
+        registry: *registry_account.key,
+        mint: *mint_account.key,
+        depositor: *depositor.key,
+        amount,
+    }))?;
+
+    verbose!("Withdrawn successfully");
+    Ok(())
+}
@@ -2,14 +2,19 @@ pub mod instructions;
 pub mod utils;
 pub mod states;
 pub mod route;
+pub mod instruction;
+pub mod events;
+#[cfg(not(target_os = "solana"))]
+pub mod decoder;
 
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, msg, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey,
 };
 use crate::route::EscrowRoutes;
-use crate::instructions::default_escrow;
+use crate::instructions::{admin, default_escrow, sol_escrow, wallet_escrow};
 
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 pub fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -18,21 +23,85 @@ pub fn process_instruction(
     let instruction = EscrowRoutes::unpack(instruction_data)?;
 
     match instruction {
-        EscrowRoutes::InitEscrow { amount } => {
+        EscrowRoutes::InitEscrow { amount, time_basis, memo } => {
             msg!("Instruction: Init Escrow");
-            let _ = default_escrow::init_escrow::handler(accounts, amount, program_id);
+            default_escrow::init_escrow::handler(accounts, amount, time_basis, memo, program_id)?;
         }
         EscrowRoutes::ReleaseEscrow => {
             msg!("Instruction: Release Escrow");
-            let _ = default_escrow::release_escrow::handler(accounts, program_id);
+            default_escrow::release_escrow::handler(accounts, program_id)?;
         }
         EscrowRoutes::CollectDeposit => {
-            msg!("Instruction: Oracle Call");
-            let _ = default_escrow::oracle_call::handler(accounts, program_id);
+            msg!("Instruction: Collect Deposit");
+            wallet_escrow::collect_deposit::handler(accounts, program_id)?;
         }
         EscrowRoutes::Oracle => {
             msg!("Instruction: Oracle Call");
-            let _ = default_escrow::oracle_call::handler(accounts, program_id);
+            default_escrow::oracle_call::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::InitSolEscrow { amount } => {
+            msg!("Instruction: Init Sol Escrow");
+            sol_escrow::init_sol_escrow::handler(accounts, amount, program_id)?;
+        }
+        EscrowRoutes::ReleaseSolEscrow => {
+            msg!("Instruction: Release Sol Escrow");
+            sol_escrow::release_sol_escrow::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::RefundSolEscrow => {
+            msg!("Instruction: Refund Sol Escrow");
+            sol_escrow::refund_sol_escrow::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::RegisterEscrow => {
+            msg!("Instruction: Register Escrow");
+            wallet_escrow::register_escrow::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::MigrateAccount => {
+            msg!("Instruction: Migrate Account");
+            admin::migrate_account::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::Deposit { amount } => {
+            msg!("Instruction: Deposit");
+            wallet_escrow::deposit::handler(accounts, amount, program_id)?;
+        }
+        EscrowRoutes::Withdraw { amount } => {
+            msg!("Instruction: Withdraw");
+            wallet_escrow::withdraw::handler(accounts, amount, program_id)?;
+        }
+        EscrowRoutes::AddMint => {
+            msg!("Instruction: Add Mint");
+            wallet_escrow::add_mint::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::RemoveMint => {
+            msg!("Instruction: Remove Mint");
+            wallet_escrow::remove_mint::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::ReleaseWithVoucher { voucher } => {
+            msg!("Instruction: Release With Voucher");
+            default_escrow::release_escrow::voucher_handler(accounts, voucher, program_id)?;
+        }
+        EscrowRoutes::IncreaseDeposit { amount } => {
+            msg!("Instruction: Increase Deposit");
+            default_escrow::increase_deposit::handler(accounts, amount, program_id)?;
+        }
+        EscrowRoutes::AmendEscrow {
+            amount,
+            expire_date,
+            receiver,
+        } => {
+            msg!("Instruction: Amend Escrow");
+            default_escrow::amend_escrow::handler(accounts, amount, expire_date, receiver, program_id)?;
+        }
+        EscrowRoutes::AssignReceiver { new_receiver } => {
+            msg!("Instruction: Assign Receiver");
+            default_escrow::assign_receiver::handler(accounts, new_receiver, program_id)?;
+        }
+        EscrowRoutes::RefundEscrow => {
+            msg!("Instruction: Refund Escrow");
+            default_escrow::refund_escrow::handler(accounts, program_id)?;
+        }
+        EscrowRoutes::SetConfig { max_open_escrows_per_user } => {
+            msg!("Instruction: Set Config");
+            admin::set_config::handler(accounts, max_open_escrows_per_user, program_id)?;
         }
     }
 
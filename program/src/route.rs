@@ -1,11 +1,32 @@
-use solana_program::program_error::ProgramError;
-use crate::utils::errors::EscrowError::InvalidInstruction;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use crate::utils::{
+    errors::EscrowError::InvalidInstruction, memo::EscrowMemo, time_basis::TimeBasis,
+    voucher::Voucher,
+};
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum EscrowRoutes {
-    InitEscrow { amount: u64 },
+    /// `time_basis` is an optional trailing byte, unix seconds when absent.
+    /// `memo` optionally follows it, empty when absent.
+    InitEscrow { amount: u64, time_basis: TimeBasis, memo: EscrowMemo },
     ReleaseEscrow,
     CollectDeposit,
     Oracle,
+    InitSolEscrow { amount: u64 },
+    ReleaseSolEscrow,
+    RefundSolEscrow,
+    RegisterEscrow,
+    MigrateAccount,
+    Deposit { amount: u64 },
+    Withdraw { amount: u64 },
+    AddMint,
+    RemoveMint,
+    ReleaseWithVoucher { voucher: Voucher },
+    IncreaseDeposit { amount: u64 },
+    AmendEscrow { amount: u64, expire_date: i64, receiver: Pubkey },
+    AssignReceiver { new_receiver: Pubkey },
+    RefundEscrow,
+    SetConfig { max_open_escrows_per_user: u64 },
 }
 
 impl EscrowRoutes {
@@ -15,19 +36,133 @@ impl EscrowRoutes {
         Ok(match tag {
             0 => Self::InitEscrow {
                 amount: Self::unpack_amount(rest)?,
+                time_basis: match rest.get(8) {
+                    None => TimeBasis::UnixTimestamp,
+                    Some(&time_basis) => TimeBasis::from_u8(time_basis).ok_or(InvalidInstruction)?,
+                },
+                memo: match rest.get(9..) {
+                    None | Some([]) => EscrowMemo::default(),
+                    Some(memo) => EscrowMemo::unpack(memo)?,
+                },
             },
             1 => Self::ReleaseEscrow,
             2 => Self::CollectDeposit,
             3 => Self::Oracle,
+            4 => Self::InitSolEscrow {
+                amount: Self::unpack_amount(rest)?,
+            },
+            5 => Self::ReleaseSolEscrow,
+            6 => Self::RefundSolEscrow,
+            7 => Self::RegisterEscrow,
+            8 => Self::MigrateAccount,
+            9 => Self::Deposit {
+                amount: Self::unpack_amount(rest)?,
+            },
+            10 => Self::Withdraw {
+                amount: Self::unpack_amount(rest)?,
+            },
+            11 => Self::AddMint,
+            12 => Self::RemoveMint,
+            13 => Self::ReleaseWithVoucher {
+                voucher: Voucher::unpack(rest)?,
+            },
+            14 => Self::IncreaseDeposit {
+                amount: Self::unpack_amount(rest)?,
+            },
+            15 => Self::AmendEscrow {
+                amount: Self::unpack_amount(rest)?,
+                expire_date: Self::unpack_amount(rest.get(8..).ok_or(InvalidInstruction)?)? as i64,
+                receiver: Self::unpack_pubkey(rest.get(16..).ok_or(InvalidInstruction)?)?,
+            },
+            16 => Self::AssignReceiver {
+                new_receiver: Self::unpack_pubkey(rest)?,
+            },
+            17 => Self::RefundEscrow,
+            18 => Self::SetConfig {
+                max_open_escrows_per_user: Self::unpack_amount(rest)?,
+            },
             _ => return Err(InvalidInstruction.into()),
         })
     }
 
-    fn unpack_amount(input: &[u8]) -> Result<u64, ProgramError> {
-        if input.len() == 1 {
-            Ok(u64::from(input[0]))
-        } else {
-            Err(InvalidInstruction.into())
+    pub fn pack(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(1 + 8);
+        match self {
+            Self::InitEscrow { amount, time_basis, memo } => {
+                buf.push(0);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                if *time_basis != TimeBasis::UnixTimestamp || !memo.is_empty() {
+                    buf.push(*time_basis as u8);
+                }
+                if !memo.is_empty() {
+                    buf.extend_from_slice(&memo.pack());
+                }
+            }
+            Self::ReleaseEscrow => buf.push(1),
+            Self::CollectDeposit => buf.push(2),
+            Self::Oracle => buf.push(3),
+            Self::InitSolEscrow { amount } => {
+                buf.push(4);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::ReleaseSolEscrow => buf.push(5),
+            Self::RefundSolEscrow => buf.push(6),
+            Self::RegisterEscrow => buf.push(7),
+            Self::MigrateAccount => buf.push(8),
+            Self::Deposit { amount } => {
+                buf.push(9);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::Withdraw { amount } => {
+                buf.push(10);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::AddMint => buf.push(11),
+            Self::RemoveMint => buf.push(12),
+            Self::ReleaseWithVoucher { voucher } => {
+                buf.push(13);
+                buf.extend_from_slice(&voucher.pack());
+            }
+            Self::IncreaseDeposit { amount } => {
+                buf.push(14);
+                buf.extend_from_slice(&amount.to_le_bytes());
+            }
+            Self::AmendEscrow {
+                amount,
+                expire_date,
+                receiver,
+            } => {
+                buf.push(15);
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&expire_date.to_le_bytes());
+                buf.extend_from_slice(receiver.as_ref());
+            }
+            Self::AssignReceiver { new_receiver } => {
+                buf.push(16);
+                buf.extend_from_slice(new_receiver.as_ref());
+            }
+            Self::RefundEscrow => buf.push(17),
+            Self::SetConfig { max_open_escrows_per_user } => {
+                buf.push(18);
+                buf.extend_from_slice(&max_open_escrows_per_user.to_le_bytes());
+            }
         }
+        buf
+    }
+
+    fn unpack_amount(input: &[u8]) -> Result<u64, ProgramError> {
+        input
+            .get(..8)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or_else(|| InvalidInstruction.into())
+    }
+
+    fn unpack_pubkey(input: &[u8]) -> Result<Pubkey, ProgramError> {
+        input
+            .get(..32)
+            .and_then(|slice| slice.try_into().ok())
+            .map(Pubkey::new_from_array)
+            .ok_or_else(|| InvalidInstruction.into())
     }
 }
@@ -5,20 +5,214 @@ use solana_program::{
 };
 
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
-use crate::utils::constants::DEFAULT_ESCROW_STATE_LEN;
+use bytemuck::{Pod, Zeroable};
+use crate::utils::constants::{
+    DEFAULT_ESCROW_STATE_LEN, LEGACY_DEFAULT_ESCROW_STATE_LEN, MAX_MEMO_LEN, REFERENCE_LEN,
+};
+use crate::utils::errors::EscrowError;
+use crate::utils::memo::{reference_from_bytes, EscrowMemo};
+use crate::utils::time_basis::TimeBasis;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+    PodI64, PodU64, ZeroCopy, HEADER_LEN,
+};
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EscrowState {
     pub is_initialized: bool,
     pub caller_pubkey: Pubkey,
     pub initializer_pubkey: Pubkey,
+    /// Holder of the claim on the escrow, reassigned by `AssignReceiver`.
     pub receiver_pubkey: Pubkey,
     pub temp_token_account_pubkey: Pubkey,
     pub escrow_amount: u64,
+    /// Maturity, the start of the release window, in unix seconds, slots or
+    /// epochs depending on `time_basis`.
     pub expire_date: i64,
+    /// Nonce of the last release voucher used, vouchers must go above it.
+    pub voucher_nonce: u64,
+    pub time_basis: TimeBasis,
+    /// End of the release window, in the same basis as `expire_date`. From
+    /// then on the escrow can only be refunded to the initializer. Zero on
+    /// escrows created before the window existed, which never close.
+    pub refund_after: i64,
+    /// Order ID or hash the escrow is linked to.
+    pub reference: Option<[u8; REFERENCE_LEN]>,
+    /// Whether init and release log the reference and memo through SPL Memo.
+    pub log_memo: bool,
+    /// UTF-8 text of at most `MAX_MEMO_LEN` bytes.
+    pub memo: String,
+    /// Position of the escrow among its initializer's escrows, counted by
+    /// the `UserProfile` from 1 and part of the escrow's address. Zero on
+    /// escrows created before profiles existed, which no profile counts.
+    pub escrow_index: u64,
+}
+
+impl EscrowState {
+    pub fn escrow_memo(&self) -> EscrowMemo {
+        EscrowMemo {
+            reference: self.reference,
+            memo: self.memo.clone(),
+            log_memo: self.log_memo,
+        }
+    }
+
+    /// Whether the release window has closed at `now`.
+    pub fn refund_due(&self, now: i64) -> bool {
+        self.refund_after != 0 && now >= self.refund_after
+    }
 }
 
 impl Sealed for EscrowState {}
 
+impl AccountLayout for EscrowState {
+    const NAME: &'static str = "escrow";
+    const DISCRIMINATOR: u8 = 1;
+    const VERSION: u8 = 6;
+    const LEGACY_LEN: Option<usize> = Some(LEGACY_DEFAULT_ESCROW_STATE_LEN);
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("caller_pubkey", FieldType::Pubkey),
+        field("initializer_pubkey", FieldType::Pubkey),
+        field("receiver_pubkey", FieldType::Pubkey),
+        field("temp_token_account_pubkey", FieldType::Pubkey),
+        field("escrow_amount", FieldType::U64),
+        field("expire_date", FieldType::Maturity),
+        field("voucher_nonce", FieldType::U64),
+        field("time_basis", FieldType::TimeBasis),
+        field("refund_after", FieldType::Maturity),
+        field("reference", FieldType::Reference),
+        field("log_memo", FieldType::Bool),
+        field("memo_len", FieldType::U8),
+        field("memo", FieldType::Memo),
+        field("escrow_index", FieldType::U64),
+    ];
+}
+
+const _: () = assert!(packed_len(EscrowState::FIELDS) == EscrowState::LEN);
+
+/// `EscrowState` as laid out in the account, read and updated in place.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Pod, Zeroable)]
+pub struct PodEscrowState {
+    header: [u8; HEADER_LEN],
+    is_initialized: u8,
+    pub caller_pubkey: Pubkey,
+    pub initializer_pubkey: Pubkey,
+    pub receiver_pubkey: Pubkey,
+    pub temp_token_account_pubkey: Pubkey,
+    escrow_amount: PodU64,
+    expire_date: PodI64,
+    voucher_nonce: PodU64,
+    time_basis: u8,
+    refund_after: PodI64,
+    reference: [u8; REFERENCE_LEN],
+    log_memo: u8,
+    memo_len: u8,
+    memo: [u8; MAX_MEMO_LEN],
+    escrow_index: PodU64,
+}
+
+const _: () = assert!(std::mem::size_of::<PodEscrowState>() == EscrowState::LEN);
+
+impl ZeroCopy for PodEscrowState {
+    type State = EscrowState;
+
+    fn validate(&self) -> Result<(), ProgramError> {
+        TimeBasis::from_u8(self.time_basis).ok_or(ProgramError::InvalidAccountData)?;
+        if self.log_memo > 1 {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        memo_from_bytes(self.memo_len, &self.memo)?;
+        Ok(())
+    }
+}
+
+impl PodEscrowState {
+    pub fn escrow_amount(&self) -> u64 {
+        self.escrow_amount.into()
+    }
+
+    pub fn set_escrow_amount(&mut self, escrow_amount: u64) {
+        self.escrow_amount = escrow_amount.into();
+    }
+
+    pub fn expire_date(&self) -> i64 {
+        self.expire_date.into()
+    }
+
+    pub fn set_expire_date(&mut self, expire_date: i64) {
+        self.expire_date = expire_date.into();
+    }
+
+    pub fn voucher_nonce(&self) -> u64 {
+        self.voucher_nonce.into()
+    }
+
+    pub fn set_voucher_nonce(&mut self, voucher_nonce: u64) {
+        self.voucher_nonce = voucher_nonce.into();
+    }
+
+    pub fn time_basis(&self) -> TimeBasis {
+        // Checked by `validate` when the account was loaded.
+        TimeBasis::from_u8(self.time_basis).unwrap_or_default()
+    }
+
+    pub fn set_time_basis(&mut self, time_basis: TimeBasis) {
+        self.time_basis = time_basis as u8;
+    }
+
+    pub fn refund_after(&self) -> i64 {
+        self.refund_after.into()
+    }
+
+    pub fn set_refund_after(&mut self, refund_after: i64) {
+        self.refund_after = refund_after.into();
+    }
+
+    /// Whether the release window has closed at `now`, see `EscrowState::refund_due`.
+    pub fn refund_due(&self, now: i64) -> bool {
+        self.refund_after() != 0 && now >= self.refund_after()
+    }
+
+    pub fn escrow_memo(&self) -> EscrowMemo {
+        EscrowMemo {
+            reference: reference_from_bytes(&self.reference),
+            // Checked by `validate` when the account was loaded.
+            memo: memo_from_bytes(self.memo_len, &self.memo).unwrap_or_default(),
+            log_memo: self.log_memo != 0,
+        }
+    }
+
+    pub fn escrow_index(&self) -> u64 {
+        self.escrow_index.into()
+    }
+
+    pub fn set_escrow_index(&mut self, escrow_index: u64) {
+        self.escrow_index = escrow_index.into();
+    }
+
+    /// Stores `memo`, which has to pass `EscrowMemo::validate`.
+    pub fn set_escrow_memo(&mut self, memo: &EscrowMemo) {
+        self.reference = memo.reference.unwrap_or_default();
+        self.log_memo = memo.log_memo as u8;
+        self.memo_len = memo.memo.len() as u8;
+        self.memo = [0; MAX_MEMO_LEN];
+        self.memo[..memo.memo.len()].copy_from_slice(memo.memo.as_bytes());
+    }
+}
+
+/// The text held in the first `len` bytes of `memo`, the rest has to be
+/// zeroed so the account packs back to the same bytes.
+fn memo_from_bytes(len: u8, memo: &[u8; MAX_MEMO_LEN]) -> Result<String, ProgramError> {
+    if len as usize > MAX_MEMO_LEN || memo[len as usize..].iter().any(|&byte| byte != 0) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    String::from_utf8(memo[..len as usize].to_vec()).map_err(|_| ProgramError::InvalidAccountData)
+}
+
 impl IsInitialized for EscrowState {
     fn is_initialized(&self) -> bool {
         self.is_initialized
@@ -28,9 +222,16 @@ impl IsInitialized for EscrowState {
 impl Pack for EscrowState {
     const LEN: usize = DEFAULT_ESCROW_STATE_LEN;
 
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..EscrowState::LEN).ok_or(EscrowError::AccountTooSmall)?;
         let src = array_ref![src, 0, EscrowState::LEN];
         let (
+            header,
             is_initialized,
             caller_pubkey,
             initializer_pubkey,
@@ -38,12 +239,27 @@ impl Pack for EscrowState {
             temp_token_account_pubkey,
             escrow_amount,
             expire_date,
-        ) = array_refs![src, 1, 32, 32, 32, 32, 8, 8];
+            voucher_nonce,
+            time_basis,
+            refund_after,
+            reference,
+            log_memo,
+            memo_len,
+            memo,
+            escrow_index,
+        ) = array_refs![src, 2, 1, 32, 32, 32, 32, 8, 8, 8, 1, 8, REFERENCE_LEN, 1, 1, MAX_MEMO_LEN, 8];
         let is_initialized = match is_initialized {
             [0] => false,
             [1] => true,
             _ => return Err(ProgramError::InvalidAccountData),
         };
+        check_header::<Self>(header, is_initialized)?;
+        let time_basis = TimeBasis::from_u8(time_basis[0]).ok_or(ProgramError::InvalidAccountData)?;
+        let log_memo = match log_memo {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
 
         Ok(EscrowState {
             is_initialized,
@@ -53,12 +269,20 @@ impl Pack for EscrowState {
             temp_token_account_pubkey: Pubkey::new_from_array(*temp_token_account_pubkey),
             escrow_amount: u64::from_le_bytes(*escrow_amount),
             expire_date: i64::from_le_bytes(*expire_date),
+            voucher_nonce: u64::from_le_bytes(*voucher_nonce),
+            time_basis,
+            refund_after: i64::from_le_bytes(*refund_after),
+            reference: reference_from_bytes(reference),
+            log_memo,
+            memo: memo_from_bytes(memo_len[0], memo)?,
+            escrow_index: u64::from_le_bytes(*escrow_index),
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, EscrowState::LEN];
         let (
+            header_dst,
             is_initialized_dst,
             caller_pubkey_dst,
             initializer_pubkey_dst,
@@ -66,7 +290,15 @@ impl Pack for EscrowState {
             temp_token_account_pubkey_dst,
             escrow_amount_dst,
             expire_date_dst,
-        ) = mut_array_refs![dst, 1, 32, 32, 32, 32, 8, 8];
+            voucher_nonce_dst,
+            time_basis_dst,
+            refund_after_dst,
+            reference_dst,
+            log_memo_dst,
+            memo_len_dst,
+            memo_dst,
+            escrow_index_dst,
+        ) = mut_array_refs![dst, 2, 1, 32, 32, 32, 32, 8, 8, 8, 1, 8, REFERENCE_LEN, 1, 1, MAX_MEMO_LEN, 8];
 
         let EscrowState {
             is_initialized,
@@ -76,8 +308,16 @@ impl Pack for EscrowState {
             temp_token_account_pubkey,
             escrow_amount,
             expire_date,
+            voucher_nonce,
+            time_basis,
+            refund_after,
+            reference,
+            log_memo,
+            memo,
+            escrow_index,
         } = self;
 
+        write_header::<Self>(header_dst);
         is_initialized_dst[0] = *is_initialized as u8;
         caller_pubkey_dst.copy_from_slice(caller_pubkey.as_ref());
         initializer_pubkey_dst.copy_from_slice(initializer_pubkey.as_ref());
@@ -85,5 +325,16 @@ impl Pack for EscrowState {
         temp_token_account_pubkey_dst.copy_from_slice(temp_token_account_pubkey.as_ref());
         *escrow_amount_dst = escrow_amount.to_le_bytes();
         *expire_date_dst = expire_date.to_le_bytes();
+        *voucher_nonce_dst = voucher_nonce.to_le_bytes();
+        time_basis_dst[0] = *time_basis as u8;
+        *refund_after_dst = refund_after.to_le_bytes();
+        *reference_dst = reference.unwrap_or_default();
+        log_memo_dst[0] = *log_memo as u8;
+        // Longer memos are cut to the capacity, `EscrowMemo::validate` keeps them out.
+        let memo = &memo.as_bytes()[..memo.len().min(MAX_MEMO_LEN)];
+        memo_len_dst[0] = memo.len() as u8;
+        memo_dst.fill(0);
+        memo_dst[..memo.len()].copy_from_slice(memo);
+        *escrow_index_dst = escrow_index.to_le_bytes();
     }
 }
@@ -0,0 +1,121 @@
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use crate::utils::constants::DEPOSIT_RECORD_STATE_LEN;
+use crate::utils::errors::EscrowError;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+};
+
+/// Balance one depositor holds in one mint of a wallet escrow registry. Lives
+/// at the PDA `[DEPOSIT_SEED, registry, mint, depositor]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DepositRecordState {
+    pub is_initialized: bool,
+    pub registry_pubkey: Pubkey,
+    pub mint_pubkey: Pubkey,
+    pub depositor_pubkey: Pubkey,
+    pub amount: u64,
+    /// Reset by every deposit, the registry owner can collect from then on.
+    /// Deposits into a matured record are rejected until it is collected.
+    pub expire_date: i64,
+}
+
+impl Sealed for DepositRecordState {}
+
+impl AccountLayout for DepositRecordState {
+    const NAME: &'static str = "deposit_record";
+    const DISCRIMINATOR: u8 = 4;
+    const VERSION: u8 = 1;
+    const LEGACY_LEN: Option<usize> = None;
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("registry_pubkey", FieldType::Pubkey),
+        field("mint_pubkey", FieldType::Pubkey),
+        field("depositor_pubkey", FieldType::Pubkey),
+        field("amount", FieldType::U64),
+        field("expire_date", FieldType::UnixTimestamp),
+    ];
+}
+
+const _: () = assert!(packed_len(DepositRecordState::FIELDS) == DepositRecordState::LEN);
+
+impl IsInitialized for DepositRecordState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for DepositRecordState {
+    const LEN: usize = DEPOSIT_RECORD_STATE_LEN;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..DepositRecordState::LEN).ok_or(EscrowError::AccountTooSmall)?;
+        let src = array_ref![src, 0, DepositRecordState::LEN];
+        let (
+            header,
+            is_initialized,
+            registry_pubkey,
+            mint_pubkey,
+            depositor_pubkey,
+            amount,
+            expire_date,
+        ) = array_refs![src, 2, 1, 32, 32, 32, 8, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        check_header::<Self>(header, is_initialized)?;
+
+        Ok(DepositRecordState {
+            is_initialized,
+            registry_pubkey: Pubkey::new_from_array(*registry_pubkey),
+            mint_pubkey: Pubkey::new_from_array(*mint_pubkey),
+            depositor_pubkey: Pubkey::new_from_array(*depositor_pubkey),
+            amount: u64::from_le_bytes(*amount),
+            expire_date: i64::from_le_bytes(*expire_date),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, DepositRecordState::LEN];
+        let (
+            header_dst,
+            is_initialized_dst,
+            registry_pubkey_dst,
+            mint_pubkey_dst,
+            depositor_pubkey_dst,
+            amount_dst,
+            expire_date_dst,
+        ) = mut_array_refs![dst, 2, 1, 32, 32, 32, 8, 8];
+
+        let DepositRecordState {
+            is_initialized,
+            registry_pubkey,
+            mint_pubkey,
+            depositor_pubkey,
+            amount,
+            expire_date,
+        } = self;
+
+        write_header::<Self>(header_dst);
+        is_initialized_dst[0] = *is_initialized as u8;
+        registry_pubkey_dst.copy_from_slice(registry_pubkey.as_ref());
+        mint_pubkey_dst.copy_from_slice(mint_pubkey.as_ref());
+        depositor_pubkey_dst.copy_from_slice(depositor_pubkey.as_ref());
+        *amount_dst = amount.to_le_bytes();
+        *expire_date_dst = expire_date.to_le_bytes();
+    }
+}
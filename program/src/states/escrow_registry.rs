@@ -5,16 +5,70 @@ use solana_program::{
 };
 
 use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
-use crate::utils::constants::ESCROW_REGISTRY_STATE_LEN;
+use bytemuck::{Pod, Zeroable};
+use crate::utils::constants::{ESCROW_REGISTRY_STATE_LEN, LEGACY_ESCROW_REGISTRY_STATE_LEN};
+use crate::utils::errors::EscrowError;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+    PodU64, ZeroCopy, HEADER_LEN,
+};
 
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EscrowRegistryState {
     pub is_initialized: bool,
     pub initializer_pubkey: Pubkey,
     pub token_account_pubkey: Pubkey,
+    /// Number of mints the registry accepts, each with a `RegistryMintState`.
+    pub mint_count: u64,
 }
 
 impl Sealed for EscrowRegistryState {}
 
+impl AccountLayout for EscrowRegistryState {
+    const NAME: &'static str = "registry";
+    const DISCRIMINATOR: u8 = 2;
+    const VERSION: u8 = 2;
+    const LEGACY_LEN: Option<usize> = Some(LEGACY_ESCROW_REGISTRY_STATE_LEN);
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("initializer_pubkey", FieldType::Pubkey),
+        field("token_account_pubkey", FieldType::Pubkey),
+        field("mint_count", FieldType::U64),
+    ];
+}
+
+const _: () = assert!(packed_len(EscrowRegistryState::FIELDS) == EscrowRegistryState::LEN);
+
+/// `EscrowRegistryState` as laid out in the account, read and updated in
+/// place.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Pod, Zeroable)]
+pub struct PodEscrowRegistryState {
+    header: [u8; HEADER_LEN],
+    is_initialized: u8,
+    pub initializer_pubkey: Pubkey,
+    pub token_account_pubkey: Pubkey,
+    mint_count: PodU64,
+}
+
+const _: () = assert!(std::mem::size_of::<PodEscrowRegistryState>() == EscrowRegistryState::LEN);
+
+impl ZeroCopy for PodEscrowRegistryState {
+    type State = EscrowRegistryState;
+}
+
+impl PodEscrowRegistryState {
+    pub fn mint_count(&self) -> u64 {
+        self.mint_count.into()
+    }
+
+    pub fn set_mint_count(&mut self, mint_count: u64) {
+        self.mint_count = mint_count.into();
+    }
+}
+
 impl IsInitialized for EscrowRegistryState {
     fn is_initialized(&self) -> bool {
         self.is_initialized
@@ -24,42 +78,57 @@ impl IsInitialized for EscrowRegistryState {
 impl Pack for EscrowRegistryState {
     const LEN: usize = ESCROW_REGISTRY_STATE_LEN;
 
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..EscrowRegistryState::LEN).ok_or(EscrowError::AccountTooSmall)?;
         let src = array_ref![src, 0, EscrowRegistryState::LEN];
         let (
+            header,
             is_initialized,
             initializer_pubkey,
-            token_account_pubkey
-        ) = array_refs![src, 1, 32, 32];
+            token_account_pubkey,
+            mint_count,
+        ) = array_refs![src, 2, 1, 32, 32, 8];
         let is_initialized = match is_initialized {
             [0] => false,
             [1] => true,
             _ => return Err(ProgramError::InvalidAccountData),
         };
+        check_header::<Self>(header, is_initialized)?;
 
         Ok(EscrowRegistryState {
             is_initialized,
             initializer_pubkey: Pubkey::new_from_array(*initializer_pubkey),
             token_account_pubkey: Pubkey::new_from_array(*token_account_pubkey),
+            mint_count: u64::from_le_bytes(*mint_count),
         })
     }
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let dst = array_mut_ref![dst, 0, EscrowRegistryState::LEN];
         let (
+            header_dst,
             is_initialized_dst,
             initializer_pubkey_dst,
-            token_account_pubkey_dst
-        ) = mut_array_refs![dst, 1, 32, 32];
+            token_account_pubkey_dst,
+            mint_count_dst,
+        ) = mut_array_refs![dst, 2, 1, 32, 32, 8];
 
         let EscrowRegistryState {
             is_initialized,
             initializer_pubkey,
             token_account_pubkey,
+            mint_count,
         } = self;
 
+        write_header::<Self>(header_dst);
         is_initialized_dst[0] = *is_initialized as u8;
         initializer_pubkey_dst.copy_from_slice(initializer_pubkey.as_ref());
         token_account_pubkey_dst.copy_from_slice(token_account_pubkey.as_ref());
+        *mint_count_dst = mint_count.to_le_bytes();
     }
 }
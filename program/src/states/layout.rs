@@ -0,0 +1,259 @@
+use std::cmp::Ordering;
+
+use arrayref::{array_mut_ref, array_ref};
+use bytemuck::{Pod, Zeroable};
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack},
+};
+
+use crate::utils::{
+    constants::{MAX_MEMO_LEN, REFERENCE_LEN},
+    errors::EscrowError,
+};
+
+/// Every state account starts with `[discriminator, version]`.
+pub const HEADER_LEN: usize = 2;
+
+/// Wire type of a packed state field.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldType {
+    U8,
+    Bool,
+    Pubkey,
+    U64,
+    /// `i64` seconds since the unix epoch.
+    UnixTimestamp,
+    /// `u8` tag of a `TimeBasis`.
+    TimeBasis,
+    /// `i64` in the `TimeBasis` of the account's `time_basis` field.
+    Maturity,
+    /// 32 opaque bytes, all zeroes for none.
+    Reference,
+    /// UTF-8 text, as long as the account's `memo_len` field.
+    Memo,
+}
+
+impl FieldType {
+    pub const fn size(self) -> usize {
+        match self {
+            FieldType::U8 | FieldType::Bool | FieldType::TimeBasis => 1,
+            FieldType::Pubkey => 32,
+            FieldType::U64 | FieldType::UnixTimestamp | FieldType::Maturity => 8,
+            FieldType::Reference => REFERENCE_LEN,
+            FieldType::Memo => MAX_MEMO_LEN,
+        }
+    }
+
+    /// Type name used by `configs/schema.json`.
+    pub const fn schema_type(self) -> &'static str {
+        match self {
+            FieldType::U8 | FieldType::TimeBasis => "u8",
+            FieldType::Bool => "bool",
+            FieldType::Pubkey => "pub",
+            FieldType::U64 => "u64",
+            FieldType::UnixTimestamp | FieldType::Maturity => "i64",
+            FieldType::Reference => "[u8;32]",
+            FieldType::Memo => "[u8;64]",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub name: &'static str,
+    pub field_type: FieldType,
+}
+
+/// The packed field order of a state type. Kept next to its `Pack` impl and
+/// checked against `Pack::LEN` at compile time.
+pub trait AccountLayout: Pack {
+    /// Schema key of the account type.
+    const NAME: &'static str;
+    /// First header byte, unique per state type.
+    const DISCRIMINATOR: u8;
+    /// Second header byte. Bumped whenever fields are appended.
+    const VERSION: u8;
+    /// Size of accounts created before the header existed, if there are any.
+    const LEGACY_LEN: Option<usize>;
+    const FIELDS: &'static [Field];
+
+    /// Unpacks an initialized account written by any layout version.
+    /// Headerless legacy accounts and older versions are read as if their
+    /// missing fields were zero, which is also how they get migrated.
+    fn unpack_versioned(data: &[u8]) -> Result<Self, ProgramError>
+    where
+        Self: IsInitialized,
+    {
+        let mut upgraded = vec![0; Self::LEN];
+        if Some(data.len()) == Self::LEGACY_LEN {
+            upgraded[HEADER_LEN..HEADER_LEN + data.len()].copy_from_slice(data);
+        } else if (HEADER_LEN..=Self::LEN).contains(&data.len()) {
+            if data[0] != Self::DISCRIMINATOR {
+                return Err(EscrowError::InvalidAccountDiscriminator.into());
+            }
+            if data[1] > Self::VERSION {
+                return Err(EscrowError::UnsupportedAccountVersion.into());
+            }
+            if data[1] == Self::VERSION && data.len() < Self::LEN {
+                return Err(EscrowError::AccountTooSmall.into());
+            }
+            upgraded[..data.len()].copy_from_slice(data);
+        } else if data.len() < HEADER_LEN {
+            return Err(EscrowError::AccountTooSmall.into());
+        } else {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        upgraded[0] = Self::DISCRIMINATOR;
+        upgraded[1] = Self::VERSION;
+        Self::unpack(&upgraded)
+    }
+
+    /// Byte offset of the field called `name`.
+    fn offset_of(name: &str) -> Option<usize> {
+        let mut offset = 0;
+        for field in Self::FIELDS {
+            if field.name == name {
+                return Some(offset);
+            }
+            offset += field.field_type.size();
+        }
+        None
+    }
+}
+
+pub const fn packed_len(fields: &[Field]) -> usize {
+    let mut len = 0;
+    let mut i = 0;
+    while i < fields.len() {
+        len += fields[i].field_type.size();
+        i += 1;
+    }
+    len
+}
+
+pub(crate) const fn field(name: &'static str, field_type: FieldType) -> Field {
+    Field { name, field_type }
+}
+
+/// Checks `data` holds exactly one packed `T`, used in place of the
+/// `InvalidAccountData` `Pack::unpack_unchecked` returns for any other size.
+pub(crate) fn check_len<T: Pack>(data: &[u8]) -> Result<(), ProgramError> {
+    match data.len().cmp(&T::LEN) {
+        Ordering::Less => Err(EscrowError::AccountTooSmall.into()),
+        Ordering::Equal => Ok(()),
+        Ordering::Greater => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Checks the header of a packed `T`. A zeroed header belongs to an account
+/// that was allocated but never initialized.
+pub(crate) fn check_header<T: AccountLayout>(
+    header: &[u8; HEADER_LEN],
+    is_initialized: bool,
+) -> Result<(), ProgramError> {
+    match *header {
+        [discriminator, version] if discriminator == T::DISCRIMINATOR && version == T::VERSION => {
+            Ok(())
+        }
+        [discriminator, _] if discriminator == T::DISCRIMINATOR => {
+            Err(EscrowError::UnsupportedAccountVersion.into())
+        }
+        [0, 0] if !is_initialized => Ok(()),
+        _ => Err(EscrowError::InvalidAccountDiscriminator.into()),
+    }
+}
+
+pub(crate) fn write_header<T: AccountLayout>(header: &mut [u8; HEADER_LEN]) {
+    *header = [T::DISCRIMINATOR, T::VERSION];
+}
+
+/// Little endian `u64` aligned to 1 byte, so a `Pod` state can keep the
+/// packed layout, which puts integers at any offset.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct PodU64([u8; 8]);
+
+impl From<u64> for PodU64 {
+    fn from(value: u64) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<PodU64> for u64 {
+    fn from(value: PodU64) -> Self {
+        u64::from_le_bytes(value.0)
+    }
+}
+
+/// Little endian `i64` aligned to 1 byte, see [`PodU64`].
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct PodI64([u8; 8]);
+
+impl From<i64> for PodI64 {
+    fn from(value: i64) -> Self {
+        Self(value.to_le_bytes())
+    }
+}
+
+impl From<PodI64> for i64 {
+    fn from(value: PodI64) -> Self {
+        i64::from_le_bytes(value.0)
+    }
+}
+
+/// In place view of a packed `State`, read and written through field
+/// accessors without copying the account out and back. Every state packs
+/// its `is_initialized` flag right after the header, which is where the
+/// loaders check it. Views borrow the account data, so handlers drop them
+/// before any CPI the account could be passed to.
+pub trait ZeroCopy: Pod {
+    type State: AccountLayout;
+
+    /// Checks the fields that not every byte pattern is valid for.
+    fn validate(&self) -> Result<(), ProgramError> {
+        Ok(())
+    }
+
+    /// Checks the size and header of `data`, which may be uninitialized.
+    fn check(data: &[u8]) -> Result<bool, ProgramError> {
+        check_len::<Self::State>(data)?;
+        let is_initialized = match data[HEADER_LEN] {
+            0 => false,
+            1 => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        check_header::<Self::State>(array_ref![data, 0, HEADER_LEN], is_initialized)?;
+        Ok(is_initialized)
+    }
+
+    fn load(data: &[u8]) -> Result<&Self, ProgramError> {
+        if !Self::check(data)? {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let account: &Self = bytemuck::from_bytes(data);
+        account.validate()?;
+        Ok(account)
+    }
+
+    fn load_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if !Self::check(data)? {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        let account: &mut Self = bytemuck::from_bytes_mut(data);
+        account.validate()?;
+        Ok(account)
+    }
+
+    /// Writes the header and `is_initialized` flag of an allocated but
+    /// uninitialized account, leaving its fields to the caller.
+    fn initialize(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        if Self::check(data)? {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        write_header::<Self::State>(array_mut_ref![data, 0, HEADER_LEN]);
+        data[HEADER_LEN] = 1;
+        Ok(bytemuck::from_bytes_mut(data))
+    }
+}
@@ -1,2 +1,8 @@
+pub mod layout;
 pub mod default_escrow;
-pub mod escrow_registry;
\ No newline at end of file
+pub mod escrow_registry;
+pub mod sol_escrow;
+pub mod deposit_record;
+pub mod registry_mint;
+pub mod user_profile;
+pub mod program_config;
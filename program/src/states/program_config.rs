@@ -0,0 +1,84 @@
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+};
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use crate::utils::constants::PROGRAM_CONFIG_STATE_LEN;
+use crate::utils::errors::EscrowError;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+};
+
+/// Settings of the program, set by its upgrade authority with `SetConfig`.
+/// Lives at the PDA `[PROGRAM_CONFIG_SEED]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramConfigState {
+    pub is_initialized: bool,
+    /// Token escrows an initializer can have open at once.
+    pub max_open_escrows_per_user: u64,
+}
+
+impl Sealed for ProgramConfigState {}
+
+impl AccountLayout for ProgramConfigState {
+    const NAME: &'static str = "program_config";
+    const DISCRIMINATOR: u8 = 7;
+    const VERSION: u8 = 1;
+    const LEGACY_LEN: Option<usize> = None;
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("max_open_escrows_per_user", FieldType::U64),
+    ];
+}
+
+const _: () = assert!(packed_len(ProgramConfigState::FIELDS) == ProgramConfigState::LEN);
+
+impl IsInitialized for ProgramConfigState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for ProgramConfigState {
+    const LEN: usize = PROGRAM_CONFIG_STATE_LEN;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..ProgramConfigState::LEN).ok_or(EscrowError::AccountTooSmall)?;
+        let src = array_ref![src, 0, ProgramConfigState::LEN];
+        let (header, is_initialized, max_open_escrows_per_user) = array_refs![src, 2, 1, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        check_header::<Self>(header, is_initialized)?;
+
+        Ok(ProgramConfigState {
+            is_initialized,
+            max_open_escrows_per_user: u64::from_le_bytes(*max_open_escrows_per_user),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, ProgramConfigState::LEN];
+        let (header_dst, is_initialized_dst, max_open_escrows_per_user_dst) =
+            mut_array_refs![dst, 2, 1, 8];
+
+        let ProgramConfigState {
+            is_initialized,
+            max_open_escrows_per_user,
+        } = self;
+
+        write_header::<Self>(header_dst);
+        is_initialized_dst[0] = *is_initialized as u8;
+        *max_open_escrows_per_user_dst = max_open_escrows_per_user.to_le_bytes();
+    }
+}
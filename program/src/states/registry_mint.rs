@@ -0,0 +1,114 @@
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use crate::utils::constants::REGISTRY_MINT_STATE_LEN;
+use crate::utils::errors::EscrowError;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+};
+
+/// A mint accepted by a wallet escrow registry and the vault its deposits
+/// sit in. Lives at the PDA `[REGISTRY_MINT_SEED, registry, mint]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegistryMintState {
+    pub is_initialized: bool,
+    pub registry_pubkey: Pubkey,
+    pub mint_pubkey: Pubkey,
+    /// Token account owned by the wallet escrow PDA.
+    pub vault_pubkey: Pubkey,
+    /// Sum of the `amount` of every deposit record of this mint.
+    pub total_deposits: u64,
+}
+
+impl Sealed for RegistryMintState {}
+
+impl AccountLayout for RegistryMintState {
+    const NAME: &'static str = "registry_mint";
+    const DISCRIMINATOR: u8 = 5;
+    const VERSION: u8 = 1;
+    const LEGACY_LEN: Option<usize> = None;
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("registry_pubkey", FieldType::Pubkey),
+        field("mint_pubkey", FieldType::Pubkey),
+        field("vault_pubkey", FieldType::Pubkey),
+        field("total_deposits", FieldType::U64),
+    ];
+}
+
+const _: () = assert!(packed_len(RegistryMintState::FIELDS) == RegistryMintState::LEN);
+
+impl IsInitialized for RegistryMintState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for RegistryMintState {
+    const LEN: usize = REGISTRY_MINT_STATE_LEN;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..RegistryMintState::LEN).ok_or(EscrowError::AccountTooSmall)?;
+        let src = array_ref![src, 0, RegistryMintState::LEN];
+        let (
+            header,
+            is_initialized,
+            registry_pubkey,
+            mint_pubkey,
+            vault_pubkey,
+            total_deposits,
+        ) = array_refs![src, 2, 1, 32, 32, 32, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        check_header::<Self>(header, is_initialized)?;
+
+        Ok(RegistryMintState {
+            is_initialized,
+            registry_pubkey: Pubkey::new_from_array(*registry_pubkey),
+            mint_pubkey: Pubkey::new_from_array(*mint_pubkey),
+            vault_pubkey: Pubkey::new_from_array(*vault_pubkey),
+            total_deposits: u64::from_le_bytes(*total_deposits),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, RegistryMintState::LEN];
+        let (
+            header_dst,
+            is_initialized_dst,
+            registry_pubkey_dst,
+            mint_pubkey_dst,
+            vault_pubkey_dst,
+            total_deposits_dst,
+        ) = mut_array_refs![dst, 2, 1, 32, 32, 32, 8];
+
+        let RegistryMintState {
+            is_initialized,
+            registry_pubkey,
+            mint_pubkey,
+            vault_pubkey,
+            total_deposits,
+        } = self;
+
+        write_header::<Self>(header_dst);
+        is_initialized_dst[0] = *is_initialized as u8;
+        registry_pubkey_dst.copy_from_slice(registry_pubkey.as_ref());
+        mint_pubkey_dst.copy_from_slice(mint_pubkey.as_ref());
+        vault_pubkey_dst.copy_from_slice(vault_pubkey.as_ref());
+        *total_deposits_dst = total_deposits.to_le_bytes();
+    }
+}
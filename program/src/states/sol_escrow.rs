@@ -0,0 +1,143 @@
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use crate::utils::constants::SOL_ESCROW_STATE_LEN;
+use crate::utils::errors::EscrowError;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+};
+
+/// Lamport escrow. Shares the field order of `EscrowState`, with the
+/// PDA vault holding the deposit in place of the temp token account.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SolEscrowState {
+    pub is_initialized: bool,
+    pub caller_pubkey: Pubkey,
+    pub initializer_pubkey: Pubkey,
+    /// Holder of the claim on the escrow, reassigned by `AssignReceiver`.
+    pub receiver_pubkey: Pubkey,
+    pub vault_pubkey: Pubkey,
+    pub escrow_amount: u64,
+    pub expire_date: i64,
+    /// End of the release window, in unix seconds. `0` for escrows created
+    /// before the window existed, which stay releasable.
+    pub refund_after: i64,
+}
+
+impl SolEscrowState {
+    /// Whether the release window has closed at `now`.
+    pub fn refund_due(&self, now: i64) -> bool {
+        self.refund_after != 0 && now >= self.refund_after
+    }
+}
+
+impl Sealed for SolEscrowState {}
+
+impl AccountLayout for SolEscrowState {
+    const NAME: &'static str = "sol_escrow";
+    const DISCRIMINATOR: u8 = 3;
+    const VERSION: u8 = 2;
+    const LEGACY_LEN: Option<usize> = None;
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("caller_pubkey", FieldType::Pubkey),
+        field("initializer_pubkey", FieldType::Pubkey),
+        field("receiver_pubkey", FieldType::Pubkey),
+        field("vault_pubkey", FieldType::Pubkey),
+        field("escrow_amount", FieldType::U64),
+        field("expire_date", FieldType::UnixTimestamp),
+        field("refund_after", FieldType::UnixTimestamp),
+    ];
+}
+
+const _: () = assert!(packed_len(SolEscrowState::FIELDS) == SolEscrowState::LEN);
+
+impl IsInitialized for SolEscrowState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for SolEscrowState {
+    const LEN: usize = SOL_ESCROW_STATE_LEN;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..SolEscrowState::LEN).ok_or(EscrowError::AccountTooSmall)?;
+        let src = array_ref![src, 0, SolEscrowState::LEN];
+        let (
+            header,
+            is_initialized,
+            caller_pubkey,
+            initializer_pubkey,
+            receiver_pubkey,
+            vault_pubkey,
+            escrow_amount,
+            expire_date,
+            refund_after,
+        ) = array_refs![src, 2, 1, 32, 32, 32, 32, 8, 8, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        check_header::<Self>(header, is_initialized)?;
+
+        Ok(SolEscrowState {
+            is_initialized,
+            caller_pubkey: Pubkey::new_from_array(*caller_pubkey),
+            initializer_pubkey: Pubkey::new_from_array(*initializer_pubkey),
+            receiver_pubkey: Pubkey::new_from_array(*receiver_pubkey),
+            vault_pubkey: Pubkey::new_from_array(*vault_pubkey),
+            escrow_amount: u64::from_le_bytes(*escrow_amount),
+            expire_date: i64::from_le_bytes(*expire_date),
+            refund_after: i64::from_le_bytes(*refund_after),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, SolEscrowState::LEN];
+        let (
+            header_dst,
+            is_initialized_dst,
+            caller_pubkey_dst,
+            initializer_pubkey_dst,
+            receiver_pubkey_dst,
+            vault_pubkey_dst,
+            escrow_amount_dst,
+            expire_date_dst,
+            refund_after_dst,
+        ) = mut_array_refs![dst, 2, 1, 32, 32, 32, 32, 8, 8, 8];
+
+        let SolEscrowState {
+            is_initialized,
+            caller_pubkey,
+            initializer_pubkey,
+            receiver_pubkey,
+            vault_pubkey,
+            escrow_amount,
+            expire_date,
+            refund_after,
+        } = self;
+
+        write_header::<Self>(header_dst);
+        is_initialized_dst[0] = *is_initialized as u8;
+        caller_pubkey_dst.copy_from_slice(caller_pubkey.as_ref());
+        initializer_pubkey_dst.copy_from_slice(initializer_pubkey.as_ref());
+        receiver_pubkey_dst.copy_from_slice(receiver_pubkey.as_ref());
+        vault_pubkey_dst.copy_from_slice(vault_pubkey.as_ref());
+        *escrow_amount_dst = escrow_amount.to_le_bytes();
+        *expire_date_dst = expire_date.to_le_bytes();
+        *refund_after_dst = refund_after.to_le_bytes();
+    }
+}
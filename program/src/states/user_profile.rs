@@ -0,0 +1,128 @@
+use solana_program::{
+    program_error::ProgramError,
+    program_pack::{IsInitialized, Pack, Sealed},
+    pubkey::Pubkey,
+};
+
+use arrayref::{array_mut_ref, array_ref, array_refs, mut_array_refs};
+use crate::utils::constants::USER_PROFILE_STATE_LEN;
+use crate::utils::errors::EscrowError;
+use super::layout::{
+    check_header, check_len, field, packed_len, write_header, AccountLayout, Field, FieldType,
+};
+
+/// Token escrows of one initializer. Lives at the PDA
+/// `[USER_PROFILE_SEED, initializer]`, created with its first escrow.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UserProfileState {
+    pub is_initialized: bool,
+    pub initializer_pubkey: Pubkey,
+    /// Escrows created so far, never decreases. The next escrow gets
+    /// `escrow_count + 1` as its index and address seed.
+    pub escrow_count: u64,
+    /// Escrows not yet released or refunded, capped by the program config's
+    /// `max_open_escrows_per_user`.
+    pub open_escrows: u64,
+    /// Tokens escrowed on creation and by `IncreaseDeposit`, saturating. It
+    /// adds up raw base units of whichever mint each escrow holds, so it is
+    /// only a volume for initializers escrowing a single mint; per mint
+    /// volumes come from the `EscrowCreated` and `DepositIncreased` events.
+    pub total_volume: u64,
+    /// Escrows disputed, no instruction raises a dispute yet.
+    pub disputes: u64,
+}
+
+impl Sealed for UserProfileState {}
+
+impl AccountLayout for UserProfileState {
+    const NAME: &'static str = "user_profile";
+    const DISCRIMINATOR: u8 = 6;
+    const VERSION: u8 = 2;
+    const LEGACY_LEN: Option<usize> = None;
+    const FIELDS: &'static [Field] = &[
+        field("discriminator", FieldType::U8),
+        field("version", FieldType::U8),
+        field("is_initialized", FieldType::Bool),
+        field("initializer_pubkey", FieldType::Pubkey),
+        field("escrow_count", FieldType::U64),
+        field("open_escrows", FieldType::U64),
+        field("total_volume", FieldType::U64),
+        field("disputes", FieldType::U64),
+    ];
+}
+
+const _: () = assert!(packed_len(UserProfileState::FIELDS) == UserProfileState::LEN);
+
+impl IsInitialized for UserProfileState {
+    fn is_initialized(&self) -> bool {
+        self.is_initialized
+    }
+}
+
+impl Pack for UserProfileState {
+    const LEN: usize = USER_PROFILE_STATE_LEN;
+
+    fn unpack_unchecked(input: &[u8]) -> Result<Self, ProgramError> {
+        check_len::<Self>(input)?;
+        Self::unpack_from_slice(input)
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let src = src.get(..UserProfileState::LEN).ok_or(EscrowError::AccountTooSmall)?;
+        let src = array_ref![src, 0, UserProfileState::LEN];
+        let (
+            header,
+            is_initialized,
+            initializer_pubkey,
+            escrow_count,
+            open_escrows,
+            total_volume,
+            disputes,
+        ) = array_refs![src, 2, 1, 32, 8, 8, 8, 8];
+        let is_initialized = match is_initialized {
+            [0] => false,
+            [1] => true,
+            _ => return Err(ProgramError::InvalidAccountData),
+        };
+        check_header::<Self>(header, is_initialized)?;
+
+        Ok(UserProfileState {
+            is_initialized,
+            initializer_pubkey: Pubkey::new_from_array(*initializer_pubkey),
+            escrow_count: u64::from_le_bytes(*escrow_count),
+            open_escrows: u64::from_le_bytes(*open_escrows),
+            total_volume: u64::from_le_bytes(*total_volume),
+            disputes: u64::from_le_bytes(*disputes),
+        })
+    }
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let dst = array_mut_ref![dst, 0, UserProfileState::LEN];
+        let (
+            header_dst,
+            is_initialized_dst,
+            initializer_pubkey_dst,
+            escrow_count_dst,
+            open_escrows_dst,
+            total_volume_dst,
+            disputes_dst,
+        ) = mut_array_refs![dst, 2, 1, 32, 8, 8, 8, 8];
+
+        let UserProfileState {
+            is_initialized,
+            initializer_pubkey,
+            escrow_count,
+            open_escrows,
+            total_volume,
+            disputes,
+        } = self;
+
+        write_header::<Self>(header_dst);
+        is_initialized_dst[0] = *is_initialized as u8;
+        initializer_pubkey_dst.copy_from_slice(initializer_pubkey.as_ref());
+        *escrow_count_dst = escrow_count.to_le_bytes();
+        *open_escrows_dst = open_escrows.to_le_bytes();
+        *total_volume_dst = total_volume.to_le_bytes();
+        *disputes_dst = disputes.to_le_bytes();
+    }
+}
@@ -0,0 +1,121 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, instruction::AccountMeta,
+    pubkey::Pubkey,
+};
+
+use crate::utils::validation;
+
+/// Who has to own an account listed in an instruction context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountOwner {
+    Any,
+    /// This escrow program.
+    Program,
+    /// SPL Token or Token-2022.
+    Token,
+}
+
+pub fn check_constraints(
+    account: &AccountInfo,
+    signer: bool,
+    writable: bool,
+    owner: AccountOwner,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    if signer {
+        validation::assert_signer(account)?;
+    }
+    if writable {
+        validation::assert_writable(account)?;
+    }
+    match owner {
+        AccountOwner::Any => Ok(()),
+        AccountOwner::Program => validation::assert_owned_by(account, program_id),
+        AccountOwner::Token => {
+            if *account.owner == spl_token::id() {
+                Ok(())
+            } else {
+                validation::assert_owned_by(account, &spl_token_2022::id())
+            }
+        }
+    }
+}
+
+pub fn account_meta(pubkey: Pubkey, signer: bool, writable: bool) -> AccountMeta {
+    if writable {
+        AccountMeta::new(pubkey, signer)
+    } else {
+        AccountMeta::new_readonly(pubkey, signer)
+    }
+}
+
+/// Declares the account list of an instruction once and generates both sides of it:
+///
+/// - `$name`, built on-chain from `&[AccountInfo]` in order, enforcing the signer,
+///   writable and owner constraints of every account. Accounts past the fixed
+///   list are kept in `remaining`.
+/// - `$keys`, the client-side pubkeys, turned into the matching `AccountMeta` list.
+macro_rules! instruction_accounts {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident / $keys:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: signer = $signer:literal, writable = $writable:literal, owner = $owner:ident;
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        pub struct $name<'a, 'info> {
+            $(
+                $(#[$field_meta])*
+                pub $field: &'a solana_program::account_info::AccountInfo<'info>,
+            )*
+            /// Accounts passed after the fixed list.
+            pub remaining: &'a [solana_program::account_info::AccountInfo<'info>],
+        }
+
+        impl<'a, 'info> $name<'a, 'info> {
+            pub fn try_from_accounts(
+                accounts: &'a [solana_program::account_info::AccountInfo<'info>],
+                program_id: &solana_program::pubkey::Pubkey,
+            ) -> Result<Self, solana_program::program_error::ProgramError> {
+                let account_info_iter = &mut accounts.iter();
+                $(
+                    let $field = solana_program::account_info::next_account_info(account_info_iter)?;
+                    $crate::utils::accounts::check_constraints(
+                        $field,
+                        $signer,
+                        $writable,
+                        $crate::utils::accounts::AccountOwner::$owner,
+                        program_id,
+                    )?;
+                )*
+
+                Ok(Self {
+                    $($field,)*
+                    remaining: account_info_iter.as_slice(),
+                })
+            }
+        }
+
+        #[doc = concat!("Client-side keys of [`", stringify!($name), "`], in the same order.")]
+        #[derive(Clone, Debug, PartialEq, Eq)]
+        pub struct $keys {
+            $(
+                $(#[$field_meta])*
+                pub $field: solana_program::pubkey::Pubkey,
+            )*
+        }
+
+        impl $keys {
+            pub fn to_account_metas(&self) -> Vec<solana_program::instruction::AccountMeta> {
+                vec![
+                    $($crate::utils::accounts::account_meta(self.$field, $signer, $writable),)*
+                ]
+            }
+        }
+    };
+}
+
+pub(crate) use instruction_accounts;
@@ -1,6 +1,29 @@
 
-pub const DEFAULT_ESCROW_STATE_LEN: usize = 145;
-pub const ESCROW_REGISTRY_STATE_LEN: usize = 65;
+pub const DEFAULT_ESCROW_STATE_LEN: usize = 270;
+pub const ESCROW_REGISTRY_STATE_LEN: usize = 75;
+pub const SOL_ESCROW_STATE_LEN: usize = 155;
+pub const DEPOSIT_RECORD_STATE_LEN: usize = 115;
+pub const REGISTRY_MINT_STATE_LEN: usize = 107;
+pub const USER_PROFILE_STATE_LEN: usize = 67;
+pub const PROGRAM_CONFIG_STATE_LEN: usize = 11;
+/// State sizes before accounts carried a discriminator and version header.
+pub const LEGACY_DEFAULT_ESCROW_STATE_LEN: usize = 145;
+pub const LEGACY_ESCROW_REGISTRY_STATE_LEN: usize = 65;
 pub const ESCROW_SEED: &[u8] = b"escrow";
 pub const ESCROW_WALLET_SEED: &[u8] = b"escrow ";
-pub const ESCROW_WAIT_TIME_SEC: i64 = 2; //300;
\ No newline at end of file
+pub const SOL_VAULT_SEED: &[u8] = b"sol_vault";
+pub const DEPOSIT_SEED: &[u8] = b"deposit";
+pub const REGISTRY_MINT_SEED: &[u8] = b"registry_mint";
+pub const REGISTRY_VAULT_SEED: &[u8] = b"registry_vault";
+pub const USER_PROFILE_SEED: &[u8] = b"user_profile";
+pub const PROGRAM_CONFIG_SEED: &[u8] = b"program_config";
+pub const ESCROW_WAIT_TIME_SEC: i64 = 2; //300;
+pub const ESCROW_WAIT_SLOTS: i64 = 5;
+pub const ESCROW_WAIT_EPOCHS: i64 = 1;
+/// How long a matured escrow can be released before it can only be refunded.
+pub const ESCROW_RELEASE_WINDOW_SEC: i64 = 7 * 24 * 60 * 60;
+pub const ESCROW_RELEASE_WINDOW_SLOTS: i64 = 1_512_000;
+pub const ESCROW_RELEASE_WINDOW_EPOCHS: i64 = 3;
+/// Capacity of the memo stored in a token escrow, in UTF-8 bytes.
+pub const MAX_MEMO_LEN: usize = 64;
+pub const REFERENCE_LEN: usize = 32;
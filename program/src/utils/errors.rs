@@ -13,6 +13,78 @@ pub enum EscrowError {
     AmountOverflow,
     #[error("Escrow time not reached yet")]
     EscrowNotMaturedYet,
+    #[error("Escrow time already reached")]
+    EscrowAlreadyMatured,
+    #[error("Insufficient Funds")]
+    InsufficientFunds,
+    #[error("Token program is neither SPL Token nor Token-2022")]
+    InvalidTokenProgram,
+    #[error("Mint has an extension that breaks escrow custody")]
+    UnsupportedMintExtension,
+    #[error("Account is not the system program")]
+    InvalidSystemProgram,
+    #[error("Account is not the expected sysvar")]
+    InvalidSysvar,
+    #[error("Account does not match the derived program address")]
+    InvalidPda,
+    #[error("Account must be a signer")]
+    AccountNotSigner,
+    #[error("Account must be writable")]
+    AccountNotWritable,
+    #[error("Account is not owned by the expected program")]
+    InvalidAccountOwner,
+    #[error("Account data belongs to a different account type")]
+    InvalidAccountDiscriminator,
+    #[error("Account was written by a newer or older layout version")]
+    UnsupportedAccountVersion,
+    #[error("Signer is not the program upgrade authority")]
+    InvalidUpgradeAuthority,
+    #[error("Deposit ledger does not reconcile with the vault balance")]
+    LedgerMismatch,
+    #[error("Mint still holds deposits")]
+    OutstandingDeposits,
+    #[error("Voucher is not signed by the initializer or receiver, or does not match the escrow")]
+    InvalidVoucher,
+    #[error("Voucher has expired")]
+    VoucherExpired,
+    #[error("Voucher nonce was already used")]
+    VoucherReplayed,
+    #[error("An amendment can only lower the amount, extend the expire date or change the receiver")]
+    InvalidAmendment,
+    #[error("Account data is shorter than its layout")]
+    AccountTooSmall,
+    #[error("Clock sysvar is unavailable")]
+    ClockUnavailable,
+    #[error("Release window has closed, the escrow can only be refunded")]
+    ReleaseWindowClosed,
+    #[error("Escrow can only be refunded once its release window has closed")]
+    RefundNotDue,
+    #[error("Memo is longer than its capacity, not UTF-8, or there is nothing to log")]
+    InvalidMemo,
+    #[error("Initializer has as many open escrows as allowed")]
+    TooManyOpenEscrows,
+    #[error("Account is not the initializer recorded on the escrow")]
+    InitializerMismatch,
+    #[error("Account is not the receiver recorded on the escrow")]
+    ReceiverMismatch,
+    #[error("Account is not the caller recorded on the escrow")]
+    CallerMismatch,
+    #[error("Token account is not the escrow's temp token account")]
+    TempTokenAccountMismatch,
+    #[error("Account is not the vault recorded on the escrow or registry mint")]
+    VaultMismatch,
+    #[error("Token account is owned by someone other than expected")]
+    TokenOwnerMismatch,
+    #[error("Token account or record holds another mint")]
+    MintMismatch,
+    #[error("Signer is not the registry owner")]
+    RegistryOwnerMismatch,
+    #[error("Record belongs to another registry or depositor")]
+    DepositRecordMismatch,
+    #[error("Account is not the SPL Memo program")]
+    InvalidMemoProgram,
+    #[error("Account is not the program data of an upgradeable program")]
+    InvalidProgramData,
 }
 
 impl From<EscrowError> for ProgramError {
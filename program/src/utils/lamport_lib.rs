@@ -0,0 +1,91 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::{invoke, invoke_signed},
+    pubkey::Pubkey, system_instruction,
+};
+
+use crate::utils::{errors::EscrowError, logging::verbose};
+
+/// Creates (or adopts, when someone already sent lamports to the address) a
+/// PDA of `space` bytes owned by this program and funds it with `lamports`.
+pub fn create_pda_account<'a>(
+    payer: AccountInfo<'a>,
+    account: AccountInfo<'a>,
+    system_program: AccountInfo<'a>,
+    lamports: u64,
+    space: usize,
+    program_id: &Pubkey,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let account_infos = &[payer.clone(), account.clone(), system_program.clone()];
+
+    if account.lamports() == 0 {
+        verbose!("Calling the system program to create the account...");
+        let instruction = system_instruction::create_account(
+            payer.key,
+            account.key,
+            lamports,
+            space as u64,
+            program_id,
+        );
+        return invoke_signed(&instruction, account_infos, &[signer_seeds]);
+    }
+
+    let top_up = lamports.saturating_sub(account.lamports());
+    if top_up > 0 {
+        verbose!("Calling the system program to fund the account...");
+        let instruction = system_instruction::transfer(payer.key, account.key, top_up);
+        invoke_signed(&instruction, account_infos, &[signer_seeds])?;
+    }
+
+    if space > 0 {
+        verbose!("Calling the system program to allocate the account...");
+        let instruction = system_instruction::allocate(account.key, space as u64);
+        invoke_signed(&instruction, account_infos, &[signer_seeds])?;
+    }
+
+    verbose!("Calling the system program to assign the account...");
+    let instruction = system_instruction::assign(account.key, program_id);
+    invoke_signed(&instruction, account_infos, &[signer_seeds])
+}
+
+/// Tops `account` up to `lamports` from `payer`, through the system program.
+pub fn top_up<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    lamports: u64,
+) -> ProgramResult {
+    let top_up = lamports.saturating_sub(account.lamports());
+    if top_up == 0 {
+        return Ok(());
+    }
+
+    verbose!("Calling the system program to top up rent...");
+    let instruction = system_instruction::transfer(payer.key, account.key, top_up);
+    invoke(
+        &instruction,
+        &[payer.clone(), account.clone(), system_program.clone()],
+    )
+}
+
+/// Moves lamports out of an account owned by this program.
+pub fn transfer_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    **from.lamports.borrow_mut() = from
+        .lamports()
+        .checked_sub(amount)
+        .ok_or(EscrowError::InsufficientFunds)?;
+    **to.lamports.borrow_mut() = to
+        .lamports()
+        .checked_add(amount)
+        .ok_or(EscrowError::AmountOverflow)?;
+
+    Ok(())
+}
+
+/// Drains an account owned by this program into `destination` and wipes its data.
+pub fn close_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+    transfer_lamports(account, destination, account.lamports())?;
+    *account.try_borrow_mut_data()? = &mut [];
+
+    Ok(())
+}
@@ -0,0 +1,12 @@
+/// `msg!` for step by step progress and debugging output. Compiled out
+/// unless the `verbose-logs` feature is enabled: every log line costs
+/// compute units, and events already tell clients what an instruction did.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "verbose-logs") {
+            solana_program::msg!($($arg)*);
+        }
+    };
+}
+
+pub(crate) use verbose;
@@ -0,0 +1,101 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke,
+    program_error::ProgramError,
+};
+
+use crate::utils::{
+    constants::{MAX_MEMO_LEN, REFERENCE_LEN},
+    errors::EscrowError,
+};
+
+/// External reference data attached to a token escrow, so it can be matched
+/// to an invoice or order.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct EscrowMemo {
+    /// Order ID or hash, stored as all zeroes when `None`.
+    pub reference: Option<[u8; REFERENCE_LEN]>,
+    /// UTF-8 text of at most `MAX_MEMO_LEN` bytes.
+    pub memo: String,
+    /// Also log `text` through the SPL Memo program on init and release, so
+    /// it shows up in explorers.
+    pub log_memo: bool,
+}
+
+impl EscrowMemo {
+    /// Bytes before the memo text: reference, `log_memo` and the text length.
+    pub const HEADER_LEN: usize = REFERENCE_LEN + 2;
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn pack(&self) -> Vec<u8> {
+        let mut dst = Vec::with_capacity(Self::HEADER_LEN + self.memo.len());
+        dst.extend_from_slice(&self.reference.unwrap_or_default());
+        dst.push(self.log_memo as u8);
+        dst.push(self.memo.len() as u8);
+        dst.extend_from_slice(self.memo.as_bytes());
+        dst
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let header = input
+            .get(..Self::HEADER_LEN)
+            .ok_or(EscrowError::InvalidInstruction)?;
+        let rest = &input[Self::HEADER_LEN..];
+        let log_memo = match header[REFERENCE_LEN] {
+            0 => false,
+            1 => true,
+            _ => return Err(EscrowError::InvalidInstruction.into()),
+        };
+        let memo = rest
+            .get(..header[REFERENCE_LEN + 1] as usize)
+            .ok_or(EscrowError::InvalidInstruction)?;
+        Ok(EscrowMemo {
+            reference: reference_from_bytes(&header[..REFERENCE_LEN]),
+            memo: String::from_utf8(memo.to_vec()).map_err(|_| EscrowError::InvalidMemo)?,
+            log_memo,
+        })
+    }
+
+    /// Checks that the memo fits the escrow and that there is something to
+    /// log when `log_memo` is set.
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        if self.memo.len() > MAX_MEMO_LEN || (self.log_memo && self.text().is_empty()) {
+            return Err(EscrowError::InvalidMemo.into());
+        }
+        Ok(())
+    }
+
+    /// What gets logged through SPL Memo: the hex reference, then the memo.
+    pub fn text(&self) -> String {
+        self.reference
+            .map(|reference| to_hex(&reference))
+            .into_iter()
+            .chain((!self.memo.is_empty()).then(|| self.memo.clone()))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// `None` for the all zero reference of escrows without one.
+pub fn reference_from_bytes(bytes: &[u8]) -> Option<[u8; REFERENCE_LEN]> {
+    let reference: [u8; REFERENCE_LEN] = bytes.try_into().ok()?;
+    (reference != [0; REFERENCE_LEN]).then_some(reference)
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Logs `memo` through the SPL Memo program, without signers.
+pub fn log(memo_program: &AccountInfo, memo: &EscrowMemo) -> ProgramResult {
+    if *memo_program.key != spl_memo::id() {
+        return Err(EscrowError::InvalidMemoProgram.into());
+    }
+    invoke(
+        &spl_memo::build_memo(memo.text().as_bytes(), &[]),
+        std::slice::from_ref(memo_program),
+    )
+}
@@ -1,7 +1,16 @@
 pub mod errors;
 pub mod constants;
 pub mod token_lib;
+pub mod lamport_lib;
+pub mod validation;
+pub mod accounts;
+pub mod voucher;
+pub mod logging;
+pub mod time_basis;
+pub mod memo;
+pub mod user_profile;
 
 pub use errors::*;
 pub use constants::*;
-pub use token_lib::*;
\ No newline at end of file
+pub use token_lib::*;
+pub use lamport_lib::*;
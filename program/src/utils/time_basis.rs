@@ -0,0 +1,64 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::clock::Clock;
+
+use crate::utils::constants::{
+    ESCROW_RELEASE_WINDOW_EPOCHS, ESCROW_RELEASE_WINDOW_SEC, ESCROW_RELEASE_WINDOW_SLOTS,
+    ESCROW_WAIT_EPOCHS, ESCROW_WAIT_SLOTS, ESCROW_WAIT_TIME_SEC,
+};
+
+/// The `Clock` field an escrow's `expire_date` is compared against. Validators
+/// can skew `unix_timestamp`, slots and epochs only move forward at the pace
+/// of the cluster.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeBasis {
+    #[default]
+    UnixTimestamp,
+    Slot,
+    Epoch,
+}
+
+impl TimeBasis {
+    pub const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(TimeBasis::UnixTimestamp),
+            1 => Some(TimeBasis::Slot),
+            2 => Some(TimeBasis::Epoch),
+            _ => None,
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            TimeBasis::UnixTimestamp => "unix_timestamp",
+            TimeBasis::Slot => "slot",
+            TimeBasis::Epoch => "epoch",
+        }
+    }
+
+    /// The current time in this basis.
+    pub fn now(self, clock: &Clock) -> i64 {
+        match self {
+            TimeBasis::UnixTimestamp => clock.unix_timestamp,
+            TimeBasis::Slot => clock.slot as i64,
+            TimeBasis::Epoch => clock.epoch as i64,
+        }
+    }
+
+    /// How long a new escrow waits before it matures, in this basis.
+    pub const fn wait_time(self) -> i64 {
+        match self {
+            TimeBasis::UnixTimestamp => ESCROW_WAIT_TIME_SEC,
+            TimeBasis::Slot => ESCROW_WAIT_SLOTS,
+            TimeBasis::Epoch => ESCROW_WAIT_EPOCHS,
+        }
+    }
+
+    /// How long a new escrow can be released once matured, in this basis.
+    pub const fn release_window(self) -> i64 {
+        match self {
+            TimeBasis::UnixTimestamp => ESCROW_RELEASE_WINDOW_SEC,
+            TimeBasis::Slot => ESCROW_RELEASE_WINDOW_SLOTS,
+            TimeBasis::Epoch => ESCROW_RELEASE_WINDOW_EPOCHS,
+        }
+    }
+}
@@ -1,11 +1,121 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke,
-    program::invoke_signed, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint::ProgramResult, program::invoke,
+    program::invoke_signed, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
 };
 
-use spl_token::instruction::AuthorityType;
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{TransferFeeAmount, TransferFeeConfig},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    instruction::AuthorityType,
+    state::{Account, Mint},
+};
+
+use crate::utils::{errors::EscrowError, lamport_lib, logging::verbose, validation};
+
+/// Mint extensions that let someone other than the escrow PDA move or lock
+/// the tokens held in custody.
+const UNSUPPORTED_MINT_EXTENSIONS: &[ExtensionType] = &[
+    ExtensionType::PermanentDelegate,
+    ExtensionType::NonTransferable,
+];
+
+/// Unpacks a token account owned by `token_program_account`, extensions included.
+pub fn unpack_token_account(
+    token_program_account: &AccountInfo,
+    token_account: &AccountInfo,
+) -> Result<Account, ProgramError> {
+    validation::assert_owned_by(token_account, token_program_account.key)?;
+    let data = token_account.try_borrow_data()?;
+    Ok(StateWithExtensions::<Account>::unpack(&data)?.base)
+}
+
+/// Unpacks the mint, rejects custody breaking extensions and returns its decimals.
+pub fn check_mint(
+    token_program_account: &AccountInfo,
+    mint_account: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    validation::assert_owned_by(mint_account, token_program_account.key)?;
+    let data = mint_account.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    if mint
+        .get_extension_types()?
+        .iter()
+        .any(|extension| UNSUPPORTED_MINT_EXTENSIONS.contains(extension))
+    {
+        return Err(EscrowError::UnsupportedMintExtension.into());
+    }
+
+    Ok(mint.base.decimals)
+}
+
+/// Fee the mint withholds when `amount` is transferred in the current epoch.
+pub fn transfer_fee(mint_account: &AccountInfo, amount: u64) -> Result<u64, ProgramError> {
+    let data = mint_account.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(validation::clock()?.epoch, amount)
+            .ok_or(EscrowError::AmountOverflow)?,
+        Err(_) => 0,
+    };
+
+    Ok(fee)
+}
+
+/// Creates a token account of `mint_account` at a PDA, with `owner` as its
+/// authority and room for the account extensions the mint requires.
+#[allow(clippy::too_many_arguments)]
+pub fn create_pda_token_account<'a>(
+    payer: AccountInfo<'a>,
+    token_account: AccountInfo<'a>,
+    mint_account: AccountInfo<'a>,
+    token_program_account: AccountInfo<'a>,
+    system_program: AccountInfo<'a>,
+    owner: &Pubkey,
+    rent: &Rent,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let space = {
+        let data = mint_account.try_borrow_data()?;
+        let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        let extensions =
+            ExtensionType::get_required_init_account_extensions(&mint.get_extension_types()?);
+        ExtensionType::try_calculate_account_len::<Account>(&extensions)?
+    };
+    lamport_lib::create_pda_account(
+        payer,
+        token_account.clone(),
+        system_program,
+        rent.minimum_balance(space),
+        space,
+        token_program_account.key,
+        signer_seeds,
+    )?;
 
-use crate::utils::constants;
+    let instruction = spl_token_2022::instruction::initialize_account3(
+        token_program_account.key,
+        token_account.key,
+        mint_account.key,
+        owner,
+    )?;
+    verbose!("Calling the token program to initialize the token account...");
+    invoke(&instruction, &[token_account, mint_account])
+}
+
+/// Checks the vault still holds at least what the deposit ledger says it
+/// does. Tokens sent to the vault outside of `Deposit` are never credited.
+pub fn check_vault_balance(
+    token_program: &AccountInfo,
+    vault: &AccountInfo,
+    total_deposits: u64,
+) -> ProgramResult {
+    if unpack_token_account(token_program, vault)?.amount < total_deposits {
+        return Err(EscrowError::LedgerMismatch.into());
+    }
+    Ok(())
+}
 
 pub fn change_account_authority(
     token_program_account: AccountInfo,
@@ -15,44 +125,105 @@ pub fn change_account_authority(
     owner_account: AccountInfo,
     account_infos: &[AccountInfo],
 ) -> ProgramResult {
-    let instruction = spl_token::instruction::set_authority(
+    let instruction = spl_token_2022::instruction::set_authority(
         token_program_account.key,
         sender_token_account.key,
         Some(&new_authority_pubkey),
         authority_type,
         owner_account.key,
-        &[&owner_account.key],
+        &[owner_account.key],
     )?;
 
-    msg!("Calling the token program to transfer token account ownership...");
+    verbose!("Calling the token program to transfer token account ownership...");
     invoke(&instruction, account_infos)?;
 
     Ok(())
 }
 
-pub fn transfer_tokens(
-    token_program_account: AccountInfo,
-    sender_token_account: AccountInfo,
-    receiver_token_account: AccountInfo,
-    authority_pubkey: Pubkey,
+/// Transfers with `transfer_checked`, out of a PDA owned token account or,
+/// with empty `signer_seeds`, one whose authority signed the transaction.
+/// `additional_accounts` carries the extra accounts a transfer hook needs.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_tokens<'a>(
+    token_program_account: AccountInfo<'a>,
+    sender_token_account: AccountInfo<'a>,
+    mint_account: AccountInfo<'a>,
+    receiver_token_account: AccountInfo<'a>,
+    authority_account: AccountInfo<'a>,
+    additional_accounts: &[AccountInfo<'a>],
     amount: u64,
-    account_infos: &[AccountInfo],
-    bump_seed: u8,
+    decimals: u8,
+    signer_seeds: &[&[u8]],
 ) -> ProgramResult {
-    let instruction = spl_token::instruction::transfer(
-        &token_program_account.key,
-        &sender_token_account.key,
-        &receiver_token_account.key,
-        &authority_pubkey,
-        &[&authority_pubkey],
+    verbose!("Calling the token program to transfer tokens...");
+    let signers: &[&[&[u8]]] = if signer_seeds.is_empty() { &[] } else { &[signer_seeds] };
+    spl_token_2022::onchain::invoke_transfer_checked(
+        token_program_account.key,
+        sender_token_account,
+        mint_account,
+        receiver_token_account,
+        authority_account,
+        additional_accounts,
         amount,
+        decimals,
+        signers,
+    )
+}
+
+/// Moves fees withheld in `token_account` to the mint. Token-2022 refuses to
+/// close an account that still holds withheld fees.
+pub fn harvest_withheld_fees<'a>(
+    token_program_account: AccountInfo<'a>,
+    mint_account: AccountInfo<'a>,
+    token_account: AccountInfo<'a>,
+) -> ProgramResult {
+    let withheld_amount = {
+        let data = token_account.try_borrow_data()?;
+        let account = StateWithExtensions::<Account>::unpack(&data)?;
+        account
+            .get_extension::<TransferFeeAmount>()
+            .map(|extension| u64::from(extension.withheld_amount))
+            .unwrap_or(0)
+    };
+    if withheld_amount == 0 {
+        return Ok(());
+    }
+
+    let instruction = spl_token_2022::extension::transfer_fee::instruction::harvest_withheld_tokens_to_mint(
+        token_program_account.key,
+        mint_account.key,
+        &[token_account.key],
     )?;
-    msg!("Calling the token program to transfer tokens to the taker...");
-    invoke_signed(
+    verbose!("Calling the token program to harvest withheld fees...");
+    invoke(
         &instruction,
-        account_infos,
-        &[&[&constants::ESCROW_SEED[..], &[bump_seed]]],
-    )?;
+        &[mint_account, token_account, token_program_account],
+    )
+}
 
-    Ok(())
+pub fn close_token_account<'a>(
+    token_program_account: AccountInfo<'a>,
+    token_account: AccountInfo<'a>,
+    destination_account: AccountInfo<'a>,
+    authority_account: AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let instruction = spl_token_2022::instruction::close_account(
+        token_program_account.key,
+        token_account.key,
+        destination_account.key,
+        authority_account.key,
+        &[],
+    )?;
+    verbose!("Calling the token program to close pda's temp account...");
+    invoke_signed(
+        &instruction,
+        &[
+            token_account,
+            destination_account,
+            authority_account,
+            token_program_account,
+        ],
+        &[signer_seeds],
+    )
 }
@@ -0,0 +1,116 @@
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_error::ProgramError,
+    program_pack::{IsInitialized, Pack}, pubkey::Pubkey, rent::Rent,
+};
+
+use crate::{
+    states::{program_config::ProgramConfigState, user_profile::UserProfileState},
+    utils::{constants, errors::EscrowError, lamport_lib, logging::verbose, validation},
+};
+
+/// Counts a new escrow of `amount` tokens on the profile of `initializer`,
+/// creating the profile with its first escrow, up to the cap `program_config`
+/// sets. Returns the escrow's index, its address seed.
+#[allow(clippy::too_many_arguments)]
+pub fn open_escrow<'a>(
+    user_profile: &AccountInfo<'a>,
+    initializer: &Pubkey,
+    program_config: &AccountInfo<'a>,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    rent: &Rent,
+    amount: u64,
+    program_id: &Pubkey,
+) -> Result<u64, ProgramError> {
+    validation::assert_pda(program_config, &[constants::PROGRAM_CONFIG_SEED], program_id)?;
+    let config = ProgramConfigState::unpack(&program_config.try_borrow_data()?)?;
+    let bump_seed = assert_profile(user_profile, initializer, program_id)?;
+    if user_profile.data_is_empty() {
+        verbose!("Creating the user profile...");
+        lamport_lib::create_pda_account(
+            payer.clone(),
+            user_profile.clone(),
+            system_program.clone(),
+            rent.minimum_balance(UserProfileState::LEN),
+            UserProfileState::LEN,
+            program_id,
+            &[constants::USER_PROFILE_SEED, initializer.as_ref(), &[bump_seed]],
+        )?;
+    }
+    validation::assert_owned_by(user_profile, program_id)?;
+
+    let mut profile = UserProfileState::unpack_unchecked(&user_profile.try_borrow_data()?)?;
+    if !profile.is_initialized() {
+        profile.is_initialized = true;
+        profile.initializer_pubkey = *initializer;
+    }
+    if profile.open_escrows >= config.max_open_escrows_per_user {
+        return Err(EscrowError::TooManyOpenEscrows.into());
+    }
+    profile.escrow_count = profile
+        .escrow_count
+        .checked_add(1)
+        .ok_or(EscrowError::AmountOverflow)?;
+    profile.open_escrows += 1;
+    profile.total_volume = profile.total_volume.saturating_add(amount);
+    let escrow_index = profile.escrow_count;
+    UserProfileState::pack(profile, &mut user_profile.try_borrow_mut_data()?)?;
+
+    Ok(escrow_index)
+}
+
+/// Adds `amount` to the volume of the profile counting the escrow at
+/// `escrow_index`, escrows created before profiles have none.
+pub fn add_volume(
+    user_profile: &AccountInfo,
+    initializer: &Pubkey,
+    escrow_index: u64,
+    amount: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    update(user_profile, initializer, escrow_index, program_id, |profile| {
+        profile.total_volume = profile.total_volume.saturating_add(amount);
+    })
+}
+
+/// Uncounts the escrow at `escrow_index` from the open escrows of its
+/// profile, once it is released or refunded.
+pub fn close_escrow(
+    user_profile: &AccountInfo,
+    initializer: &Pubkey,
+    escrow_index: u64,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    update(user_profile, initializer, escrow_index, program_id, |profile| {
+        profile.open_escrows = profile.open_escrows.saturating_sub(1);
+    })
+}
+
+fn update(
+    user_profile: &AccountInfo,
+    initializer: &Pubkey,
+    escrow_index: u64,
+    program_id: &Pubkey,
+    apply: impl FnOnce(&mut UserProfileState),
+) -> ProgramResult {
+    if escrow_index == 0 {
+        return Ok(());
+    }
+    assert_profile(user_profile, initializer, program_id)?;
+    validation::assert_owned_by(user_profile, program_id)?;
+    let mut profile = UserProfileState::unpack(&user_profile.try_borrow_data()?)?;
+    apply(&mut profile);
+    UserProfileState::pack(profile, &mut user_profile.try_borrow_mut_data()?)
+}
+
+fn assert_profile(
+    user_profile: &AccountInfo,
+    initializer: &Pubkey,
+    program_id: &Pubkey,
+) -> Result<u8, ProgramError> {
+    Ok(validation::assert_pda(
+        user_profile,
+        &[constants::USER_PROFILE_SEED, initializer.as_ref()],
+        program_id,
+    )?)
+}
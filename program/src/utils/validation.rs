@@ -0,0 +1,99 @@
+use arrayref::{array_ref, array_refs};
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::{self, Sysvar},
+};
+
+use crate::utils::errors::EscrowError;
+
+pub fn assert_signer(account: &AccountInfo) -> ProgramResult {
+    if !account.is_signer {
+        return Err(EscrowError::AccountNotSigner.into());
+    }
+    Ok(())
+}
+
+pub fn assert_writable(account: &AccountInfo) -> ProgramResult {
+    if !account.is_writable {
+        return Err(EscrowError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+pub fn assert_owned_by(account: &AccountInfo, owner: &Pubkey) -> ProgramResult {
+    if account.owner != owner {
+        return Err(EscrowError::InvalidAccountOwner.into());
+    }
+    Ok(())
+}
+
+/// Accepts both SPL Token and Token-2022.
+pub fn assert_token_program(account: &AccountInfo) -> ProgramResult {
+    if *account.key != spl_token::id() && *account.key != spl_token_2022::id() {
+        return Err(EscrowError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+pub fn assert_system_program(account: &AccountInfo) -> ProgramResult {
+    if !system_program::check_id(account.key) {
+        return Err(EscrowError::InvalidSystemProgram.into());
+    }
+    Ok(())
+}
+
+pub fn assert_rent_sysvar(account: &AccountInfo) -> ProgramResult {
+    if !sysvar::rent::check_id(account.key) {
+        return Err(EscrowError::InvalidSysvar.into());
+    }
+    Ok(())
+}
+
+/// The clock sysvar, as `ClockUnavailable` when the runtime cannot provide
+/// it.
+pub fn clock() -> Result<Clock, EscrowError> {
+    Clock::get().map_err(|_| EscrowError::ClockUnavailable)
+}
+
+/// Derives the PDA for `seeds`, checks `account` sits at that address and
+/// returns the bump seed.
+pub fn assert_pda(account: &AccountInfo, seeds: &[&[u8]], program_id: &Pubkey) -> Result<u8, EscrowError> {
+    let (pda, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+    if pda != *account.key {
+        return Err(EscrowError::InvalidPda);
+    }
+    Ok(bump_seed)
+}
+
+/// Checks `authority` signed and is the upgrade authority recorded in the
+/// program data account of `program_id`.
+pub fn assert_upgrade_authority(
+    program_data: &AccountInfo,
+    authority: &AccountInfo,
+    program_id: &Pubkey,
+) -> ProgramResult {
+    assert_signer(authority)?;
+    assert_pda(program_data, &[program_id.as_ref()], &bpf_loader_upgradeable::id())?;
+    assert_owned_by(program_data, &bpf_loader_upgradeable::id())?;
+
+    // Bincode `UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }`.
+    const METADATA_LEN: usize = UpgradeableLoaderState::size_of_programdata_metadata();
+    let data = program_data.try_borrow_data()?;
+    if data.len() < METADATA_LEN {
+        return Err(EscrowError::AccountTooSmall.into());
+    }
+    let metadata = array_ref![data, 0, METADATA_LEN];
+    let (tag, _slot, has_authority, upgrade_authority) = array_refs![metadata, 4, 8, 1, 32];
+    if u32::from_le_bytes(*tag) != 3 {
+        return Err(EscrowError::InvalidProgramData.into());
+    }
+    if *has_authority != [1] || upgrade_authority != authority.key.as_ref() {
+        return Err(EscrowError::InvalidUpgradeAuthority.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use arrayref::{array_ref, array_refs};
+use solana_program::{
+    account_info::AccountInfo,
+    ed25519_program,
+    program_error::ProgramError,
+    pubkey::{Pubkey, PUBKEY_BYTES},
+    sysvar::instructions,
+};
+
+use crate::utils::errors::EscrowError;
+
+/// Prefix of every signed voucher message, so a voucher signature can't be
+/// replayed as any other message signed by the same key.
+pub const VOUCHER_DOMAIN: &[u8] = b"solana-escrow:voucher:v1";
+pub const VOUCHER_LEN: usize = 88;
+
+/// Size of the offsets header of the Ed25519 precompile instruction data.
+const ED25519_OFFSETS_START: usize = 2;
+const ED25519_OFFSETS_LEN: usize = 14;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// An off-chain approval to release `amount` of `escrow` to `recipient`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Voucher {
+    pub escrow: Pubkey,
+    pub amount: u64,
+    /// Owner of the token account receiving the funds
+    pub recipient: Pubkey,
+    /// Must be above the nonce of the last voucher used on the escrow
+    pub nonce: u64,
+    /// Unix timestamp after which the voucher is rejected
+    pub expiry: i64,
+}
+
+impl Voucher {
+    pub fn pack(&self) -> [u8; VOUCHER_LEN] {
+        let mut dst = [0u8; VOUCHER_LEN];
+        dst[..32].copy_from_slice(self.escrow.as_ref());
+        dst[32..40].copy_from_slice(&self.amount.to_le_bytes());
+        dst[40..72].copy_from_slice(self.recipient.as_ref());
+        dst[72..80].copy_from_slice(&self.nonce.to_le_bytes());
+        dst[80..].copy_from_slice(&self.expiry.to_le_bytes());
+        dst
+    }
+
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let src = input
+            .get(..VOUCHER_LEN)
+            .ok_or(EscrowError::InvalidInstruction)?;
+        let src = array_ref![src, 0, VOUCHER_LEN];
+        let (escrow, amount, recipient, nonce, expiry) = array_refs![src, 32, 8, 32, 8, 8];
+        Ok(Voucher {
+            escrow: Pubkey::new_from_array(*escrow),
+            amount: u64::from_le_bytes(*amount),
+            recipient: Pubkey::new_from_array(*recipient),
+            nonce: u64::from_le_bytes(*nonce),
+            expiry: i64::from_le_bytes(*expiry),
+        })
+    }
+
+    /// The bytes the approver signs.
+    pub fn message(&self) -> Vec<u8> {
+        [VOUCHER_DOMAIN, &self.pack()].concat()
+    }
+}
+
+/// Returns the key whose signature over `message` is checked by the Ed25519
+/// precompile instruction right before the current instruction.
+///
+/// The runtime rejects the transaction if that signature is invalid, so only
+/// the key and the message it covers are read here.
+pub fn ed25519_signer(
+    instructions_sysvar: &AccountInfo,
+    message: &[u8],
+) -> Result<Pubkey, ProgramError> {
+    if !instructions::check_id(instructions_sysvar.key) {
+        return Err(EscrowError::InvalidSysvar.into());
+    }
+    let current = instructions::load_current_index_checked(instructions_sysvar)?;
+    let index = current.checked_sub(1).ok_or(EscrowError::InvalidVoucher)?;
+    let verify = instructions::load_instruction_at_checked(index as usize, instructions_sysvar)?;
+    if verify.program_id != ed25519_program::id() {
+        return Err(EscrowError::InvalidVoucher.into());
+    }
+
+    let data = verify.data.as_slice();
+    if data.len() < ED25519_OFFSETS_START + ED25519_OFFSETS_LEN || data[0] != 1 {
+        return Err(EscrowError::InvalidVoucher.into());
+    }
+    let offsets = array_ref![data, ED25519_OFFSETS_START, ED25519_OFFSETS_LEN];
+    let (
+        signature_offset,
+        signature_index,
+        public_key_offset,
+        public_key_index,
+        message_offset,
+        message_size,
+        message_index,
+    ) = array_refs![offsets, 2, 2, 2, 2, 2, 2, 2];
+    // Every part must come from the precompile instruction itself, otherwise
+    // the signature checked could cover data other than what is read here.
+    if [signature_index, public_key_index, message_index]
+        .iter()
+        .any(|index| u16::from_le_bytes(**index) != u16::MAX)
+    {
+        return Err(EscrowError::InvalidVoucher.into());
+    }
+
+    let slice = |offset: &[u8; 2], len: usize| {
+        let start = u16::from_le_bytes(*offset) as usize;
+        data.get(start..start + len).ok_or(EscrowError::InvalidVoucher)
+    };
+    slice(signature_offset, ED25519_SIGNATURE_LEN)?;
+    let public_key = slice(public_key_offset, PUBKEY_BYTES)?;
+    if slice(message_offset, u16::from_le_bytes(*message_size) as usize)? != message {
+        return Err(EscrowError::InvalidVoucher.into());
+    }
+    Ok(Pubkey::try_from(public_key).map_err(|_| EscrowError::InvalidVoucher)?)
+}
@@ -0,0 +1,346 @@
+mod common;
+
+use common::{assert_escrow_error, assert_instruction_error, Harness, SolEscrow, TokenEscrow};
+use solana_escrow::{
+    instruction,
+    states::{
+        default_escrow::EscrowState, escrow_registry::EscrowRegistryState, layout::AccountLayout,
+        program_config::ProgramConfigState, sol_escrow::SolEscrowState,
+        user_profile::UserProfileState,
+    },
+    utils::{constants, errors::EscrowError, voucher::Voucher},
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    instruction::InstructionError, program_pack::Pack, pubkey::Pubkey, signature::Keypair,
+    signer::Signer,
+};
+
+/// A token escrow as written before the layout header existed.
+fn legacy_escrow(
+    caller: &Pubkey,
+    initializer: &Pubkey,
+    receiver: &Pubkey,
+    temp_token_account: &Pubkey,
+    amount: u64,
+) -> Vec<u8> {
+    let mut data = vec![1];
+    data.extend_from_slice(caller.as_ref());
+    data.extend_from_slice(initializer.as_ref());
+    data.extend_from_slice(receiver.as_ref());
+    data.extend_from_slice(temp_token_account.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    assert_eq!(data.len(), constants::LEGACY_DEFAULT_ESCROW_STATE_LEN);
+    data
+}
+
+#[tokio::test]
+async fn migrate_rewrites_a_legacy_escrow_once() {
+    let mut harness = Harness::start().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let escrow = Pubkey::new_unique();
+    let (initializer, receiver) = (Pubkey::new_unique(), Pubkey::new_unique());
+    harness.set_program_account(&escrow, legacy_escrow(&Pubkey::new_unique(), &initializer, &receiver, &Pubkey::new_unique(), 42));
+
+    let migrate =
+        instruction::migrate_account(&harness.program_id, &authority.pubkey(), &harness.caller(), &escrow);
+    harness.process(std::slice::from_ref(&migrate), &[&authority]).await.unwrap();
+
+    let account = harness.account(&escrow).await.unwrap();
+    assert_eq!(account.data.len(), EscrowState::LEN);
+    assert_eq!(account.data[..2], [EscrowState::DISCRIMINATOR, EscrowState::VERSION]);
+    let state = EscrowState::unpack(&account.data).unwrap();
+    assert_eq!(state.initializer_pubkey, initializer);
+    assert_eq!(state.receiver_pubkey, receiver);
+    assert_eq!(state.escrow_amount, 42);
+    assert_eq!(state.voucher_nonce, 0);
+
+    // Migrating a current account does nothing.
+    harness.next_blockhash().await;
+    harness.process(&[migrate], &[&authority]).await.unwrap();
+    assert_eq!(harness.account(&escrow).await.unwrap().data, account.data);
+}
+
+#[tokio::test]
+async fn migrate_grows_a_sol_escrow_without_a_release_window() {
+    let mut harness = Harness::start().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let escrow = SolEscrow::create(&mut harness, 1_000_000_000).await;
+    let state = escrow.state(&mut harness).await;
+    // Version 1 ended before `refund_after`.
+    let mut account = harness.account(&escrow.escrow).await.unwrap();
+    account.data.truncate(SolEscrowState::LEN - 8);
+    account.data[1] = 1;
+    harness.set_account(&escrow.escrow, account);
+
+    let migrate =
+        instruction::migrate_account(&harness.program_id, &authority.pubkey(), &harness.caller(), &escrow.escrow);
+    harness.process(&[migrate], &[&authority]).await.unwrap();
+
+    let account = harness.account(&escrow.escrow).await.unwrap();
+    assert_eq!(account.data.len(), SolEscrowState::LEN);
+    assert_eq!(account.data[..2], [SolEscrowState::DISCRIMINATOR, SolEscrowState::VERSION]);
+    let migrated = SolEscrowState::unpack(&account.data).unwrap();
+    assert_eq!(migrated, SolEscrowState { refund_after: 0, ..state });
+    // Without a window it stays releasable.
+    assert!(!migrated.refund_due(i64::MAX));
+}
+
+#[tokio::test]
+async fn migrate_grows_a_user_profile_without_disputes() {
+    let mut harness = Harness::start().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let user_profile = escrow.user_profile(&harness);
+    let profile = escrow.profile(&mut harness).await;
+    // Version 1 ended before `disputes`.
+    let mut data = harness.account(&user_profile).await.unwrap().data;
+    data.truncate(UserProfileState::LEN - 8);
+    data[1] = 1;
+    harness.set_program_account(&user_profile, data);
+
+    let migrate =
+        instruction::migrate_account(&harness.program_id, &authority.pubkey(), &harness.caller(), &user_profile);
+    harness.process(&[migrate], &[&authority]).await.unwrap();
+
+    let account = harness.account(&user_profile).await.unwrap();
+    assert_eq!(account.data.len(), UserProfileState::LEN);
+    assert_eq!(UserProfileState::unpack(&account.data).unwrap(), profile);
+}
+
+#[tokio::test]
+async fn set_config_creates_the_config_then_changes_it() {
+    let mut harness = Harness::start_unconfigured().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let (program_config, _) = instruction::find_program_config(&harness.program_id);
+
+    let set_config = instruction::set_config(&harness.program_id, &authority.pubkey(), &harness.caller(), 4);
+    harness.process(&[set_config], &[&authority]).await.unwrap();
+    let config: ProgramConfigState = harness.state(&program_config).await;
+    assert_eq!(config.max_open_escrows_per_user, 4);
+
+    let set_config = instruction::set_config(&harness.program_id, &authority.pubkey(), &harness.caller(), 1);
+    harness.process(&[set_config], &[&authority]).await.unwrap();
+    let config: ProgramConfigState = harness.state(&program_config).await;
+    assert_eq!(config.max_open_escrows_per_user, 1);
+
+    // The initializer's second escrow goes over the new cap.
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let caller = harness.caller();
+    let temp = harness
+        .create_token_account(&escrow.token_program, &escrow.mint, &caller)
+        .await;
+    harness.mint_to(&escrow.token_program, &escrow.mint, &temp, 50).await;
+    let mut init = escrow.init(&harness, 50);
+    init.accounts[2].pubkey = temp;
+    init.accounts[3].pubkey = escrow.next_escrow(&mut harness).await;
+    let result = harness.process(&[init], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::TooManyOpenEscrows);
+}
+
+#[tokio::test]
+async fn set_config_by_another_signer_fails() {
+    let mut harness = Harness::start().await;
+    harness.set_upgrade_authority(&Pubkey::new_unique());
+
+    let impostor = Keypair::new();
+    let set_config = instruction::set_config(&harness.program_id, &impostor.pubkey(), &harness.caller(), 1_000);
+    let result = harness.process(&[set_config], &[&impostor]).await;
+    assert_escrow_error(result, EscrowError::InvalidUpgradeAuthority);
+}
+
+#[tokio::test]
+async fn set_config_without_open_escrows_fails() {
+    let mut harness = Harness::start().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+
+    let set_config = instruction::set_config(&harness.program_id, &authority.pubkey(), &harness.caller(), 0);
+    let result = harness.process(&[set_config], &[&authority]).await;
+    assert_instruction_error(result, InstructionError::InvalidArgument);
+}
+
+/// A legacy escrow of 42 tokens to `receiver` paid from a funded temp
+/// account, with the token accounts of its initializer and receiver.
+async fn fund_legacy_escrow(
+    harness: &mut Harness,
+    receiver: &Pubkey,
+) -> (Pubkey, Pubkey, Pubkey, Pubkey, Pubkey, Pubkey) {
+    let token_program = spl_token::id();
+    let mint = harness.create_mint(&token_program).await;
+    let pda = instruction::find_escrow_authority(&harness.program_id).0;
+    let temp_token_account = harness.create_token_account(&token_program, &mint, &pda).await;
+    harness.mint_to(&token_program, &mint, &temp_token_account, 42).await;
+    let initializer = Pubkey::new_unique();
+    let initializer_token_account = harness.create_token_account(&token_program, &mint, &initializer).await;
+    let receiver_token_account = harness.create_token_account(&token_program, &mint, receiver).await;
+    let escrow = Pubkey::new_unique();
+    let caller = harness.caller();
+    harness.set_program_account(
+        &escrow,
+        legacy_escrow(&caller, &initializer, receiver, &temp_token_account, 42),
+    );
+    (escrow, mint, temp_token_account, initializer, initializer_token_account, receiver_token_account)
+}
+
+#[tokio::test]
+async fn legacy_escrow_is_released_without_migration() {
+    let mut harness = Harness::start().await;
+    let (escrow, mint, temp_token_account, initializer, initializer_token_account, receiver_token_account) =
+        fund_legacy_escrow(&mut harness, &Pubkey::new_unique()).await;
+
+    let release = instruction::release_escrow(
+        &harness.program_id,
+        &receiver_token_account,
+        &temp_token_account,
+        &initializer,
+        &initializer_token_account,
+        &escrow,
+        &spl_token::id(),
+        &harness.caller(),
+        &mint,
+    );
+    harness.process(&[release], &[]).await.unwrap();
+
+    assert_eq!(harness.token_balance(&receiver_token_account).await, 42);
+    assert!(harness.account(&escrow).await.is_none());
+    assert!(harness.account(&temp_token_account).await.is_none());
+}
+
+#[tokio::test]
+async fn legacy_escrow_is_not_released_in_part() {
+    let mut harness = Harness::start().await;
+    let receiver = Keypair::new();
+    let (escrow, mint, temp_token_account, initializer, initializer_token_account, receiver_token_account) =
+        fund_legacy_escrow(&mut harness, &receiver.pubkey()).await;
+    let voucher = Voucher {
+        escrow,
+        amount: 40,
+        recipient: receiver.pubkey(),
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+    let signature: [u8; 64] = receiver.sign_message(&voucher.message()).into();
+
+    // The 145 bytes have no room for the voucher nonce and what is left.
+    let release = [
+        instruction::ed25519_verify(&receiver.pubkey(), &signature, &voucher),
+        instruction::release_with_voucher(
+            &harness.program_id,
+            &receiver_token_account,
+            &temp_token_account,
+            &initializer,
+            &initializer_token_account,
+            &escrow,
+            &spl_token::id(),
+            &harness.caller(),
+            &mint,
+            voucher,
+        ),
+    ];
+    let result = harness.process(&release, &[]).await;
+    assert_escrow_error(result, EscrowError::AccountTooSmall);
+    assert_eq!(harness.token_balance(&temp_token_account).await, 42);
+}
+
+#[tokio::test]
+async fn legacy_escrow_is_not_refunded() {
+    let mut harness = Harness::start().await;
+    let (escrow, mint, temp_token_account, initializer, initializer_token_account, _) =
+        fund_legacy_escrow(&mut harness, &Pubkey::new_unique()).await;
+
+    let refund = instruction::refund_escrow(
+        &harness.program_id,
+        &initializer,
+        &initializer_token_account,
+        &temp_token_account,
+        &escrow,
+        &spl_token::id(),
+        &harness.caller(),
+        &mint,
+    );
+    let result = harness.process(&[refund], &[]).await;
+    assert_escrow_error(result, EscrowError::AccountTooSmall);
+    assert_eq!(harness.token_balance(&temp_token_account).await, 42);
+}
+
+#[tokio::test]
+async fn migrate_grows_a_legacy_registry() {
+    let mut harness = Harness::start().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let registry = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut data = vec![1];
+    data.extend_from_slice(owner.as_ref());
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    harness.set_program_account(&registry, data);
+
+    let migrate =
+        instruction::migrate_account(&harness.program_id, &authority.pubkey(), &harness.caller(), &registry);
+    harness.process(&[migrate], &[&authority]).await.unwrap();
+
+    let state: EscrowRegistryState = harness.state(&registry).await;
+    assert_eq!(state.initializer_pubkey, owner);
+    assert_eq!(state.mint_count, 0);
+}
+
+#[tokio::test]
+async fn migrate_by_another_signer_fails() {
+    let mut harness = Harness::start().await;
+    harness.set_upgrade_authority(&Pubkey::new_unique());
+    let escrow = Pubkey::new_unique();
+    harness.set_program_account(
+        &escrow,
+        legacy_escrow(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            42,
+        ),
+    );
+
+    let impostor = Keypair::new();
+    let migrate =
+        instruction::migrate_account(&harness.program_id, &impostor.pubkey(), &harness.caller(), &escrow);
+    let result = harness.process(&[migrate], &[&impostor]).await;
+    assert_escrow_error(result, EscrowError::InvalidUpgradeAuthority);
+}
+
+#[tokio::test]
+async fn migrate_against_other_loader_state_fails() {
+    let mut harness = Harness::start().await;
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let program_data = Pubkey::find_program_address(
+        &[harness.program_id.as_ref()],
+        &solana_sdk::bpf_loader_upgradeable::id(),
+    )
+    .0;
+    // A `Program` account where the `ProgramData` account should be.
+    let mut account = harness.account(&program_data).await.unwrap();
+    account.data[0] = 2;
+    harness.set_account(&program_data, account);
+    let escrow = Pubkey::new_unique();
+    harness.set_program_account(
+        &escrow,
+        legacy_escrow(
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            42,
+        ),
+    );
+
+    let migrate =
+        instruction::migrate_account(&harness.program_id, &authority.pubkey(), &harness.caller(), &escrow);
+    let result = harness.process(&[migrate], &[&authority]).await;
+    assert_escrow_error(result, EscrowError::InvalidProgramData);
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{assert_escrow_error, Harness, TokenEscrow};
+use solana_escrow::utils::errors::EscrowError;
+use solana_program_test::tokio;
+use solana_sdk::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{self, SyscallStubs},
+    pubkey::Pubkey,
+};
+
+/// Syscalls of `ProgramTest`, except that the clock sysvar is unavailable.
+struct NoClockStubs {
+    inner: Box<dyn SyscallStubs>,
+}
+
+/// Stands in while [`NoClockStubs`] takes over the stubs it wraps.
+struct NoStubs;
+
+impl SyscallStubs for NoStubs {}
+
+impl SyscallStubs for NoClockStubs {
+    fn sol_log(&self, message: &str) {
+        self.inner.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.inner.sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.inner.sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.inner.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.inner.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        self.inner.sol_log_data(fields)
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.inner.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.inner.sol_get_stack_height()
+    }
+}
+
+// The stubs are process wide, so this file holds a single test.
+#[tokio::test]
+async fn clock_unavailable() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let inner = program_stubs::set_syscall_stubs(Box::new(NoStubs));
+    program_stubs::set_syscall_stubs(Box::new(NoClockStubs { inner }));
+
+    let release = escrow.release(&harness);
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::ClockUnavailable);
+}
@@ -0,0 +1,731 @@
+//! Shared `ProgramTest` harness: a bank running the escrow program next to
+//! SPL Token and Token-2022, with the test payer acting as caller / relayer.
+
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use solana_escrow::{
+    instruction, process_instruction,
+    states::{
+        default_escrow::EscrowState, escrow_registry::EscrowRegistryState,
+        program_config::ProgramConfigState, sol_escrow::SolEscrowState,
+        user_profile::UserProfileState,
+    },
+    utils::{errors::EscrowError, memo::EscrowMemo, time_basis::TimeBasis},
+};
+use solana_program_test::{
+    processor, tokio, BanksClientError, BanksTransactionResultWithMetadata, ProgramTest,
+    ProgramTestContext,
+};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    clock::Clock,
+    instruction::{Instruction, InstructionError},
+    program_pack::{IsInitialized, Pack},
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token_2022::extension::ExtensionType;
+
+pub const DECIMALS: u8 = 6;
+/// The open escrow cap of the program config [`Harness::start`] sets.
+pub const MAX_OPEN_ESCROWS_PER_USER: u64 = 16;
+/// How often, and how far apart, a transaction whose accounts are still
+/// locked by the previous one is retried before the test gives up.
+const ACCOUNT_IN_USE_ATTEMPTS: u32 = 100;
+const ACCOUNT_IN_USE_DELAY: Duration = Duration::from_millis(10);
+
+pub struct Harness {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+}
+
+impl Harness {
+    /// Starts with a program config capping open escrows at
+    /// [`MAX_OPEN_ESCROWS_PER_USER`].
+    pub async fn start() -> Self {
+        let program_id = Pubkey::new_unique();
+        let mut data = vec![0; ProgramConfigState::LEN];
+        let config = ProgramConfigState {
+            is_initialized: true,
+            max_open_escrows_per_user: MAX_OPEN_ESCROWS_PER_USER,
+        };
+        ProgramConfigState::pack(config, &mut data).unwrap();
+        let mut program_test =
+            ProgramTest::new("solana_escrow", program_id, processor!(process_instruction));
+        program_test.add_account(
+            instruction::find_program_config(&program_id).0,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+        Self::start_with(program_test, program_id).await
+    }
+
+    /// Starts without a program config, as a fresh deployment would.
+    pub async fn start_unconfigured() -> Self {
+        let program_id = Pubkey::new_unique();
+        let program_test =
+            ProgramTest::new("solana_escrow", program_id, processor!(process_instruction));
+        Self::start_with(program_test, program_id).await
+    }
+
+    async fn start_with(program_test: ProgramTest, program_id: Pubkey) -> Self {
+        Self {
+            context: program_test.start_with_context().await,
+            program_id,
+        }
+    }
+
+    /// The fee payer, which doubles as the escrow caller / relayer.
+    pub fn caller(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let transaction = self.transaction(instructions, signers).await;
+        self.context.banks_client.process_transaction(transaction).await
+    }
+
+    /// Processes `instructions`, which must succeed, and returns the compute
+    /// units they consumed.
+    pub async fn process_metered(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> u64 {
+        let outcome = self.process_with_metadata(instructions, signers).await;
+        outcome.metadata.expect("transaction metadata").compute_units_consumed
+    }
+
+    /// Processes `instructions`, which must succeed, and returns the log
+    /// messages of the transaction.
+    pub async fn process_logged(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Vec<String> {
+        let outcome = self.process_with_metadata(instructions, signers).await;
+        outcome.metadata.expect("transaction metadata").log_messages
+    }
+
+    /// Runs the transaction, which must succeed, on the working bank
+    /// directly. That bank can still hold the locks of a transaction
+    /// [`Self::process`] just saw confirmed, so the transaction is retried
+    /// while its accounts are in use, up to [`ACCOUNT_IN_USE_ATTEMPTS`] times.
+    async fn process_with_metadata(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> BanksTransactionResultWithMetadata {
+        let transaction = self.transaction(instructions, signers).await;
+        for _ in 0..ACCOUNT_IN_USE_ATTEMPTS {
+            let outcome = self
+                .context
+                .banks_client
+                .process_transaction_with_metadata(transaction.clone())
+                .await
+                .unwrap();
+            match &outcome.result {
+                Err(TransactionError::AccountInUse) => {
+                    tokio::time::sleep(ACCOUNT_IN_USE_DELAY).await
+                }
+                result => {
+                    result.clone().unwrap();
+                    return outcome;
+                }
+            }
+        }
+        panic!(
+            "accounts of the transaction still in use after {ACCOUNT_IN_USE_ATTEMPTS} attempts \
+             {ACCOUNT_IN_USE_DELAY:?} apart"
+        )
+    }
+
+    async fn transaction(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Transaction {
+        let blockhash = self.context.banks_client.get_latest_blockhash().await.unwrap();
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        )
+    }
+
+    pub async fn account(&mut self, address: &Pubkey) -> Option<Account> {
+        self.context.banks_client.get_account(*address).await.unwrap()
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.account(address).await.map_or(0, |account| account.lamports)
+    }
+
+    pub async fn state<T: Pack + IsInitialized>(&mut self, address: &Pubkey) -> T {
+        T::unpack(&self.account(address).await.expect("state account").data).unwrap()
+    }
+
+    pub fn set_account(&mut self, address: &Pubkey, account: Account) {
+        self.context.set_account(address, &AccountSharedData::from(account));
+    }
+
+    /// Rewrites the state held in `address`, keeping its lamports and owner.
+    pub async fn set_state<T: Pack>(&mut self, address: &Pubkey, state: T) {
+        let mut account = self.account(address).await.expect("state account");
+        T::pack(state, &mut account.data).unwrap();
+        self.set_account(address, account);
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context.banks_client.get_sysvar::<Clock>().await.unwrap()
+    }
+
+    /// Waits for a new blockhash, so an identical transaction can be sent
+    /// again.
+    pub async fn next_blockhash(&mut self) {
+        self.context.get_new_latest_blockhash().await.unwrap();
+    }
+
+    /// Moves to a later slot, so later transactions get a new blockhash, and
+    /// sets the clock past `timestamp`. Warping fails the bank's accounts
+    /// hash check once [`Self::set_account`] was used.
+    pub async fn warp_past(&mut self, timestamp: i64) {
+        let slot = self.clock().await.slot;
+        self.context.warp_to_slot(slot + 2).unwrap();
+        let mut clock = self.clock().await;
+        clock.unix_timestamp = clock.unix_timestamp.max(timestamp + 1);
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn warp_to_slot(&mut self, slot: u64) {
+        self.context.warp_to_slot(slot).unwrap();
+    }
+
+    /// Creates a rent exempt account of `space` bytes owned by the program.
+    pub async fn create_state_account(&mut self, space: usize) -> Keypair {
+        let account = Keypair::new();
+        let caller = self.caller();
+        self.process(
+            &[system_instruction::create_account(
+                &caller,
+                &account.pubkey(),
+                Rent::default().minimum_balance(space),
+                space as u64,
+                &self.program_id,
+            )],
+            &[&account],
+        )
+        .await
+        .unwrap();
+        account
+    }
+
+    pub async fn create_mint(&mut self, token_program: &Pubkey) -> Pubkey {
+        self.create_mint_with_extensions(token_program, &[]).await
+    }
+
+    /// Creates a mint with the caller as mint authority. `extensions` lists
+    /// the Token-2022 extensions initialized before the mint, in order.
+    pub async fn create_mint_with_extensions(
+        &mut self,
+        token_program: &Pubkey,
+        extensions: &[ExtensionType],
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let caller = self.caller();
+        let space = if extensions.is_empty() {
+            spl_token::state::Mint::LEN
+        } else {
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)
+                .unwrap()
+        };
+        let mut instructions = vec![system_instruction::create_account(
+            &caller,
+            &mint.pubkey(),
+            Rent::default().minimum_balance(space),
+            space as u64,
+            token_program,
+        )];
+        for extension in extensions {
+            instructions.push(match extension {
+                ExtensionType::NonTransferable => {
+                    spl_token_2022::instruction::initialize_non_transferable_mint(
+                        token_program,
+                        &mint.pubkey(),
+                    )
+                    .unwrap()
+                }
+                extension => panic!("no initializer for {extension:?}"),
+            });
+        }
+        instructions.push(
+            spl_token_2022::instruction::initialize_mint2(
+                token_program,
+                &mint.pubkey(),
+                &caller,
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        );
+        self.process(&instructions, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    pub async fn create_token_account(
+        &mut self,
+        token_program: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+    ) -> Pubkey {
+        let account = Keypair::new();
+        let caller = self.caller();
+        let space = spl_token::state::Account::LEN;
+        self.process(
+            &[
+                system_instruction::create_account(
+                    &caller,
+                    &account.pubkey(),
+                    Rent::default().minimum_balance(space),
+                    space as u64,
+                    token_program,
+                ),
+                spl_token_2022::instruction::initialize_account3(
+                    token_program,
+                    &account.pubkey(),
+                    mint,
+                    owner,
+                )
+                .unwrap(),
+            ],
+            &[&account],
+        )
+        .await
+        .unwrap();
+        account.pubkey()
+    }
+
+    pub async fn mint_to(
+        &mut self,
+        token_program: &Pubkey,
+        mint: &Pubkey,
+        account: &Pubkey,
+        amount: u64,
+    ) {
+        let caller = self.caller();
+        self.process(
+            &[spl_token_2022::instruction::mint_to(
+                token_program,
+                mint,
+                account,
+                &caller,
+                &[],
+                amount,
+            )
+            .unwrap()],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    pub async fn token_account(&mut self, address: &Pubkey) -> spl_token_2022::state::Account {
+        let account = self.account(address).await.expect("token account");
+        spl_token_2022::extension::StateWithExtensions::<spl_token_2022::state::Account>::unpack(
+            &account.data,
+        )
+        .unwrap()
+        .base
+    }
+
+    pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        self.token_account(address).await.amount
+    }
+
+    /// Writes a program data account naming `authority` as the program's
+    /// upgrade authority, as the upgradeable loader would.
+    pub fn set_upgrade_authority(&mut self, authority: &Pubkey) {
+        let (program_data, _) = Pubkey::find_program_address(
+            &[self.program_id.as_ref()],
+            &solana_sdk::bpf_loader_upgradeable::id(),
+        );
+        let mut data = vec![3, 0, 0, 0];
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(authority.as_ref());
+        self.set_account(
+            &program_data,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: solana_sdk::bpf_loader_upgradeable::id(),
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+
+    /// Writes a program owned account holding `data` as is.
+    pub fn set_program_account(&mut self, address: &Pubkey, data: Vec<u8>) {
+        let program_id = self.program_id;
+        self.set_account(
+            address,
+            Account {
+                lamports: Rent::default().minimum_balance(data.len()),
+                data,
+                owner: program_id,
+                executable: false,
+                rent_epoch: 0,
+            },
+        );
+    }
+}
+
+/// A token escrow initialized by [`TokenEscrow::create`], relayed by the
+/// harness caller.
+pub struct TokenEscrow {
+    pub token_program: Pubkey,
+    pub mint: Pubkey,
+    pub initializer: Keypair,
+    pub initializer_token_account: Pubkey,
+    pub receiver: Keypair,
+    pub receiver_token_account: Pubkey,
+    pub temp_token_account: Pubkey,
+    pub escrow: Pubkey,
+    pub amount: u64,
+    /// Unix seconds unless changed before [`Self::init`].
+    pub time_basis: TimeBasis,
+    /// Empty unless changed before [`Self::init`].
+    pub memo: EscrowMemo,
+}
+
+impl TokenEscrow {
+    /// Escrows `amount` tokens of a new mint. The initializer keeps `amount`
+    /// more tokens in its own token account.
+    pub async fn create(harness: &mut Harness, token_program: &Pubkey, amount: u64) -> Self {
+        let mint = harness.create_mint(token_program).await;
+        Self::create_with_mint(harness, token_program, &mint, amount).await
+    }
+
+    pub async fn create_with_mint(
+        harness: &mut Harness,
+        token_program: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Self {
+        let escrow = Self::prepare(harness, token_program, mint, amount).await;
+        harness.process(&[escrow.init(harness, amount)], &[&escrow.initializer]).await.unwrap();
+        escrow
+    }
+
+    /// Funds the accounts of an escrow of `amount` tokens, leaving
+    /// [`Self::init`] to the caller.
+    pub async fn prepare(
+        harness: &mut Harness,
+        token_program: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+    ) -> Self {
+        let initializer = Keypair::new();
+        let receiver = Keypair::new();
+        let caller = harness.caller();
+        let temp_token_account = harness.create_token_account(token_program, mint, &caller).await;
+        let initializer_token_account = harness
+            .create_token_account(token_program, mint, &initializer.pubkey())
+            .await;
+        let receiver_token_account = harness
+            .create_token_account(token_program, mint, &receiver.pubkey())
+            .await;
+        harness.mint_to(token_program, mint, &temp_token_account, amount).await;
+        harness.mint_to(token_program, mint, &initializer_token_account, amount).await;
+        let (escrow, _) =
+            instruction::find_escrow_account(&harness.program_id, &initializer.pubkey(), 1);
+
+        Self {
+            token_program: *token_program,
+            mint: *mint,
+            initializer,
+            initializer_token_account,
+            receiver,
+            receiver_token_account,
+            temp_token_account,
+            escrow,
+            amount,
+            time_basis: TimeBasis::UnixTimestamp,
+            memo: EscrowMemo::default(),
+        }
+    }
+
+    pub fn init(&self, harness: &Harness, amount: u64) -> Instruction {
+        instruction::init_escrow(
+            &harness.program_id,
+            &self.initializer.pubkey(),
+            &self.receiver.pubkey(),
+            &self.temp_token_account,
+            &self.escrow,
+            &self.token_program,
+            &harness.caller(),
+            &self.mint,
+            amount,
+            self.time_basis,
+            &self.memo,
+        )
+    }
+
+    pub fn release(&self, harness: &Harness) -> Instruction {
+        let mut release = instruction::release_escrow(
+            &harness.program_id,
+            &self.receiver_token_account,
+            &self.temp_token_account,
+            &self.initializer.pubkey(),
+            &self.initializer_token_account,
+            &self.escrow,
+            &self.token_program,
+            &harness.caller(),
+            &self.mint,
+        );
+        if self.memo.log_memo {
+            instruction::append_memo_program(&mut release);
+        }
+        release
+    }
+
+    pub fn refund(&self, harness: &Harness) -> Instruction {
+        instruction::refund_escrow(
+            &harness.program_id,
+            &self.initializer.pubkey(),
+            &self.initializer_token_account,
+            &self.temp_token_account,
+            &self.escrow,
+            &self.token_program,
+            &harness.caller(),
+            &self.mint,
+        )
+    }
+
+    pub async fn state(&self, harness: &mut Harness) -> EscrowState {
+        harness.state(&self.escrow).await
+    }
+
+    pub fn user_profile(&self, harness: &Harness) -> Pubkey {
+        instruction::find_user_profile(&harness.program_id, &self.initializer.pubkey()).0
+    }
+
+    pub async fn profile(&self, harness: &mut Harness) -> UserProfileState {
+        let user_profile = self.user_profile(harness);
+        harness.state(&user_profile).await
+    }
+
+    /// The address the initializer's next escrow gets.
+    pub async fn next_escrow(&self, harness: &mut Harness) -> Pubkey {
+        let escrow_count = match harness.account(&self.user_profile(harness)).await {
+            Some(_) => self.profile(harness).await.escrow_count,
+            None => 0,
+        };
+        let initializer = self.initializer.pubkey();
+        instruction::find_escrow_account(&harness.program_id, &initializer, escrow_count + 1).0
+    }
+}
+
+/// A native SOL escrow funded by a new initializer.
+pub struct SolEscrow {
+    pub initializer: Keypair,
+    pub receiver: Pubkey,
+    pub escrow: Pubkey,
+    pub vault: Pubkey,
+    pub amount: u64,
+}
+
+impl SolEscrow {
+    pub async fn create(harness: &mut Harness, amount: u64) -> Self {
+        let sol_escrow = Self::prepare(harness, amount).await;
+        let init = sol_escrow.init(harness);
+        harness.process(&[init], &[&sol_escrow.initializer]).await.unwrap();
+        sol_escrow
+    }
+
+    /// Funds the initializer and allocates the escrow account, leaving
+    /// [`Self::init`] to the caller.
+    pub async fn prepare(harness: &mut Harness, amount: u64) -> Self {
+        let initializer = Keypair::new();
+        let caller = harness.caller();
+        harness
+            .process(
+                &[system_instruction::transfer(&caller, &initializer.pubkey(), amount * 2)],
+                &[],
+            )
+            .await
+            .unwrap();
+        let escrow = harness.create_state_account(SolEscrowState::LEN).await;
+        Self {
+            receiver: Pubkey::new_unique(),
+            vault: instruction::find_sol_vault(&harness.program_id, &escrow.pubkey()).0,
+            escrow: escrow.pubkey(),
+            initializer,
+            amount,
+        }
+    }
+
+    pub fn init(&self, harness: &Harness) -> Instruction {
+        instruction::init_sol_escrow(
+            &harness.program_id,
+            &self.initializer.pubkey(),
+            &self.receiver,
+            &self.escrow,
+            &harness.caller(),
+            self.amount,
+        )
+    }
+
+    pub async fn state(&self, harness: &mut Harness) -> SolEscrowState {
+        harness.state(&self.escrow).await
+    }
+}
+
+/// A wallet escrow registry whose owner registered a token account of a new
+/// mint, adopted as the vault of that mint.
+pub struct WalletEscrow {
+    pub token_program: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Keypair,
+    pub owner_token_account: Pubkey,
+    pub registry: Pubkey,
+    pub vault: Pubkey,
+}
+
+impl WalletEscrow {
+    pub async fn create(harness: &mut Harness, token_program: &Pubkey) -> Self {
+        let mint = harness.create_mint(token_program).await;
+        let owner = Keypair::new();
+        let caller = harness.caller();
+        harness
+            .process(&[system_instruction::transfer(&caller, &owner.pubkey(), 1_000_000_000)], &[])
+            .await
+            .unwrap();
+        let vault = harness.create_token_account(token_program, &mint, &owner.pubkey()).await;
+        let owner_token_account = harness
+            .create_token_account(token_program, &mint, &owner.pubkey())
+            .await;
+        let registry = harness.create_state_account(EscrowRegistryState::LEN).await;
+        let wallet = Self {
+            token_program: *token_program,
+            mint,
+            owner,
+            owner_token_account,
+            registry: registry.pubkey(),
+            vault,
+        };
+        let program_id = harness.program_id;
+        harness
+            .process(
+                &[
+                    instruction::register_escrow(
+                        &program_id,
+                        &wallet.owner.pubkey(),
+                        &wallet.vault,
+                        &wallet.registry,
+                        token_program,
+                        &mint,
+                    ),
+                    instruction::add_mint(
+                        &program_id,
+                        &wallet.owner.pubkey(),
+                        &wallet.registry,
+                        &wallet.vault,
+                        token_program,
+                        &mint,
+                    ),
+                ],
+                &[&wallet.owner],
+            )
+            .await
+            .unwrap();
+        wallet
+    }
+
+    /// A depositor holding `amount` tokens of the registry's mint.
+    pub async fn depositor(&self, harness: &mut Harness, amount: u64) -> (Keypair, Pubkey) {
+        let depositor = Keypair::new();
+        let caller = harness.caller();
+        harness
+            .process(
+                &[system_instruction::transfer(&caller, &depositor.pubkey(), 1_000_000_000)],
+                &[],
+            )
+            .await
+            .unwrap();
+        let token_account = harness
+            .create_token_account(&self.token_program, &self.mint, &depositor.pubkey())
+            .await;
+        harness.mint_to(&self.token_program, &self.mint, &token_account, amount).await;
+        (depositor, token_account)
+    }
+
+    pub fn deposit(&self, harness: &Harness, depositor: &Pubkey, from: &Pubkey, amount: u64) -> Instruction {
+        instruction::deposit(
+            &harness.program_id,
+            depositor,
+            from,
+            &self.vault,
+            &self.registry,
+            &self.token_program,
+            &self.mint,
+            amount,
+        )
+    }
+
+    pub fn withdraw(&self, harness: &Harness, depositor: &Pubkey, to: &Pubkey, amount: u64) -> Instruction {
+        instruction::withdraw(
+            &harness.program_id,
+            depositor,
+            to,
+            &self.vault,
+            &self.registry,
+            &self.token_program,
+            &self.mint,
+            amount,
+        )
+    }
+
+    pub fn collect(&self, harness: &Harness, depositor: &Pubkey, depositor_signs: bool) -> Instruction {
+        instruction::collect_deposit(
+            &harness.program_id,
+            &self.owner.pubkey(),
+            &self.owner_token_account,
+            &self.vault,
+            &self.registry,
+            depositor,
+            depositor_signs,
+            &self.token_program,
+            &self.mint,
+        )
+    }
+
+    pub fn record(&self, harness: &Harness, depositor: &Pubkey) -> Pubkey {
+        instruction::find_deposit_record(&harness.program_id, &self.registry, &self.mint, depositor).0
+    }
+
+    pub fn registry_mint(&self, harness: &Harness) -> Pubkey {
+        instruction::find_registry_mint(&harness.program_id, &self.registry, &self.mint).0
+    }
+}
+
+/// Asserts the transaction failed in one of its instructions with `expected`.
+pub fn assert_instruction_error(result: Result<(), BanksClientError>, expected: InstructionError) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(_, error))) => {
+            assert_eq!(error, expected)
+        }
+        other => panic!("expected {expected:?}, got {other:?}"),
+    }
+}
+
+pub fn assert_escrow_error(result: Result<(), BanksClientError>, expected: EscrowError) {
+    assert_instruction_error(result, InstructionError::Custom(expected as u32));
+}
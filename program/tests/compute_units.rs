@@ -0,0 +1,317 @@
+//! Compute units consumed by each instruction, checked against the baseline
+//! in `compute_units.txt`. An instruction fails the run once it consumes
+//! more than its baseline plus `TOLERANCE_PERCENT`, or has no baseline for
+//! the build; record new baselines with `UPDATE_COMPUTE_UNITS=1`.
+//!
+//! `cargo test` runs the program as a builtin, which only meters CPIs into
+//! the SPL programs and sysvar reads. `cargo test-sbf` runs the BPF build,
+//! which meters every instruction, against its own baselines.
+
+mod common;
+
+use std::{collections::BTreeMap, env, fs, path::PathBuf};
+
+use common::{Harness, SolEscrow, TokenEscrow, WalletEscrow};
+use solana_escrow::{
+    instruction,
+    states::{deposit_record::DepositRecordState, escrow_registry::EscrowRegistryState},
+    utils::{constants, voucher::Voucher},
+};
+use solana_program_test::tokio;
+use solana_sdk::{program_pack::Pack, pubkey::Pubkey, signature::Keypair, signer::Signer, system_instruction};
+
+const AMOUNT: u64 = 100;
+/// PDAs derived from new keypairs take a varying number of bump seed
+/// attempts, which moves the BPF counts slightly from run to run.
+const TOLERANCE_PERCENT: u64 = 5;
+
+fn baseline_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/compute_units.txt")
+}
+
+/// `sbf` when `cargo test-sbf` loaded the BPF build, `native` otherwise.
+fn build() -> &'static str {
+    if env::var_os("SBF_OUT_DIR").is_some() || env::var_os("BPF_OUT_DIR").is_some() {
+        "sbf"
+    } else {
+        "native"
+    }
+}
+
+async fn measure() -> Vec<(&'static str, u64)> {
+    let mut harness = Harness::start().await;
+    let mut units = Vec::new();
+    let token_program = spl_token::id();
+
+    let mint = harness.create_mint(&token_program).await;
+    let escrow = TokenEscrow::prepare(&mut harness, &token_program, &mint, AMOUNT).await;
+    let init = escrow.init(&harness, AMOUNT);
+    units.push(("init_escrow", harness.process_metered(&[init], &[&escrow.initializer]).await));
+
+    let increase = instruction::increase_deposit(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.initializer_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &escrow.mint,
+        10,
+    );
+    units.push((
+        "increase_deposit",
+        harness
+            .process_metered(&[increase], &[&escrow.initializer])
+            .await,
+    ));
+
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    let amend = instruction::amend_escrow(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.receiver.pubkey(),
+        &escrow.initializer_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &escrow.mint,
+        AMOUNT,
+        expire_date,
+        &escrow.receiver.pubkey(),
+    );
+    let signers = [&escrow.initializer, &escrow.receiver];
+    units.push((
+        "amend_escrow",
+        harness.process_metered(&[amend], &signers).await,
+    ));
+
+    let assigned = TokenEscrow::create_with_mint(&mut harness, &token_program, &mint, AMOUNT).await;
+    let assign = instruction::assign_receiver(
+        &harness.program_id,
+        &assigned.receiver.pubkey(),
+        &assigned.escrow,
+        &Pubkey::new_unique(),
+    );
+    units.push((
+        "assign_receiver",
+        harness
+            .process_metered(&[assign], &[&assigned.receiver])
+            .await,
+    ));
+
+    let expired = TokenEscrow::create_with_mint(&mut harness, &token_program, &mint, AMOUNT).await;
+
+    let vouched = TokenEscrow::create_with_mint(&mut harness, &token_program, &mint, AMOUNT).await;
+    let voucher = Voucher {
+        escrow: vouched.escrow,
+        amount: AMOUNT / 2,
+        recipient: vouched.receiver.pubkey(),
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+    let signature: [u8; 64] = vouched.initializer.sign_message(&voucher.message()).into();
+    let release = [
+        instruction::ed25519_verify(&vouched.initializer.pubkey(), &signature, &voucher),
+        instruction::release_with_voucher(
+            &harness.program_id,
+            &vouched.receiver_token_account,
+            &vouched.temp_token_account,
+            &vouched.initializer.pubkey(),
+            &vouched.initializer_token_account,
+            &vouched.escrow,
+            &vouched.token_program,
+            &harness.caller(),
+            &vouched.mint,
+            voucher,
+        ),
+    ];
+    units.push((
+        "release_with_voucher",
+        harness.process_metered(&release, &[]).await,
+    ));
+
+    let oracle = instruction::oracle(&harness.program_id);
+    units.push(("oracle", harness.process_metered(&[oracle], &[]).await));
+
+    let sol_escrow = SolEscrow::prepare(&mut harness, 1_000_000_000).await;
+    let init = sol_escrow.init(&harness);
+    units.push((
+        "init_sol_escrow",
+        harness
+            .process_metered(&[init], &[&sol_escrow.initializer])
+            .await,
+    ));
+    let refunded = SolEscrow::create(&mut harness, 1_000_000_000).await;
+    let refund = instruction::refund_sol_escrow(
+        &harness.program_id,
+        &refunded.initializer.pubkey(),
+        &refunded.escrow,
+        &harness.caller(),
+        true,
+    );
+    units.push((
+        "refund_sol_escrow",
+        harness.process_metered(&[refund], &[]).await,
+    ));
+
+    let owner = Keypair::new();
+    let caller = harness.caller();
+    harness
+        .process(&[system_instruction::transfer(&caller, &owner.pubkey(), 1_000_000_000)], &[])
+        .await
+        .unwrap();
+    let vault = harness.create_token_account(&token_program, &mint, &owner.pubkey()).await;
+    let owner_token_account = harness.create_token_account(&token_program, &mint, &owner.pubkey()).await;
+    let registry = harness.create_state_account(EscrowRegistryState::LEN).await.pubkey();
+    let program_id = harness.program_id;
+    let register = instruction::register_escrow(&program_id, &owner.pubkey(), &vault, &registry, &token_program, &mint);
+    units.push((
+        "register_escrow",
+        harness.process_metered(&[register], &[&owner]).await,
+    ));
+    let add = instruction::add_mint(&program_id, &owner.pubkey(), &registry, &vault, &token_program, &mint);
+    units.push(("add_mint", harness.process_metered(&[add], &[&owner]).await));
+    let remove = instruction::remove_mint(
+        &program_id,
+        &owner.pubkey(),
+        &owner_token_account,
+        &registry,
+        &vault,
+        &token_program,
+        &mint,
+    );
+    units.push(("remove_mint", harness.process_metered(&[remove], &[&owner]).await));
+
+    let wallet = WalletEscrow::create(&mut harness, &token_program).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, AMOUNT).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, AMOUNT);
+    units.push((
+        "deposit",
+        harness.process_metered(&[deposit], &[&depositor]).await,
+    ));
+    let withdraw = wallet.withdraw(&harness, &depositor.pubkey(), &token_account, 10);
+    units.push((
+        "withdraw",
+        harness.process_metered(&[withdraw], &[&depositor]).await,
+    ));
+
+    let record: DepositRecordState = harness
+        .state(&wallet.record(&harness, &depositor.pubkey()))
+        .await;
+    let sol_expire_date = sol_escrow.state(&mut harness).await.expire_date;
+    harness
+        .warp_past(expire_date.max(sol_expire_date).max(record.expire_date))
+        .await;
+
+    let release = escrow.release(&harness);
+    units.push((
+        "release_escrow",
+        harness.process_metered(&[release], &[]).await,
+    ));
+    let release = instruction::release_sol_escrow(
+        &harness.program_id,
+        &sol_escrow.receiver,
+        &sol_escrow.escrow,
+        &harness.caller(),
+    );
+    units.push((
+        "release_sol_escrow",
+        harness.process_metered(&[release], &[]).await,
+    ));
+    let collect = wallet.collect(&harness, &depositor.pubkey(), false);
+    units.push((
+        "collect_deposit",
+        harness.process_metered(&[collect], &[&wallet.owner]).await,
+    ));
+
+    let refund_after = expired.state(&mut harness).await.refund_after;
+    harness.warp_past(refund_after).await;
+    let refund = expired.refund(&harness);
+    units.push((
+        "refund_escrow",
+        harness.process_metered(&[refund], &[]).await,
+    ));
+
+    let authority = Keypair::new();
+    harness.set_upgrade_authority(&authority.pubkey());
+    let legacy = Pubkey::new_unique();
+    harness.set_program_account(&legacy, legacy_escrow());
+    let migrate = instruction::migrate_account(&program_id, &authority.pubkey(), &harness.caller(), &legacy);
+    units.push((
+        "migrate_account",
+        harness.process_metered(&[migrate], &[&authority]).await,
+    ));
+
+    units
+}
+
+/// A token escrow as written before the layout header existed.
+fn legacy_escrow() -> Vec<u8> {
+    let mut data = vec![1];
+    for _ in 0..4 {
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+    }
+    data.extend_from_slice(&AMOUNT.to_le_bytes());
+    data.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    assert_eq!(data.len(), constants::LEGACY_DEFAULT_ESCROW_STATE_LEN);
+    data
+}
+
+/// Baselines as `build instruction units` lines, `#` starting a comment.
+fn parse(baseline: &str) -> BTreeMap<(String, String), u64> {
+    baseline
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#') && !line.trim().is_empty())
+        .map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [build, name, units] => ((build.into(), name.into()), units.parse().unwrap()),
+                _ => panic!("malformed baseline line {line:?}"),
+            },
+        )
+        .collect()
+}
+
+#[tokio::test]
+async fn compute_units_stay_within_baseline() {
+    let build = build();
+    let measured = measure().await;
+    let path = baseline_path();
+    let text = fs::read_to_string(&path).unwrap_or_default();
+    let mut baselines = parse(&text);
+
+    if env::var_os("UPDATE_COMPUTE_UNITS").is_some() {
+        for (name, units) in &measured {
+            baselines.insert((build.into(), (*name).into()), *units);
+        }
+        let header: String = text
+            .lines()
+            .take_while(|line| line.starts_with('#'))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        let body: String = baselines
+            .iter()
+            .map(|((build, name), units)| format!("{build} {name} {units}\n"))
+            .collect();
+        fs::write(&path, header + &body).unwrap();
+        return;
+    }
+
+    let mut failures = Vec::new();
+    for (name, units) in measured {
+        match baselines.get(&(build.to_string(), name.to_string())) {
+            Some(&baseline) if units > baseline + baseline * TOLERANCE_PERCENT / 100 => {
+                failures.push(format!("{name}: {units} units, baseline {baseline}"))
+            }
+            Some(&baseline) if units < baseline - baseline * TOLERANCE_PERCENT / 100 => {
+                println!("{name}: {units} units, down from {baseline}, the baseline can be lowered")
+            }
+            Some(_) => {}
+            None => failures.push(format!("{name}: {units} units, no baseline")),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "over or missing from the {build} compute unit baseline:\n{}\nrecord intended changes with UPDATE_COMPUTE_UNITS=1",
+        failures.join("\n")
+    );
+}
@@ -0,0 +1,734 @@
+mod common;
+
+use common::{
+    assert_escrow_error, assert_instruction_error, Harness, TokenEscrow, MAX_OPEN_ESCROWS_PER_USER,
+};
+use solana_escrow::{
+    instruction,
+    states::default_escrow::EscrowState,
+    utils::{
+        constants::{
+            ESCROW_RELEASE_WINDOW_SEC, ESCROW_WAIT_EPOCHS, ESCROW_WAIT_SLOTS, MAX_MEMO_LEN,
+        },
+        errors::EscrowError,
+        memo::EscrowMemo,
+        time_basis::TimeBasis,
+        voucher::Voucher,
+    },
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    epoch_schedule::EpochSchedule,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+};
+
+fn voucher_release(
+    harness: &Harness,
+    escrow: &TokenEscrow,
+    receiver_token_account: &Pubkey,
+    voucher: Voucher,
+    signer: &Keypair,
+) -> Vec<Instruction> {
+    let signature: [u8; 64] = signer.sign_message(&voucher.message()).into();
+    vec![
+        instruction::ed25519_verify(&signer.pubkey(), &signature, &voucher),
+        instruction::release_with_voucher(
+            &harness.program_id,
+            receiver_token_account,
+            &escrow.temp_token_account,
+            &escrow.initializer.pubkey(),
+            &escrow.initializer_token_account,
+            &escrow.escrow,
+            &escrow.token_program,
+            &harness.caller(),
+            &escrow.mint,
+            voucher,
+        ),
+    ]
+}
+
+#[tokio::test]
+async fn init_escrow_hands_the_temp_account_to_the_pda() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let state = escrow.state(&mut harness).await;
+    let now = harness.clock().await.unix_timestamp;
+    assert!(state.is_initialized);
+    assert_eq!(state.caller_pubkey, harness.caller());
+    assert_eq!(state.initializer_pubkey, escrow.initializer.pubkey());
+    assert_eq!(state.receiver_pubkey, escrow.receiver.pubkey());
+    assert_eq!(state.temp_token_account_pubkey, escrow.temp_token_account);
+    assert_eq!(state.escrow_amount, 100);
+    assert!(state.expire_date > now);
+
+    let temp = harness.token_account(&escrow.temp_token_account).await;
+    assert_eq!(temp.owner, instruction::find_escrow_authority(&harness.program_id).0);
+    assert_eq!(temp.amount, 100);
+}
+
+#[tokio::test]
+async fn init_escrow_twice_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    // The initializer's next escrow lives at the next index.
+    let init = escrow.init(&harness, 50);
+    let result = harness.process(&[init], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::InvalidPda);
+}
+
+#[tokio::test]
+async fn init_escrow_counts_it_on_the_initializer_profile() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let initializer = escrow.initializer.pubkey();
+    assert_eq!(
+        escrow.escrow,
+        instruction::find_escrow_account(&harness.program_id, &initializer, 1).0
+    );
+    assert_eq!(escrow.state(&mut harness).await.escrow_index, 1);
+    let profile = escrow.profile(&mut harness).await;
+    assert_eq!(profile.initializer_pubkey, initializer);
+    assert_eq!(profile.escrow_count, 1);
+    assert_eq!(profile.open_escrows, 1);
+    assert_eq!(profile.total_volume, 100);
+    assert_eq!(profile.disputes, 0);
+
+    // A second escrow of the same initializer gets the next index.
+    let caller = harness.caller();
+    let temp = harness
+        .create_token_account(&escrow.token_program, &escrow.mint, &caller)
+        .await;
+    harness.mint_to(&escrow.token_program, &escrow.mint, &temp, 50).await;
+    let second = escrow.next_escrow(&mut harness).await;
+    assert_eq!(second, instruction::find_escrow_account(&harness.program_id, &initializer, 2).0);
+    let mut init = escrow.init(&harness, 50);
+    init.accounts[2].pubkey = temp;
+    init.accounts[3].pubkey = second;
+    harness.process(&[init], &[&escrow.initializer]).await.unwrap();
+
+    let state: EscrowState = harness.state(&second).await;
+    assert_eq!(state.escrow_index, 2);
+    let profile = escrow.profile(&mut harness).await;
+    assert_eq!((profile.escrow_count, profile.open_escrows, profile.total_volume), (2, 2, 150));
+
+    // Closing an escrow keeps the count, so indexes are never reused.
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+    harness.process(&[escrow.release(&harness)], &[]).await.unwrap();
+    let profile = escrow.profile(&mut harness).await;
+    assert_eq!((profile.escrow_count, profile.open_escrows, profile.total_volume), (2, 1, 150));
+}
+
+#[tokio::test]
+async fn init_escrow_over_the_open_escrow_cap_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let user_profile = escrow.user_profile(&harness);
+    let mut profile = escrow.profile(&mut harness).await;
+    profile.open_escrows = MAX_OPEN_ESCROWS_PER_USER;
+    harness.set_state(&user_profile, profile).await;
+
+    let caller = harness.caller();
+    let temp = harness
+        .create_token_account(&escrow.token_program, &escrow.mint, &caller)
+        .await;
+    harness.mint_to(&escrow.token_program, &escrow.mint, &temp, 50).await;
+    let mut init = escrow.init(&harness, 50);
+    init.accounts[2].pubkey = temp;
+    init.accounts[3].pubkey = escrow.next_escrow(&mut harness).await;
+    let result = harness.process(&[init], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::TooManyOpenEscrows);
+}
+
+#[tokio::test]
+async fn init_escrow_before_the_program_is_configured_fails() {
+    let mut harness = Harness::start_unconfigured().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+
+    let result = harness.process(&[escrow.init(&harness, 100)], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::InvalidAccountOwner);
+}
+
+#[tokio::test]
+async fn init_escrow_without_the_initializer_signature_fails() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+
+    // Otherwise anyone could fill the initializer's profile up to the cap.
+    let mut init = escrow.init(&harness, 100);
+    init.accounts[0].is_signer = false;
+    let result = harness.process(&[init], &[]).await;
+    assert_escrow_error(result, EscrowError::AccountNotSigner);
+    assert!(harness.account(&escrow.user_profile(&harness)).await.is_none());
+}
+
+#[tokio::test]
+async fn init_escrow_of_nothing_fails() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+
+    let result = harness.process(&[escrow.init(&harness, 0)], &[&escrow.initializer]).await;
+    assert_instruction_error(result, InstructionError::InvalidArgument);
+}
+
+#[tokio::test]
+async fn release_pays_the_receiver_and_closes_the_escrow() {
+    for token_program in [spl_token::id(), spl_token_2022::id()] {
+        let mut harness = Harness::start().await;
+        let escrow = TokenEscrow::create(&mut harness, &token_program, 100).await;
+        let expire_date = escrow.state(&mut harness).await.expire_date;
+        let escrow_rent = harness.lamports(&escrow.escrow).await;
+        let temp_rent = harness.lamports(&escrow.temp_token_account).await;
+
+        harness.warp_past(expire_date).await;
+        let caller = harness.caller();
+        let caller_before = harness.lamports(&caller).await;
+        let release = escrow.release(&harness);
+        harness.process(&[release], &[]).await.unwrap();
+
+        assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 100);
+        assert!(harness.account(&escrow.escrow).await.is_none());
+        assert!(harness.account(&escrow.temp_token_account).await.is_none());
+        let fee = 5_000;
+        assert_eq!(
+            harness.lamports(&caller).await,
+            caller_before + escrow_rent + temp_rent - fee
+        );
+    }
+}
+
+#[tokio::test]
+async fn release_before_maturity_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let release = escrow.release(&harness);
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::EscrowNotMaturedYet);
+}
+
+#[tokio::test]
+async fn release_after_the_window_closes_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let state = escrow.state(&mut harness).await;
+    assert_eq!(state.refund_after, state.expire_date + ESCROW_RELEASE_WINDOW_SEC);
+
+    harness.warp_past(state.refund_after).await;
+    let result = harness.process(&[escrow.release(&harness)], &[]).await;
+    assert_escrow_error(result, EscrowError::ReleaseWindowClosed);
+}
+
+#[tokio::test]
+async fn refund_before_the_window_closes_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+
+    let result = harness.process(&[escrow.refund(&harness)], &[]).await;
+    assert_escrow_error(result, EscrowError::RefundNotDue);
+    harness.warp_past(expire_date).await;
+    let result = harness.process(&[escrow.refund(&harness)], &[]).await;
+    assert_escrow_error(result, EscrowError::RefundNotDue);
+}
+
+#[tokio::test]
+async fn anyone_can_refund_once_the_window_closes() {
+    for token_program in [spl_token::id(), spl_token_2022::id()] {
+        let mut harness = Harness::start().await;
+        let escrow = TokenEscrow::create(&mut harness, &token_program, 100).await;
+        let refund_after = escrow.state(&mut harness).await.refund_after;
+        let escrow_rent = harness.lamports(&escrow.escrow).await;
+        let temp_rent = harness.lamports(&escrow.temp_token_account).await;
+
+        harness.warp_past(refund_after).await;
+        let caller = harness.caller();
+        let caller_before = harness.lamports(&caller).await;
+        let refund = escrow.refund(&harness);
+        assert!(refund.accounts.iter().all(|account| !account.is_signer));
+        harness.process(&[refund], &[]).await.unwrap();
+
+        assert_eq!(harness.token_balance(&escrow.initializer_token_account).await, 200);
+        assert!(harness.account(&escrow.escrow).await.is_none());
+        assert!(harness.account(&escrow.temp_token_account).await.is_none());
+        let fee = 5_000;
+        assert_eq!(
+            harness.lamports(&caller).await,
+            caller_before + escrow_rent + temp_rent - fee
+        );
+        assert_eq!(escrow.profile(&mut harness).await.open_escrows, 0);
+    }
+}
+
+#[tokio::test]
+async fn extra_tokens_in_the_temp_account_go_back_to_the_initializer() {
+    for token_program in [spl_token::id(), spl_token_2022::id()] {
+        // Dust sent to the temp account after init.
+        let mut harness = Harness::start().await;
+        let escrow = TokenEscrow::create(&mut harness, &token_program, 100).await;
+        harness.mint_to(&token_program, &escrow.mint, &escrow.temp_token_account, 7).await;
+        let expire_date = escrow.state(&mut harness).await.expire_date;
+        harness.warp_past(expire_date).await;
+        harness.process(&[escrow.release(&harness)], &[]).await.unwrap();
+
+        assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 100);
+        assert_eq!(harness.token_balance(&escrow.initializer_token_account).await, 107);
+        assert!(harness.account(&escrow.temp_token_account).await.is_none());
+
+        // A temp account holding more than the escrowed amount from the start.
+        let mut harness = Harness::start().await;
+        let mint = harness.create_mint(&token_program).await;
+        let escrow = TokenEscrow::prepare(&mut harness, &token_program, &mint, 100).await;
+        harness.process(&[escrow.init(&harness, 90)], &[&escrow.initializer]).await.unwrap();
+        harness.mint_to(&token_program, &mint, &escrow.temp_token_account, 7).await;
+        let refund_after = escrow.state(&mut harness).await.refund_after;
+        harness.warp_past(refund_after).await;
+        harness.process(&[escrow.refund(&harness)], &[]).await.unwrap();
+
+        assert_eq!(harness.token_balance(&escrow.initializer_token_account).await, 207);
+        assert!(harness.account(&escrow.temp_token_account).await.is_none());
+        assert!(harness.account(&escrow.escrow).await.is_none());
+    }
+}
+
+#[tokio::test]
+async fn refund_to_another_account_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let refund_after = escrow.state(&mut harness).await.refund_after;
+
+    harness.warp_past(refund_after).await;
+    let refund = instruction::refund_escrow(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.receiver_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &harness.caller(),
+        &escrow.mint,
+    );
+    let result = harness.process(&[refund], &[]).await;
+    assert_escrow_error(result, EscrowError::TokenOwnerMismatch);
+}
+
+#[tokio::test]
+async fn init_escrow_stores_the_reference_and_memo() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let mut escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+    escrow.memo = EscrowMemo {
+        reference: Some([7; 32]),
+        memo: "Invoice 2024-0042".to_string(),
+        log_memo: false,
+    };
+    harness.process(&[escrow.init(&harness, 100)], &[&escrow.initializer]).await.unwrap();
+
+    let state = escrow.state(&mut harness).await;
+    assert_eq!(state.escrow_memo(), escrow.memo);
+    harness.warp_past(state.expire_date).await;
+    let logs = harness.process_logged(&[escrow.release(&harness)], &[]).await;
+    assert!(!logs.iter().any(|log| log.contains("Memo")));
+}
+
+#[tokio::test]
+async fn logged_memo_goes_through_spl_memo_on_init_and_release() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let mut escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+    escrow.memo = EscrowMemo {
+        reference: Some([0xab; 32]),
+        memo: "order 42".to_string(),
+        log_memo: true,
+    };
+    let text = format!("{} order 42", "ab".repeat(32));
+
+    let logs = harness.process_logged(&[escrow.init(&harness, 100)], &[&escrow.initializer]).await;
+    assert!(logs.iter().any(|log| log.contains(&text)), "{logs:?}");
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+
+    let mut without_memo_program = escrow.release(&harness);
+    without_memo_program.accounts.pop();
+    let result = harness.process(&[without_memo_program], &[]).await;
+    assert_instruction_error(result, InstructionError::NotEnoughAccountKeys);
+    let mut wrong_memo_program = escrow.release(&harness);
+    wrong_memo_program.accounts.last_mut().unwrap().pubkey = spl_token::id();
+    let result = harness.process(&[wrong_memo_program], &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidMemoProgram);
+
+    let logs = harness.process_logged(&[escrow.release(&harness)], &[]).await;
+    assert!(logs.iter().any(|log| log.contains(&text)), "{logs:?}");
+    assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 100);
+}
+
+#[tokio::test]
+async fn invalid_memo_fails() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let mut escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+    for memo in [
+        EscrowMemo {
+            memo: "x".repeat(MAX_MEMO_LEN + 1),
+            ..EscrowMemo::default()
+        },
+        EscrowMemo {
+            log_memo: true,
+            ..EscrowMemo::default()
+        },
+    ] {
+        escrow.memo = memo;
+        let result = harness.process(&[escrow.init(&harness, 100)], &[&escrow.initializer]).await;
+        assert_escrow_error(result, EscrowError::InvalidMemo);
+    }
+}
+
+#[tokio::test]
+async fn slot_based_escrow_matures_by_slot() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let mut escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+    escrow.time_basis = TimeBasis::Slot;
+    harness.process(&[escrow.init(&harness, 100)], &[&escrow.initializer]).await.unwrap();
+    let state = escrow.state(&mut harness).await;
+    assert_eq!(state.time_basis, TimeBasis::Slot);
+    assert_eq!(state.expire_date, harness.clock().await.slot as i64 + ESCROW_WAIT_SLOTS);
+
+    // A unix timestamp pushed a day ahead doesn't mature it.
+    let timestamp = harness.clock().await.unix_timestamp;
+    harness.warp_past(timestamp + 86_400).await;
+    let result = harness.process(&[escrow.release(&harness)], &[]).await;
+    assert_escrow_error(result, EscrowError::EscrowNotMaturedYet);
+
+    harness.warp_to_slot(state.expire_date as u64).await;
+    harness.process(&[escrow.release(&harness)], &[]).await.unwrap();
+    assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 100);
+}
+
+#[tokio::test]
+async fn epoch_based_escrow_matures_by_epoch() {
+    let mut harness = Harness::start().await;
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let mut escrow = TokenEscrow::prepare(&mut harness, &spl_token::id(), &mint, 100).await;
+    escrow.time_basis = TimeBasis::Epoch;
+    harness.process(&[escrow.init(&harness, 100)], &[&escrow.initializer]).await.unwrap();
+    let state = escrow.state(&mut harness).await;
+    assert_eq!(state.expire_date, harness.clock().await.epoch as i64 + ESCROW_WAIT_EPOCHS);
+
+    let schedule: EpochSchedule = harness.context.banks_client.get_sysvar().await.unwrap();
+    let first_slot = schedule.get_first_slot_in_epoch(state.expire_date as u64);
+    harness.warp_to_slot(first_slot - 1).await;
+    let result = harness.process(&[escrow.release(&harness)], &[]).await;
+    assert_escrow_error(result, EscrowError::EscrowNotMaturedYet);
+
+    harness.warp_to_slot(first_slot).await;
+    harness.process(&[escrow.release(&harness)], &[]).await.unwrap();
+    assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 100);
+}
+
+#[tokio::test]
+async fn release_to_another_receiver_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[0].pubkey = escrow.initializer_token_account;
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::TokenOwnerMismatch);
+}
+
+#[tokio::test]
+async fn initializer_voucher_releases_early_in_parts() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let voucher = Voucher {
+        escrow: escrow.escrow,
+        amount: 40,
+        recipient: escrow.receiver.pubkey(),
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        voucher,
+        &escrow.initializer,
+    );
+    harness.process(&release, &[]).await.unwrap();
+    assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 40);
+    let state = escrow.state(&mut harness).await;
+    assert_eq!((state.escrow_amount, state.voucher_nonce), (60, 1));
+
+    let rest = Voucher {
+        amount: 60,
+        nonce: 2,
+        ..voucher
+    };
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        rest,
+        &escrow.initializer,
+    );
+    harness.process(&release, &[]).await.unwrap();
+    assert_eq!(harness.token_balance(&escrow.receiver_token_account).await, 100);
+    assert!(harness.account(&escrow.escrow).await.is_none());
+    assert!(harness.account(&escrow.temp_token_account).await.is_none());
+}
+
+#[tokio::test]
+async fn receiver_voucher_redirects_the_payout_after_maturity() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let buyer = Pubkey::new_unique();
+    let buyer_token_account = harness
+        .create_token_account(&escrow.token_program, &escrow.mint, &buyer)
+        .await;
+    let voucher = Voucher {
+        escrow: escrow.escrow,
+        amount: 100,
+        recipient: buyer,
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+
+    let release = voucher_release(&harness, &escrow, &buyer_token_account, voucher, &escrow.receiver);
+    let result = harness.process(&release, &[]).await;
+    assert_escrow_error(result, EscrowError::EscrowNotMaturedYet);
+
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+    let release = voucher_release(&harness, &escrow, &buyer_token_account, voucher, &escrow.receiver);
+    harness.process(&release, &[]).await.unwrap();
+    assert_eq!(harness.token_balance(&buyer_token_account).await, 100);
+    assert!(harness.account(&escrow.escrow).await.is_none());
+}
+
+#[tokio::test]
+async fn voucher_from_a_stranger_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let voucher = Voucher {
+        escrow: escrow.escrow,
+        amount: 100,
+        recipient: escrow.receiver.pubkey(),
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        voucher,
+        &Keypair::new(),
+    );
+    let result = harness.process(&release, &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidVoucher);
+
+    // Without the precompile instruction there is no signature to go by.
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        voucher,
+        &escrow.initializer,
+    );
+    let result = harness.process(&release[1..], &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidVoucher);
+}
+
+#[tokio::test]
+async fn voucher_relayed_by_another_caller_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let voucher = Voucher {
+        escrow: escrow.escrow,
+        amount: 100,
+        recipient: escrow.receiver.pubkey(),
+        nonce: 1,
+        expiry: i64::MAX,
+    };
+    let relayer = Keypair::new();
+
+    // The caller gets the rent back, so nobody else can close the escrow.
+    let mut release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        voucher,
+        &escrow.initializer,
+    );
+    release[1].accounts[6].pubkey = relayer.pubkey();
+    let result = harness.process(&release, &[&relayer]).await;
+    assert_escrow_error(result, EscrowError::CallerMismatch);
+    assert_eq!(escrow.state(&mut harness).await.escrow_amount, 100);
+}
+
+#[tokio::test]
+async fn expired_voucher_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let voucher = Voucher {
+        escrow: escrow.escrow,
+        amount: 100,
+        recipient: escrow.receiver.pubkey(),
+        nonce: 1,
+        expiry: harness.clock().await.unix_timestamp - 1,
+    };
+
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        voucher,
+        &escrow.initializer,
+    );
+    let result = harness.process(&release, &[]).await;
+    assert_escrow_error(result, EscrowError::VoucherExpired);
+}
+
+#[tokio::test]
+async fn replayed_voucher_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let voucher = Voucher {
+        escrow: escrow.escrow,
+        amount: 10,
+        recipient: escrow.receiver.pubkey(),
+        nonce: 7,
+        expiry: i64::MAX,
+    };
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        voucher,
+        &escrow.initializer,
+    );
+    harness.process(&release, &[]).await.unwrap();
+
+    let replay = Voucher { amount: 11, ..voucher };
+    let release = voucher_release(
+        &harness,
+        &escrow,
+        &escrow.receiver_token_account,
+        replay,
+        &escrow.initializer,
+    );
+    let result = harness.process(&release, &[]).await;
+    assert_escrow_error(result, EscrowError::VoucherReplayed);
+}
+
+#[tokio::test]
+async fn increase_deposit_adds_to_the_escrow() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let increase = instruction::increase_deposit(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.initializer_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &escrow.mint,
+        30,
+    );
+    harness.process(&[increase], &[&escrow.initializer]).await.unwrap();
+
+    assert_eq!(escrow.state(&mut harness).await.escrow_amount, 130);
+    assert_eq!(harness.token_balance(&escrow.temp_token_account).await, 130);
+    assert_eq!(harness.token_balance(&escrow.initializer_token_account).await, 70);
+    assert_eq!(escrow.profile(&mut harness).await.total_volume, 130);
+}
+
+#[tokio::test]
+async fn amend_escrow_lowers_extends_and_reassigns() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    let new_receiver = Pubkey::new_unique();
+    let amend = |amount, expire_date, new_receiver: &Pubkey| {
+        instruction::amend_escrow(
+            &harness.program_id,
+            &escrow.initializer.pubkey(),
+            &escrow.receiver.pubkey(),
+            &escrow.initializer_token_account,
+            &escrow.temp_token_account,
+            &escrow.escrow,
+            &escrow.token_program,
+            &escrow.mint,
+            amount,
+            expire_date,
+            new_receiver,
+        )
+    };
+    let signers = [&escrow.initializer, &escrow.receiver];
+
+    let raise = amend(101, expire_date, &escrow.receiver.pubkey());
+    let shorten = amend(100, expire_date - 1, &escrow.receiver.pubkey());
+    let unchanged = amend(100, expire_date, &escrow.receiver.pubkey());
+    let amend = amend(90, expire_date + 100, &new_receiver);
+    for invalid in [raise, shorten, unchanged] {
+        let result = harness.process(&[invalid], &signers).await;
+        assert_escrow_error(result, EscrowError::InvalidAmendment);
+    }
+    harness.process(&[amend], &signers).await.unwrap();
+
+    let state = escrow.state(&mut harness).await;
+    assert_eq!(state.escrow_amount, 90);
+    assert_eq!(state.expire_date, expire_date + 100);
+    assert_eq!(state.refund_after, expire_date + 100 + ESCROW_RELEASE_WINDOW_SEC);
+    assert_eq!(state.receiver_pubkey, new_receiver);
+    assert_eq!(harness.token_balance(&escrow.temp_token_account).await, 90);
+    assert_eq!(harness.token_balance(&escrow.initializer_token_account).await, 110);
+}
+
+#[tokio::test]
+async fn assigned_receiver_gets_the_release() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let buyer = Pubkey::new_unique();
+    let buyer_token_account = harness
+        .create_token_account(&escrow.token_program, &escrow.mint, &buyer)
+        .await;
+
+    let assign = instruction::assign_receiver(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.escrow,
+        &buyer,
+    );
+    let result = harness.process(&[assign], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::ReceiverMismatch);
+
+    let assign = instruction::assign_receiver(
+        &harness.program_id,
+        &escrow.receiver.pubkey(),
+        &escrow.escrow,
+        &buyer,
+    );
+    harness.process(&[assign], &[&escrow.receiver]).await.unwrap();
+
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+    let mut release = escrow.release(&harness);
+    release.accounts[0].pubkey = buyer_token_account;
+    harness.process(&[release], &[]).await.unwrap();
+    assert_eq!(harness.token_balance(&buyer_token_account).await, 100);
+}
@@ -0,0 +1,399 @@
+//! Account validation errors shared by every instruction. Errors tied to one
+//! instruction are covered next to its other tests.
+
+mod common;
+
+use common::{assert_escrow_error, Harness, SolEscrow, TokenEscrow, WalletEscrow};
+use solana_escrow::{
+    instruction,
+    states::{
+        default_escrow::EscrowState, deposit_record::DepositRecordState,
+        sol_escrow::SolEscrowState,
+    },
+    utils::errors::EscrowError,
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    system_program, sysvar,
+};
+use spl_token_2022::extension::ExtensionType;
+
+#[tokio::test]
+async fn invalid_instruction() {
+    let mut harness = Harness::start().await;
+    let unknown = Instruction {
+        program_id: harness.program_id,
+        accounts: vec![],
+        data: vec![u8::MAX],
+    };
+    let result = harness.process(&[unknown], &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidInstruction);
+}
+
+#[tokio::test]
+async fn not_rent_exempt() {
+    let mut harness = Harness::start().await;
+    let sol_escrow = SolEscrow::prepare(&mut harness, 1_000_000_000).await;
+    let underfunded = Pubkey::new_unique();
+    harness.set_account(
+        &underfunded,
+        Account {
+            lamports: Rent::default().minimum_balance(SolEscrowState::LEN) - 1,
+            data: vec![0; SolEscrowState::LEN],
+            owner: harness.program_id,
+            executable: false,
+            rent_epoch: 0,
+        },
+    );
+
+    let init = instruction::init_sol_escrow(
+        &harness.program_id,
+        &sol_escrow.initializer.pubkey(),
+        &sol_escrow.receiver,
+        &underfunded,
+        &harness.caller(),
+        sol_escrow.amount,
+    );
+    let result = harness.process(&[init], &[&sol_escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::NotRentExempt);
+}
+
+#[tokio::test]
+async fn expected_amount_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let caller = harness.caller();
+    let temp = harness
+        .create_token_account(&escrow.token_program, &escrow.mint, &caller)
+        .await;
+    harness.mint_to(&escrow.token_program, &escrow.mint, &temp, 99).await;
+    let next_escrow = escrow.next_escrow(&mut harness).await;
+
+    let mut init = escrow.init(&harness, 100);
+    init.accounts[2].pubkey = temp;
+    init.accounts[3].pubkey = next_escrow;
+    let result = harness.process(&[init], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::ExpectedAmountMismatch);
+}
+
+#[tokio::test]
+async fn amount_overflow() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let mut state = escrow.state(&mut harness).await;
+    state.escrow_amount = u64::MAX;
+    harness.set_state(&escrow.escrow, state).await;
+
+    let increase = instruction::increase_deposit(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.initializer_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &escrow.mint,
+        1,
+    );
+    let result = harness.process(&[increase], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::AmountOverflow);
+}
+
+#[tokio::test]
+async fn invalid_token_program() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[4].pubkey = system_program::id();
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidTokenProgram);
+}
+
+#[tokio::test]
+async fn unsupported_mint_extension() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token_2022::id(), 100).await;
+    let non_transferable = harness
+        .create_mint_with_extensions(&spl_token_2022::id(), &[ExtensionType::NonTransferable])
+        .await;
+    let next_escrow = escrow.next_escrow(&mut harness).await;
+
+    let mut init = escrow.init(&harness, 100);
+    init.accounts[3].pubkey = next_escrow;
+    init.accounts[7].pubkey = non_transferable;
+    let result = harness.process(&[init], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::UnsupportedMintExtension);
+}
+
+#[tokio::test]
+async fn invalid_system_program() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+
+    let mut deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 10);
+    let system_program = deposit.accounts.len() - 1;
+    deposit.accounts[system_program].pubkey = spl_token::id();
+    let result = harness.process(&[deposit], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::InvalidSystemProgram);
+}
+
+#[tokio::test]
+async fn invalid_sysvar() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let mut init = escrow.init(&harness, 100);
+    init.accounts[4].pubkey = sysvar::clock::id();
+    let result = harness.process(&[init], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::InvalidSysvar);
+}
+
+#[tokio::test]
+async fn invalid_pda() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[5].pubkey = Pubkey::new_unique();
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidPda);
+}
+
+#[tokio::test]
+async fn account_not_signer() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let mut assign = instruction::assign_receiver(
+        &harness.program_id,
+        &escrow.receiver.pubkey(),
+        &escrow.escrow,
+        &Pubkey::new_unique(),
+    );
+    assign.accounts[0].is_signer = false;
+    let result = harness.process(&[assign], &[]).await;
+    assert_escrow_error(result, EscrowError::AccountNotSigner);
+}
+
+#[tokio::test]
+async fn account_not_writable() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[3].is_writable = false;
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::AccountNotWritable);
+}
+
+#[tokio::test]
+async fn invalid_account_owner() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[3].pubkey = escrow.temp_token_account;
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::InvalidAccountOwner);
+}
+
+#[tokio::test]
+async fn invalid_account_discriminator() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+
+    let assign = instruction::assign_receiver(
+        &harness.program_id,
+        &wallet.owner.pubkey(),
+        &wallet.registry,
+        &Pubkey::new_unique(),
+    );
+    let result = harness.process(&[assign], &[&wallet.owner]).await;
+    assert_escrow_error(result, EscrowError::InvalidAccountDiscriminator);
+}
+
+#[tokio::test]
+async fn unsupported_account_version() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let mut account = harness.account(&escrow.escrow).await.unwrap();
+    account.data[1] = u8::MAX;
+    harness.set_account(&escrow.escrow, account);
+
+    let release = escrow.release(&harness);
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::UnsupportedAccountVersion);
+}
+
+#[tokio::test]
+async fn account_too_small() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let mut account = harness.account(&escrow.escrow).await.unwrap();
+    account.data.truncate(EscrowState::LEN - 8);
+    harness.set_account(&escrow.escrow, account);
+
+    let release = escrow.release(&harness);
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::AccountTooSmall);
+
+    let increase = instruction::increase_deposit(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.initializer_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &escrow.mint,
+        1,
+    );
+    let result = harness.process(&[increase], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::AccountTooSmall);
+}
+
+/// A token escrow past its maturity, inside its release window.
+async fn matured_escrow(harness: &mut Harness) -> TokenEscrow {
+    let escrow = TokenEscrow::create(harness, &spl_token::id(), 100).await;
+    let expire_date = escrow.state(harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+    escrow
+}
+
+#[tokio::test]
+async fn initializer_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = matured_escrow(&mut harness).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[2].pubkey = Pubkey::new_unique();
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::InitializerMismatch);
+}
+
+#[tokio::test]
+async fn receiver_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let stranger = Keypair::new();
+
+    let assign = instruction::assign_receiver(
+        &harness.program_id,
+        &stranger.pubkey(),
+        &escrow.escrow,
+        &stranger.pubkey(),
+    );
+    let result = harness.process(&[assign], &[&stranger]).await;
+    assert_escrow_error(result, EscrowError::ReceiverMismatch);
+}
+
+#[tokio::test]
+async fn caller_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = matured_escrow(&mut harness).await;
+    let relayer = Keypair::new();
+
+    let mut release = escrow.release(&harness);
+    release.accounts[6].pubkey = relayer.pubkey();
+    let result = harness.process(&[release], &[&relayer]).await;
+    assert_escrow_error(result, EscrowError::CallerMismatch);
+}
+
+#[tokio::test]
+async fn temp_token_account_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = matured_escrow(&mut harness).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[1].pubkey = escrow.initializer_token_account;
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::TempTokenAccountMismatch);
+}
+
+#[tokio::test]
+async fn vault_mismatch() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+
+    let mut deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 10);
+    deposit.accounts[2].pubkey = wallet.owner_token_account;
+    let result = harness.process(&[deposit], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::VaultMismatch);
+}
+
+#[tokio::test]
+async fn token_owner_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = matured_escrow(&mut harness).await;
+
+    let mut release = escrow.release(&harness);
+    release.accounts[0].pubkey = escrow.initializer_token_account;
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::TokenOwnerMismatch);
+}
+
+#[tokio::test]
+async fn mint_mismatch() {
+    let mut harness = Harness::start().await;
+    let escrow = TokenEscrow::create(&mut harness, &spl_token::id(), 100).await;
+    let other_mint = harness.create_mint(&escrow.token_program).await;
+
+    let increase = instruction::increase_deposit(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.initializer_token_account,
+        &escrow.temp_token_account,
+        &escrow.escrow,
+        &escrow.token_program,
+        &other_mint,
+        1,
+    );
+    let result = harness.process(&[increase], &[&escrow.initializer]).await;
+    assert_escrow_error(result, EscrowError::MintMismatch);
+}
+
+#[tokio::test]
+async fn registry_owner_mismatch() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let stranger = Keypair::new();
+    let token_program = spl_token_2022::id();
+    let mint = harness.create_mint(&token_program).await;
+    let vault = instruction::find_registry_vault(&harness.program_id, &wallet.registry, &mint).0;
+
+    let add = instruction::add_mint(
+        &harness.program_id,
+        &stranger.pubkey(),
+        &wallet.registry,
+        &vault,
+        &token_program,
+        &mint,
+    );
+    let result = harness.process(&[add], &[&stranger]).await;
+    assert_escrow_error(result, EscrowError::RegistryOwnerMismatch);
+}
+
+#[tokio::test]
+async fn deposit_record_mismatch() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 10);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+    let record_address = wallet.record(&harness, &depositor.pubkey());
+    let mut record: DepositRecordState = harness.state(&record_address).await;
+    record.depositor_pubkey = Pubkey::new_unique();
+    harness.set_state(&record_address, record).await;
+
+    let withdraw = wallet.withdraw(&harness, &depositor.pubkey(), &token_account, 10);
+    let result = harness.process(&[withdraw], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::DepositRecordMismatch);
+}
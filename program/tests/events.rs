@@ -0,0 +1,378 @@
+mod common;
+
+use std::sync::Once;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::{Harness, TokenEscrow, WalletEscrow};
+use solana_escrow::{
+    events::{
+        decode_event, decode_logs, DepositCollected, DepositIncreased, EscrowCreated, EscrowEvent,
+        EscrowRefunded, EscrowReleased, EventDecodeError, EVENT_MAGIC, EVENT_SCHEMA_VERSION,
+    },
+    utils::time_basis::TimeBasis,
+};
+use solana_program_test::tokio;
+use solana_sdk::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_stubs::{self, SyscallStubs},
+    pubkey::Pubkey,
+    signer::Signer,
+};
+
+/// Syscalls of `ProgramTest`, except that `sol_log_data` goes to the
+/// transaction log as the runtime writes it instead of to stdout.
+struct LogDataStubs {
+    inner: Box<dyn SyscallStubs>,
+}
+
+/// Stands in while [`LogDataStubs`] takes over the stubs it wraps.
+struct NoStubs;
+
+impl SyscallStubs for NoStubs {}
+
+impl SyscallStubs for LogDataStubs {
+    fn sol_log(&self, message: &str) {
+        self.inner.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.inner.sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.inner.sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.inner.sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.inner.sol_get_last_restart_slot(var_addr)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.inner.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.inner.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        // `sol_log` lines get a `Program log: ` prefix, dropped by [`captured`].
+        self.inner.sol_log(&data_line(fields))
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.inner.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.inner.sol_get_stack_height()
+    }
+}
+
+/// Starts a harness whose transaction logs carry the emitted events.
+async fn start() -> Harness {
+    static LOG_DATA: Once = Once::new();
+    let harness = Harness::start().await;
+    LOG_DATA.call_once(|| {
+        let inner = program_stubs::set_syscall_stubs(Box::new(NoStubs));
+        program_stubs::set_syscall_stubs(Box::new(LogDataStubs { inner }));
+    });
+    harness
+}
+
+/// The log of a transaction, with event data lines as the runtime writes them.
+fn captured(logs: Vec<String>) -> Vec<String> {
+    logs.into_iter()
+        .map(|log| match log.strip_prefix("Program log: Program data: ") {
+            Some(data) => format!("Program data: {data}"),
+            None => log,
+        })
+        .collect()
+}
+
+/// The `Program data:` line `sol_log_data` writes for `fields`.
+fn data_line(fields: &[&[u8]]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| STANDARD.encode(field)).collect();
+    format!("Program data: {}", fields.join(" "))
+}
+
+#[test]
+fn version_1_payloads_decode_with_the_new_fields_at_their_defaults() {
+    let (escrow, caller, initializer, receiver, mint) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+
+    // `EscrowCreated` before the time basis, release window and memo.
+    let mut created = vec![0];
+    for key in [escrow, caller, initializer, receiver] {
+        created.extend_from_slice(key.as_ref());
+    }
+    created.push(1);
+    created.extend_from_slice(mint.as_ref());
+    created.extend_from_slice(&100u64.to_le_bytes());
+    created.extend_from_slice(&1_700_000_000i64.to_le_bytes());
+    assert_eq!(
+        decode_event(&[EVENT_MAGIC.to_vec(), vec![1], created]).unwrap(),
+        EscrowEvent::EscrowCreated(EscrowCreated {
+            escrow,
+            caller,
+            initializer,
+            receiver,
+            mint: Some(mint),
+            amount: 100,
+            expire_date: 1_700_000_000,
+            time_basis: TimeBasis::UnixTimestamp,
+            refund_after: 0,
+            reference: None,
+            memo: String::new(),
+        })
+    );
+
+    let mut released = vec![1];
+    released.extend_from_slice(escrow.as_ref());
+    released.extend_from_slice(receiver.as_ref());
+    released.extend_from_slice(&100u64.to_le_bytes());
+    released.extend_from_slice(&3u64.to_le_bytes());
+    assert_eq!(
+        decode_event(&[EVENT_MAGIC.to_vec(), vec![1], released]).unwrap(),
+        EscrowEvent::EscrowReleased(EscrowReleased {
+            escrow,
+            receiver,
+            amount: 100,
+            fee: 3,
+            reference: None,
+            memo: String::new(),
+        })
+    );
+
+    let mut refunded = vec![2];
+    refunded.extend_from_slice(escrow.as_ref());
+    refunded.extend_from_slice(initializer.as_ref());
+    refunded.extend_from_slice(&100u64.to_le_bytes());
+    let refunded_line = data_line(&[EVENT_MAGIC, &[1], &refunded]);
+    assert_eq!(
+        decode_event(&[EVENT_MAGIC.to_vec(), vec![1], refunded]).unwrap(),
+        EscrowEvent::EscrowRefunded(EscrowRefunded {
+            escrow,
+            initializer,
+            amount: 100,
+            fee: 0,
+            reference: None,
+            memo: String::new(),
+        })
+    );
+
+    // Old and current events in the same log both decode.
+    let increased = EscrowEvent::DepositIncreased(DepositIncreased {
+        escrow,
+        amount: 10,
+        escrow_amount: 110,
+    });
+    let program_id = Pubkey::new_unique();
+    let logs = [
+        format!("Program {program_id} invoke [1]"),
+        refunded_line,
+        data_line(&[EVENT_MAGIC, &[EVENT_SCHEMA_VERSION], &borsh::to_vec(&increased).unwrap()]),
+        format!("Program {program_id} success"),
+    ];
+    let events = decode_logs(&program_id, &logs).unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[1], increased);
+}
+
+#[test]
+fn version_2_payloads_decode_with_the_new_fields_at_their_defaults() {
+    let (registry, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    // `DepositCollected` before it named the mint and depositor.
+    let mut collected = vec![3];
+    collected.extend_from_slice(registry.as_ref());
+    collected.extend_from_slice(owner.as_ref());
+    collected.extend_from_slice(&60u64.to_le_bytes());
+    let expected = EscrowEvent::DepositCollected(DepositCollected {
+        escrow: registry,
+        receiver: owner,
+        amount: 60,
+        mint: Pubkey::default(),
+        depositor: Pubkey::default(),
+    });
+    assert_eq!(decode_event(&[EVENT_MAGIC.to_vec(), vec![2], collected.clone()]).unwrap(), expected);
+    // Version 1 had the same `DepositCollected`.
+    assert_eq!(decode_event(&[EVENT_MAGIC.to_vec(), vec![1], collected]).unwrap(), expected);
+
+    let released = EscrowEvent::EscrowReleased(EscrowReleased {
+        escrow: registry,
+        receiver: owner,
+        amount: 100,
+        fee: 3,
+        reference: Some([7; 32]),
+        memo: "order 7".to_string(),
+    });
+    let payload = borsh::to_vec(&released).unwrap();
+    assert_eq!(decode_event(&[EVENT_MAGIC.to_vec(), vec![2], payload]).unwrap(), released);
+}
+
+#[tokio::test]
+async fn collected_deposits_name_their_mint_and_depositor() {
+    let mut harness = start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+
+    let collect = wallet.collect(&harness, &depositor.pubkey(), true);
+    let logs = captured(harness.process_logged(&[collect], &[&wallet.owner, &depositor]).await);
+    assert_eq!(
+        decode_logs(&harness.program_id, &logs).unwrap(),
+        [EscrowEvent::DepositCollected(DepositCollected {
+            escrow: wallet.registry,
+            receiver: wallet.owner.pubkey(),
+            amount: 60,
+            mint: wallet.mint,
+            depositor: depositor.pubkey(),
+        })]
+    );
+}
+
+/// Logs of a token escrow's init and release, and the escrow.
+async fn escrow_logs(harness: &mut Harness) -> (TokenEscrow, Vec<String>, Vec<String>) {
+    let mint = harness.create_mint(&spl_token::id()).await;
+    let escrow = TokenEscrow::prepare(harness, &spl_token::id(), &mint, 100).await;
+    let init = escrow.init(harness, 100);
+    let init_logs = captured(harness.process_logged(&[init], &[&escrow.initializer]).await);
+    let expire_date = escrow.state(harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+    let release = escrow.release(harness);
+    let release_logs = captured(harness.process_logged(&[release], &[]).await);
+    (escrow, init_logs, release_logs)
+}
+
+#[tokio::test]
+async fn decodes_the_events_of_a_transaction() {
+    let mut harness = start().await;
+    let (escrow, init_logs, release_logs) = escrow_logs(&mut harness).await;
+    let program_id = harness.program_id;
+
+    // The token program runs at depth 2 while the escrow is created.
+    assert!(init_logs.iter().any(|log| log.ends_with("invoke [2]")));
+    let [EscrowEvent::EscrowCreated(created)] = &decode_logs(&program_id, &init_logs).unwrap()[..]
+    else {
+        panic!("one EscrowCreated event expected")
+    };
+    assert_eq!(created.escrow, escrow.escrow);
+    assert_eq!(created.caller, harness.caller());
+    assert_eq!(created.initializer, escrow.initializer.pubkey());
+    assert_eq!(created.receiver, escrow.receiver.pubkey());
+    assert_eq!(created.mint, Some(escrow.mint));
+    assert_eq!(created.amount, 100);
+
+    assert_eq!(
+        decode_logs(&program_id, &release_logs).unwrap(),
+        [EscrowEvent::EscrowReleased(EscrowReleased {
+            escrow: escrow.escrow,
+            receiver: escrow.receiver.pubkey(),
+            amount: 100,
+            fee: 0,
+            reference: None,
+            memo: String::new(),
+        })]
+    );
+
+    // Events of the escrow program only.
+    assert!(decode_logs(&Pubkey::new_unique(), &init_logs).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn skips_data_logged_by_other_programs() {
+    let mut harness = start().await;
+    let (_, init_logs, _) = escrow_logs(&mut harness).await;
+    let program_id = harness.program_id;
+    let event = init_logs
+        .iter()
+        .find(|log| log.starts_with("Program data: "))
+        .unwrap()
+        .clone();
+
+    // Escrow event data logged from within the token program's CPI, and by
+    // another top level program, interleaved with the escrow program's logs.
+    let mut logs = Vec::new();
+    let other = Pubkey::new_unique();
+    logs.push(format!("Program {other} invoke [1]"));
+    logs.push(event.clone());
+    logs.push(format!("Program {other} success"));
+    for log in &init_logs {
+        logs.push(log.clone());
+        if log.ends_with("invoke [2]") {
+            logs.push(event.clone());
+            logs.push(data_line(&[b"another", &[1], &[2, 3]]));
+        }
+    }
+    logs.push(format!("Program {other} invoke [1]"));
+    logs.push(event);
+    logs.push(format!("Program {other} failed: custom program error: 0x0"));
+
+    assert_eq!(decode_logs(&program_id, &logs).unwrap(), decode_logs(&program_id, &init_logs).unwrap());
+    assert_eq!(decode_logs(&other, &logs).unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn rejects_other_schema_versions_and_malformed_payloads() {
+    let mut harness = start().await;
+    let (_, init_logs, _) = escrow_logs(&mut harness).await;
+    let program_id = harness.program_id;
+    let index = init_logs.iter().position(|log| log.starts_with("Program data: ")).unwrap();
+    let fields: Vec<Vec<u8>> = init_logs[index]["Program data: ".len()..]
+        .split(' ')
+        .map(|field| STANDARD.decode(field).unwrap())
+        .collect();
+    assert_eq!(fields[1], [EVENT_SCHEMA_VERSION]);
+    let with = |line: String| {
+        let mut logs = init_logs.clone();
+        logs[index] = line;
+        logs
+    };
+
+    let logs = with(data_line(&[EVENT_MAGIC, &[EVENT_SCHEMA_VERSION + 1], &fields[2]]));
+    assert_eq!(
+        decode_logs(&program_id, &logs),
+        Err(EventDecodeError::UnsupportedVersion(EVENT_SCHEMA_VERSION + 1))
+    );
+    assert_eq!(
+        decode_event(&[fields[0].clone(), vec![0], fields[2].clone()]),
+        Err(EventDecodeError::UnsupportedVersion(0))
+    );
+
+    let truncated = &fields[2][..fields[2].len() - 1];
+    let logs = with(data_line(&[EVENT_MAGIC, &[EVENT_SCHEMA_VERSION], truncated]));
+    assert_eq!(decode_logs(&program_id, &logs), Err(EventDecodeError::Malformed));
+    let logs = with(data_line(&[EVENT_MAGIC, &[EVENT_SCHEMA_VERSION, 0], &fields[2]]));
+    assert_eq!(decode_logs(&program_id, &logs), Err(EventDecodeError::Malformed));
+    let logs = with("Program data: not*base64".to_string());
+    assert_eq!(decode_logs(&program_id, &logs), Err(EventDecodeError::Malformed));
+
+    // Data that isn't an escrow event is skipped, as is a wrong field count.
+    let logs = with(data_line(&[b"escrowex", &[EVENT_SCHEMA_VERSION], &fields[2]]));
+    assert!(decode_logs(&program_id, &logs).unwrap().is_empty());
+    assert_eq!(decode_event(&fields[..2]), Err(EventDecodeError::NotAnEvent));
+}
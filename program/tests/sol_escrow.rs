@@ -0,0 +1,179 @@
+mod common;
+
+use common::{assert_escrow_error, Harness, SolEscrow};
+use solana_escrow::{
+    instruction,
+    utils::{constants::ESCROW_RELEASE_WINDOW_SEC, errors::EscrowError},
+};
+use solana_program_test::tokio;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
+
+const AMOUNT: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn init_sol_escrow_moves_the_lamports_to_the_vault() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+
+    let state = escrow.state(&mut harness).await;
+    assert!(state.is_initialized);
+    assert_eq!(state.caller_pubkey, harness.caller());
+    assert_eq!(state.initializer_pubkey, escrow.initializer.pubkey());
+    assert_eq!(state.receiver_pubkey, escrow.receiver);
+    assert_eq!(state.vault_pubkey, escrow.vault);
+    assert_eq!(state.escrow_amount, AMOUNT);
+    assert_eq!(state.refund_after, state.expire_date + ESCROW_RELEASE_WINDOW_SEC);
+    let vault = harness.account(&escrow.vault).await.unwrap();
+    assert_eq!(vault.owner, harness.program_id);
+    assert!(vault.lamports >= AMOUNT);
+}
+
+#[tokio::test]
+async fn release_sol_escrow_pays_the_receiver_and_closes_the_escrow() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let release = instruction::release_sol_escrow(
+        &harness.program_id,
+        &escrow.receiver,
+        &escrow.escrow,
+        &harness.caller(),
+    );
+
+    let result = harness.process(std::slice::from_ref(&release), &[]).await;
+    assert_escrow_error(result, EscrowError::EscrowNotMaturedYet);
+
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+    harness.process(&[release], &[]).await.unwrap();
+
+    assert_eq!(harness.lamports(&escrow.receiver).await, AMOUNT);
+    assert!(harness.account(&escrow.escrow).await.is_none());
+    assert!(harness.account(&escrow.vault).await.is_none());
+}
+
+#[tokio::test]
+async fn release_after_the_window_closes_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let refund_after = escrow.state(&mut harness).await.refund_after;
+
+    harness.warp_past(refund_after).await;
+    let release = instruction::release_sol_escrow(
+        &harness.program_id,
+        &escrow.receiver,
+        &escrow.escrow,
+        &harness.caller(),
+    );
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::ReleaseWindowClosed);
+}
+
+#[tokio::test]
+async fn refund_sol_escrow_returns_the_lamports_before_maturity() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let initializer = escrow.initializer.pubkey();
+    let before = harness.lamports(&initializer).await;
+
+    let refund = instruction::refund_sol_escrow(
+        &harness.program_id,
+        &initializer,
+        &escrow.escrow,
+        &harness.caller(),
+        true,
+    );
+    harness.process(&[refund], &[]).await.unwrap();
+
+    assert_eq!(harness.lamports(&initializer).await, before + AMOUNT);
+    assert!(harness.account(&escrow.escrow).await.is_none());
+    assert!(harness.account(&escrow.vault).await.is_none());
+}
+
+/// Records `caller` on `escrow`, so the fee payer of the tests is not it.
+async fn set_caller(harness: &mut Harness, escrow: &SolEscrow, caller: &Pubkey) {
+    let mut state = escrow.state(harness).await;
+    state.caller_pubkey = *caller;
+    harness.set_state(&escrow.escrow, state).await;
+}
+
+#[tokio::test]
+async fn refund_before_maturity_needs_the_caller_signature() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let caller = Pubkey::new_unique();
+    set_caller(&mut harness, &escrow, &caller).await;
+
+    let refund = instruction::refund_sol_escrow(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.escrow,
+        &caller,
+        false,
+    );
+    let result = harness.process(&[refund], &[]).await;
+    assert_escrow_error(result, EscrowError::AccountNotSigner);
+}
+
+#[tokio::test]
+async fn refund_before_the_window_closes_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+
+    let refund = instruction::refund_sol_escrow(
+        &harness.program_id,
+        &escrow.initializer.pubkey(),
+        &escrow.escrow,
+        &harness.caller(),
+        true,
+    );
+    let result = harness.process(&[refund], &[]).await;
+    assert_escrow_error(result, EscrowError::RefundNotDue);
+}
+
+#[tokio::test]
+async fn anyone_can_refund_once_the_window_closes() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let caller = Pubkey::new_unique();
+    set_caller(&mut harness, &escrow, &caller).await;
+    let initializer = escrow.initializer.pubkey();
+    let before = harness.lamports(&initializer).await;
+    let escrow_rent = harness.lamports(&escrow.escrow).await;
+    let vault_rent = harness.lamports(&escrow.vault).await - AMOUNT;
+
+    let refund_after = escrow.state(&mut harness).await.refund_after;
+    harness.warp_past(refund_after).await;
+    let refund = instruction::refund_sol_escrow(
+        &harness.program_id,
+        &initializer,
+        &escrow.escrow,
+        &caller,
+        false,
+    );
+    assert!(refund.accounts.iter().all(|account| !account.is_signer));
+    harness.process(&[refund], &[]).await.unwrap();
+
+    assert_eq!(harness.lamports(&initializer).await, before + AMOUNT);
+    assert_eq!(harness.lamports(&caller).await, escrow_rent + vault_rent);
+    assert!(harness.account(&escrow.escrow).await.is_none());
+    assert!(harness.account(&escrow.vault).await.is_none());
+}
+
+#[tokio::test]
+async fn release_to_another_receiver_fails() {
+    let mut harness = Harness::start().await;
+    let escrow = SolEscrow::create(&mut harness, AMOUNT).await;
+    let expire_date = escrow.state(&mut harness).await.expire_date;
+    harness.warp_past(expire_date).await;
+
+    let release = instruction::release_sol_escrow(
+        &harness.program_id,
+        &Pubkey::new_unique(),
+        &escrow.escrow,
+        &harness.caller(),
+    );
+    let result = harness.process(&[release], &[]).await;
+    assert_escrow_error(result, EscrowError::ReceiverMismatch);
+}
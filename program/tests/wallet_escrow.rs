@@ -0,0 +1,220 @@
+mod common;
+
+use common::{assert_escrow_error, Harness, WalletEscrow};
+use solana_escrow::{
+    instruction,
+    states::{
+        deposit_record::DepositRecordState, escrow_registry::EscrowRegistryState,
+        registry_mint::RegistryMintState,
+    },
+    utils::errors::EscrowError,
+};
+use solana_program_test::tokio;
+use solana_sdk::signer::Signer;
+
+#[tokio::test]
+async fn register_escrow_adopts_the_token_account_as_vault() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+
+    let registry: EscrowRegistryState = harness.state(&wallet.registry).await;
+    assert_eq!(registry.initializer_pubkey, wallet.owner.pubkey());
+    assert_eq!(registry.token_account_pubkey, wallet.vault);
+    assert_eq!(registry.mint_count, 1);
+    let registry_mint: RegistryMintState = harness.state(&wallet.registry_mint(&harness)).await;
+    assert_eq!(registry_mint.vault_pubkey, wallet.vault);
+    assert_eq!(registry_mint.total_deposits, 0);
+    let vault = harness.token_account(&wallet.vault).await;
+    assert_eq!(vault.owner, instruction::find_wallet_escrow_authority(&harness.program_id).0);
+}
+
+#[tokio::test]
+async fn deposit_and_withdraw_update_the_record() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+    let withdraw = wallet.withdraw(&harness, &depositor.pubkey(), &token_account, 10);
+    harness.process(&[withdraw], &[&depositor]).await.unwrap();
+
+    let record: DepositRecordState = harness.state(&wallet.record(&harness, &depositor.pubkey())).await;
+    assert_eq!(record.amount, 50);
+    assert_eq!(record.depositor_pubkey, depositor.pubkey());
+    let registry_mint: RegistryMintState = harness.state(&wallet.registry_mint(&harness)).await;
+    assert_eq!(registry_mint.total_deposits, 50);
+    assert_eq!(harness.token_balance(&wallet.vault).await, 50);
+    assert_eq!(harness.token_balance(&token_account).await, 50);
+}
+
+#[tokio::test]
+async fn withdraw_more_than_deposited_fails() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+
+    let withdraw = wallet.withdraw(&harness, &depositor.pubkey(), &token_account, 61);
+    let result = harness.process(&[withdraw], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn withdraw_after_maturity_fails() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+    let record: DepositRecordState = harness.state(&wallet.record(&harness, &depositor.pubkey())).await;
+    harness.warp_past(record.expire_date).await;
+
+    let withdraw = wallet.withdraw(&harness, &depositor.pubkey(), &token_account, 10);
+    let result = harness.process(&[withdraw], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::EscrowAlreadyMatured);
+}
+
+#[tokio::test]
+async fn deposit_into_a_matured_record_fails_until_collected() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+    let record_address = wallet.record(&harness, &depositor.pubkey());
+    let record: DepositRecordState = harness.state(&record_address).await;
+    harness.warp_past(record.expire_date).await;
+
+    // A top-up must not restart the wait of the matured balance.
+    let top_up = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 10);
+    let result = harness.process(std::slice::from_ref(&top_up), &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::EscrowAlreadyMatured);
+    let withdraw = wallet.withdraw(&harness, &depositor.pubkey(), &token_account, 60);
+    let result = harness.process(&[withdraw], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::EscrowAlreadyMatured);
+    assert_eq!(harness.state::<DepositRecordState>(&record_address).await, record);
+
+    let collect = wallet.collect(&harness, &depositor.pubkey(), false);
+    harness.process(&[collect], &[&wallet.owner]).await.unwrap();
+    harness.next_blockhash().await;
+    harness.process(&[top_up], &[&depositor]).await.unwrap();
+    let record: DepositRecordState = harness.state(&record_address).await;
+    assert_eq!(record.amount, 10);
+    assert_eq!(harness.token_balance(&wallet.owner_token_account).await, 60);
+}
+
+#[tokio::test]
+async fn collect_deposit_pays_the_owner_after_maturity() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+
+    let collect = wallet.collect(&harness, &depositor.pubkey(), false);
+    let result = harness.process(std::slice::from_ref(&collect), &[&wallet.owner]).await;
+    assert_escrow_error(result, EscrowError::EscrowNotMaturedYet);
+
+    let record_address = wallet.record(&harness, &depositor.pubkey());
+    let record: DepositRecordState = harness.state(&record_address).await;
+    harness.warp_past(record.expire_date).await;
+    harness.process(std::slice::from_ref(&collect), &[&wallet.owner]).await.unwrap();
+
+    assert_eq!(harness.token_balance(&wallet.owner_token_account).await, 60);
+    assert_eq!(harness.token_balance(&wallet.vault).await, 0);
+    let record: DepositRecordState = harness.state(&record_address).await;
+    assert_eq!(record.amount, 0);
+
+    harness.warp_past(record.expire_date).await;
+    let result = harness.process(&[collect], &[&wallet.owner]).await;
+    assert_escrow_error(result, EscrowError::InsufficientFunds);
+}
+
+#[tokio::test]
+async fn collect_deposit_early_with_the_depositor_signature() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+
+    let collect = wallet.collect(&harness, &depositor.pubkey(), true);
+    harness.process(&[collect], &[&wallet.owner, &depositor]).await.unwrap();
+    assert_eq!(harness.token_balance(&wallet.owner_token_account).await, 60);
+}
+
+#[tokio::test]
+async fn add_mint_creates_a_vault_pda_and_remove_mint_closes_it() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let token_program = spl_token_2022::id();
+    let mint = harness.create_mint(&token_program).await;
+    let program_id = harness.program_id;
+    let vault = instruction::find_registry_vault(&program_id, &wallet.registry, &mint).0;
+    let registry_mint = instruction::find_registry_mint(&program_id, &wallet.registry, &mint).0;
+    let owner = wallet.owner.pubkey();
+    let receiver_token_account = harness.create_token_account(&token_program, &mint, &owner).await;
+
+    let add = instruction::add_mint(&program_id, &owner, &wallet.registry, &vault, &token_program, &mint);
+    harness.process(&[add], &[&wallet.owner]).await.unwrap();
+    let registry: EscrowRegistryState = harness.state(&wallet.registry).await;
+    assert_eq!(registry.mint_count, 2);
+    assert_eq!(harness.token_account(&vault).await.mint, mint);
+
+    // Tokens sent to the vault outside of a deposit are swept on removal.
+    harness.mint_to(&token_program, &mint, &vault, 5).await;
+    let remove = instruction::remove_mint(
+        &program_id,
+        &owner,
+        &receiver_token_account,
+        &wallet.registry,
+        &vault,
+        &token_program,
+        &mint,
+    );
+    harness.process(&[remove], &[&wallet.owner]).await.unwrap();
+
+    assert!(harness.account(&vault).await.is_none());
+    assert!(harness.account(&registry_mint).await.is_none());
+    assert_eq!(harness.token_balance(&receiver_token_account).await, 5);
+    let registry: EscrowRegistryState = harness.state(&wallet.registry).await;
+    assert_eq!(registry.mint_count, 1);
+}
+
+#[tokio::test]
+async fn remove_mint_with_deposits_fails() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    harness.process(&[deposit], &[&depositor]).await.unwrap();
+
+    let remove = instruction::remove_mint(
+        &harness.program_id,
+        &wallet.owner.pubkey(),
+        &wallet.owner_token_account,
+        &wallet.registry,
+        &wallet.vault,
+        &wallet.token_program,
+        &wallet.mint,
+    );
+    let result = harness.process(&[remove], &[&wallet.owner]).await;
+    assert_escrow_error(result, EscrowError::OutstandingDeposits);
+}
+
+#[tokio::test]
+async fn deposit_into_an_underfunded_vault_fails() {
+    let mut harness = Harness::start().await;
+    let wallet = WalletEscrow::create(&mut harness, &spl_token::id()).await;
+    let (depositor, token_account) = wallet.depositor(&mut harness, 100).await;
+    let registry_mint_address = wallet.registry_mint(&harness);
+    let mut registry_mint: RegistryMintState = harness.state(&registry_mint_address).await;
+    registry_mint.total_deposits = 1_000;
+    harness.set_state(&registry_mint_address, registry_mint).await;
+
+    let deposit = wallet.deposit(&harness, &depositor.pubkey(), &token_account, 60);
+    let result = harness.process(&[deposit], &[&depositor]).await;
+    assert_escrow_error(result, EscrowError::LedgerMismatch);
+}